@@ -0,0 +1,62 @@
+use crate::item_id::ItemId;
+use crate::item_id::ItemIdTables;
+use crate::HasParserState;
+use debug::DebugWith;
+use intern::Has;
+use intern::Untern;
+use parser::StringId;
+
+indices::index_type! {
+    pub struct DefId { .. }
+}
+
+/// Identifies anything that can be named -- an item, a field of a
+/// struct or variant, a variant of an enum, a generic type parameter,
+/// or one of the built-in types.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DefIdData {
+    /// A built-in definition, identified by its name (e.g., `String`).
+    Builtin(StringId),
+
+    /// A top-level item.
+    Item(ItemId),
+
+    /// A field declared on the struct or variant `owner`.
+    Field { owner: DefId, name: StringId },
+
+    /// A variant declared on the enum `owner`.
+    Variant { owner: DefId, name: StringId },
+
+    /// A generic type parameter declared on the item `owner`.
+    TypeParameter { owner: DefId, name: StringId },
+}
+
+impl<Cx> DebugWith<Cx> for DefId
+where
+    Cx: Has<ItemIdTables> + HasParserState,
+{
+    fn fmt_with(&self, cx: &Cx, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.untern(cx) {
+            DefIdData::Builtin(name) => fmt
+                .debug_tuple("Builtin")
+                .field(&name.debug_with(cx))
+                .finish(),
+            DefIdData::Item(item_id) => item_id.fmt_with(cx, fmt),
+            DefIdData::Field { owner, name } => fmt
+                .debug_struct("Field")
+                .field("owner", &owner.debug_with(cx))
+                .field("name", &name.debug_with(cx))
+                .finish(),
+            DefIdData::Variant { owner, name } => fmt
+                .debug_struct("Variant")
+                .field("owner", &owner.debug_with(cx))
+                .field("name", &name.debug_with(cx))
+                .finish(),
+            DefIdData::TypeParameter { owner, name } => fmt
+                .debug_struct("TypeParameter")
+                .field("owner", &owner.debug_with(cx))
+                .field("name", &name.debug_with(cx))
+                .finish(),
+        }
+    }
+}
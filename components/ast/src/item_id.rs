@@ -1,3 +1,5 @@
+use crate::def_id::DefId;
+use crate::def_id::DefIdData;
 use crate::HasParserState;
 use debug::DebugWith;
 use intern::Has;
@@ -20,6 +22,7 @@ intern::intern_tables! {
     pub struct ItemIdTables {
         struct ItemIdTablesData {
             item_ids: map(ItemId, ItemIdData),
+            def_ids: map(DefId, DefIdData),
         }
     }
 }
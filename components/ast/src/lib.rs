@@ -7,18 +7,20 @@
 
 use crate::item_id::ItemId;
 use crate::item_id::ItemIdTables;
-use crate::parser_state::ParserState;
 use intern::Has;
 pub use parser::ast;
 use parser::ParseError;
 use parser::StringId;
 use std::sync::Arc;
 
+pub mod def_id;
 pub mod item_id;
 mod parser_state;
 mod query_definitions;
 mod test;
 
+pub use crate::parser_state::ParserState;
+
 salsa::query_group! {
     pub trait AstDatabase: HasParserState + Has<ItemIdTables> + salsa::Database {
         // These queries don't properly belong here -- probably in
@@ -34,20 +36,83 @@ salsa::query_group! {
             storage input;
         }
 
-        fn ast_of_file(path: StringId) -> Result<Arc<ast::Module>, ParseError> {
+        /// Other files whose items can be named from the file `path`
+        /// as if they were its own, searched in order after it. The
+        /// REPL puts each entry in a file of its own and makes the
+        /// earlier entries visible from it this way.
+        fn visible_files(path: StringId) -> Arc<Vec<StringId>> {
+            type VisibleFiles;
+            storage input;
+        }
+
+        /// Parses the file `path`, recovering from syntax errors. Use
+        /// `ast_of_file` and `parse_errors` instead, so that you only
+        /// depend on the half of the result you look at.
+        fn parse_file(path: StringId) -> (Arc<ast::Module>, Arc<Vec<ParseError>>) {
+            type ParseFile;
+            use fn query_definitions::parse_file;
+        }
+
+        /// Parses `text`, the source of one item and the whitespace and
+        /// comments after it, as if it began at the start of a file.
+        /// `parse_file` parses each item of a file with this, so an
+        /// item whose text did not change is not parsed again.
+        fn parse_item_text(text: StringId) -> (Arc<ast::Module>, Arc<Vec<ParseError>>) {
+            type ParseItemText;
+            use fn query_definitions::parse_item_text;
+        }
+
+        /// The text of each item of the file `path` -- as split by
+        /// `parser::split_file` -- and the offset where it starts.
+        fn item_texts(path: StringId) -> Arc<Vec<(StringId, u32)>> {
+            type ItemTexts;
+            use fn query_definitions::item_texts;
+        }
+
+        /// The items of the file `path` that parsed, with `Error`
+        /// nodes wherever a statement did not.
+        fn ast_of_file(path: StringId) -> Arc<ast::Module> {
             type AstOfFile;
             use fn query_definitions::ast_of_file;
         }
 
+        /// The syntax errors in the file `path`.
+        fn parse_errors(path: StringId) -> Arc<Vec<ParseError>> {
+            type ParseErrors;
+            use fn query_definitions::parse_errors;
+        }
+
         fn items_in_file(path: StringId) -> Arc<Vec<ItemId>> {
             type ItemsInFile;
             use fn query_definitions::items_in_file;
         }
 
-        fn ast_of_item(item: ItemId) -> Result<Arc<ast::Item>, ParseError> {
+        /// The item `item`, with spans relative to `item_offset`
+        /// rather than to the start of its file.
+        fn ast_of_item(item: ItemId) -> Arc<ast::Item> {
             type AstOfItem;
             use fn query_definitions::ast_of_item;
         }
+
+        /// The text of the item `item`, or of the struct it is a
+        /// method of, as `item_texts` splits it. Unlike `item_texts`,
+        /// this changes only when that text does, so `ast_of_item`,
+        /// which reads the item from it, and the queries after it do
+        /// not run again when a different item is edited.
+        fn item_text(item: ItemId) -> StringId {
+            type ItemText;
+            use fn query_definitions::item_text;
+        }
+
+        /// The offset in its file where the text of the item `item`
+        /// starts. The spans of `ast_of_item`, and of everything
+        /// computed from it, are relative to this, so that an edit to
+        /// one item leaves those of the items after it unchanged; add
+        /// it back when reporting them.
+        fn item_offset(item: ItemId) -> u32 {
+            type ItemOffset;
+            use fn query_definitions::item_offset;
+        }
     }
 }
 
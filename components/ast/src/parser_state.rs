@@ -2,7 +2,6 @@ use parking_lot::RwLock;
 use parser::ast;
 use parser::ParseError;
 use parser::StringId;
-use std::borrow::Cow;
 use std::sync::Arc;
 
 #[derive(Default)]
@@ -11,14 +10,27 @@ pub struct ParserState {
 }
 
 impl ParserState {
-    crate fn parse(
-        &self,
-        _path: StringId,
-        input_text: StringId,
-    ) -> Result<ast::Module, ParseError> {
-        let mut module_table = self.module_table.write();
-        let input_text = module_table.lookup(input_text).clone();
-        parser::parse(Cow::Borrowed(&**input_text), &mut module_table, 0)
+    /// Parses `text` as a file of its own. The tokenizer interns as it
+    /// goes, so it gets a table of its own rather than holding the
+    /// write lock on the shared one; each token's text is then moved to
+    /// the shared table, taking the lock only for text it does not
+    /// have yet.
+    crate fn parse(&self, text: StringId) -> (ast::Module, Vec<ParseError>) {
+        let text = self.untern_string(text);
+        let mut token_table = parser::ModuleTable::new();
+        let (tokens, mut errors) = parser::tokenize(&text, &mut token_table, 0);
+
+        let tokens: Vec<_> = tokens
+            .into_iter()
+            .map(|(start, token, end)| {
+                let token = token
+                    .map_string_id(|id| self.intern_string(token_table.lookup(id).as_str()));
+                (start, token, end)
+            })
+            .collect();
+        let (module, parse_errors) = parser::parse_tokens(&tokens, &self.module_table.read());
+        errors.extend(parse_errors);
+        (module, errors)
     }
 
     crate fn untern_string(&self, string_id: StringId) -> Arc<String> {
@@ -8,24 +8,63 @@ use parser::ParseError;
 use parser::StringId;
 use std::sync::Arc;
 
-crate fn ast_of_file(
+crate fn parse_file(
     db: &impl AstDatabase,
     path: StringId,
-) -> Result<Arc<ast::Module>, ParseError> {
+) -> (Arc<ast::Module>, Arc<Vec<ParseError>>) {
+    // Each item is parsed on its own, and then moved to where it is in
+    // the file. An edit within one item changes only that item's text,
+    // so the others come out of `parse_item_text` unparsed.
+    let mut items = vec![];
+    let mut errors = vec![];
+    for &(text, start) in db.item_texts(path).iter() {
+        let (module, item_errors) = db.parse_item_text(text);
+
+        items.extend(module.items.iter().map(|item| Arc::new(item.shifted(start))));
+        errors.extend(item_errors.iter().map(|err| ParseError {
+            description: err.description.clone(),
+            span: err.span.shifted(start),
+        }));
+    }
+
+    (Arc::new(ast::Module::new(items)), Arc::new(errors))
+}
+
+crate fn item_texts(db: &impl AstDatabase, path: StringId) -> Arc<Vec<(StringId, u32)>> {
     let input_text = db.input_text(path).unwrap_or_else(|| {
         panic!("no input text for path `{}`", db.untern_string(path));
     });
+    let input_text = db.untern_string(input_text);
+
+    let texts = parser::split_file(&input_text)
+        .into_iter()
+        .map(|range| {
+            let start = range.start as u32;
+            (db.intern_string(&input_text[range]), start)
+        })
+        .collect();
+    Arc::new(texts)
+}
+
+crate fn parse_item_text(
+    db: &impl AstDatabase,
+    text: StringId,
+) -> (Arc<ast::Module>, Arc<Vec<ParseError>>) {
+    let (module, errors) = db.parser_state().parse(text);
+
+    (Arc::new(module), Arc::new(errors))
+}
 
-    let module = db.parser_state().parse(path, input_text)?;
+crate fn ast_of_file(db: &impl AstDatabase, path: StringId) -> Arc<ast::Module> {
+    db.parse_file(path).0
+}
 
-    Ok(Arc::new(module))
+crate fn parse_errors(db: &impl AstDatabase, path: StringId) -> Arc<Vec<ParseError>> {
+    db.parse_file(path).1
 }
 
 crate fn items_in_file(db: &impl AstDatabase, input_file: StringId) -> Arc<Vec<ItemId>> {
-    let ast_of_file = match db.ast_of_file(input_file) {
-        Ok(module) => module,
-        Err(_) => return Arc::new(vec![]),
-    };
+    let ast_of_file = db.ast_of_file(input_file);
 
     let items: Vec<_> = ast_of_file
         .items
@@ -41,22 +80,55 @@ crate fn items_in_file(db: &impl AstDatabase, input_file: StringId) -> Arc<Vec<I
     Arc::new(items)
 }
 
-crate fn ast_of_item(db: &impl AstDatabase, item_id: ItemId) -> Result<Arc<ast::Item>, ParseError> {
-    let ItemIdData { input_file, path } = item_id.untern(db);
-    let module = db.ast_of_file(input_file)?;
+crate fn ast_of_item(db: &impl AstDatabase, item_id: ItemId) -> Arc<ast::Item> {
+    let ItemIdData { input_file: _, path } = item_id.untern(db);
 
-    // have to follow `path` through `module`; for now we'll just support lenth-1 paths
-    // (no nested items)
-    if path.len() != 1 {
-        unimplemented!();
+    // Follow `path` through the file: the first component names an
+    // item of the file, and each one after that an item within the one
+    // before it -- which, for now, can only be a method of a struct.
+    // The first is parsed from its own text, so its spans start from
+    // zero and it comes out the same wherever it is in the file.
+    let module = db.parse_item_text(db.item_text(item_id)).0;
+    let mut item = module
+        .items
+        .iter()
+        .find(|item| item.name() == path[0])
+        .cloned()
+        .unwrap_or_else(|| panic!("no item named `{}`", db.untern_string(path[0])));
+
+    for &name in &path[1..] {
+        let method = match &*item {
+            ast::Item::Struct(s) => s.methods.iter().find(|m| m.name.node == name),
+            _ => None,
+        };
+        item = match method {
+            Some(method) => Arc::new(ast::Item::Def(method.clone())),
+            None => panic!("no item named `{}`", db.untern_string(name)),
+        };
     }
-    let path_id = path[0];
 
-    for item in &module.items {
-        if item.name() == path_id {
-            return Ok(item.clone());
+    item
+}
+
+crate fn item_text(db: &impl AstDatabase, item_id: ItemId) -> StringId {
+    let ItemIdData { input_file, path } = item_id.untern(db);
+    item_texts_entry(db, input_file, path[0]).0
+}
+
+crate fn item_offset(db: &impl AstDatabase, item_id: ItemId) -> u32 {
+    // A method's spans are relative to the text of its struct.
+    let ItemIdData { input_file, path } = item_id.untern(db);
+    item_texts_entry(db, input_file, path[0]).1
+}
+
+/// The entry of `item_texts` for the item `name` of the file `path`.
+fn item_texts_entry(db: &impl AstDatabase, path: StringId, name: StringId) -> (StringId, u32) {
+    for &(text, start) in db.item_texts(path).iter() {
+        let module = db.parse_item_text(text).0;
+        if module.items.iter().any(|item| item.name() == name) {
+            return (text, start);
         }
     }
 
-    panic!("no such item")
+    panic!("no item named `{}`", db.untern_string(name))
 }
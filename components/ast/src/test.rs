@@ -7,8 +7,15 @@ use crate::AstOfItem;
 use crate::HasParserState;
 use crate::InputFiles;
 use crate::InputText;
+use crate::ItemOffset;
+use crate::ItemText;
+use crate::ItemTexts;
 use crate::ItemsInFile;
+use crate::ParseErrors;
+use crate::ParseFile;
+use crate::ParseItemText;
 use crate::ParserState;
+use crate::VisibleFiles;
 use debug::DebugWith;
 use intern::Has;
 use salsa::Database;
@@ -26,9 +33,16 @@ salsa::database_storage! {
         impl AstDatabase {
             fn input_files() for InputFiles;
             fn input_text() for InputText;
+            fn visible_files() for VisibleFiles;
+            fn parse_file() for ParseFile;
+            fn parse_item_text() for ParseItemText;
+            fn item_texts() for ItemTexts;
+            fn item_text() for ItemText;
             fn ast_of_file() for AstOfFile;
+            fn parse_errors() for ParseErrors;
             fn items_in_file() for ItemsInFile;
             fn ast_of_item() for AstOfItem;
+            fn item_offset() for ItemOffset;
         }
     }
 }
@@ -69,7 +83,8 @@ fn parse_error() {
     let text1 = db.intern_string("XXX");
     db.query(InputText).set(path1, Some(text1));
 
-    assert!(db.ast_of_file(path1).is_err());
+    assert_eq!(db.parse_errors(path1).len(), 1);
+    assert!(db.ast_of_file(path1).items.is_empty());
 }
 
 #[test]
@@ -91,9 +106,9 @@ def new(msg: own String, level: String) -> Diagnostic {
     db.query(InputText).set(path1, Some(text1));
 
     assert!(
-        db.ast_of_file(path1).is_ok(),
+        db.parse_errors(path1).is_empty(),
         "{:?}",
-        db.ast_of_file(path1).unwrap_err(),
+        db.parse_errors(path1),
     );
 
     let items_in_file = db.items_in_file(path1);
@@ -115,3 +130,74 @@ def new(msg: own String, level: String) -> Diagnostic {
 ]"#
     );
 }
+
+#[test]
+fn reparse_one_item() {
+    let db = TestDatabaseImpl::default();
+
+    let path1 = db.intern_string("path1");
+    db.query(InputFiles).set((), Arc::new(vec![path1]));
+    let set_text = |text: &str| {
+        let text = db.intern_string(text);
+        db.query(InputText).set(path1, Some(text));
+
+        // Parsing item by item gives what parsing the whole file does.
+        let (module, errors) = db.parser_state().parse(text);
+        assert_eq!(*db.ast_of_file(path1), module);
+        assert_eq!(*db.parse_errors(path1), errors);
+        assert_eq!(errors.len(), 1);
+    };
+
+    set_text(
+        "// A comment.
+struct Point {
+  x: i32,
+}
+
+def one() -> i32 {
+  1
+}
+
+/// Unfinished.
+def two() -> i32 {
+  one(
+}
+",
+    );
+    let items = db.items_in_file(path1);
+    let before: Vec<_> = items.iter().map(|&item| db.ast_of_item(item)).collect();
+    let texts_before: Vec<_> = items.iter().map(|&item| db.item_text(item)).collect();
+
+    set_text(
+        "// A comment.
+struct Point {
+  x: i32,
+}
+
+def one() -> i32 {
+  1 + 2
+}
+
+/// Unfinished.
+def two() -> i32 {
+  one(
+}
+",
+    );
+    assert_eq!(*db.items_in_file(path1), *items);
+    let after: Vec<_> = items.iter().map(|&item| db.ast_of_item(item)).collect();
+    let texts_after: Vec<_> = items.iter().map(|&item| db.item_text(item)).collect();
+
+    assert_eq!(after[0], before[0]);
+    assert_ne!(after[1], before[1]);
+    assert_eq!(after[2], before[2]);
+
+    // Only the edited item's text changed, so the queries about the
+    // others, which read their text from `item_text`, need not run.
+    assert_eq!(texts_after[0], texts_before[0]);
+    assert_ne!(texts_after[1], texts_before[1]);
+    assert_eq!(texts_after[2], texts_before[2]);
+
+    let offsets: Vec<_> = items.iter().map(|&item| db.item_offset(item)).collect();
+    assert_eq!(offsets, vec![0, 42, 72]);
+}
@@ -1,6 +1,6 @@
 use mir::{
-    builtin_type, BasicBlock, BinOp, BuiltinFn, Context, Definition, Function, Operand, Place,
-    Rvalue, StatementKind, Struct, Terminator, TerminatorKind, Ty, VarId,
+    BasicBlock, BinOp, BuiltinFn, BuiltinType, Context, Definition, Enum, Function, Operand,
+    Place, Rvalue, StatementKind, Struct, Terminator, TerminatorKind, Ty, UnOp, VarId,
 };
 
 pub struct RustFile {
@@ -31,11 +31,17 @@ impl RustFile {
 
 fn build_type(c: &Context, ty: Ty) -> String {
     match c.get_def_id_for_ty(ty) {
-        Some(builtin_type::I32) => "i32".into(),
-        Some(builtin_type::VOID) => "()".into(),
-        Some(builtin_type::STRING) => "String".into(),
         Some(def_id) => match &c.definitions[def_id] {
-            Definition::Struct(s) => s.name.clone(),
+            Definition::BuiltinType(builtin) => match builtin {
+                BuiltinType::Unit => "()".into(),
+                BuiltinType::Bool => "bool".into(),
+                BuiltinType::I32 => "i32".into(),
+                BuiltinType::U32 => "u32".into(),
+                BuiltinType::F64 => "f64".into(),
+                BuiltinType::String => "String".into(),
+            },
+            Definition::Struct(s) => rust_name(&s.name),
+            Definition::Enum(e) => rust_name(&e.name),
             _ => unimplemented!("Can't build name for definition"),
         },
         _ => unimplemented!("Can't build name for definition"),
@@ -44,19 +50,87 @@ fn build_type(c: &Context, ty: Ty) -> String {
 
 fn build_var_name(f: &Function, var_id: VarId) -> String {
     match &f.local_decls[var_id].name {
-        Some(n) => n.clone(),
+        Some(n) => rust_name(n),
         None => format!("_tmp_{}", var_id,),
     }
 }
 
-fn build_operand(f: &Function, operand: &Operand) -> String {
+/// Renames variables, fields and items that Rust would not accept:
+/// the `self` parameter of a method, which we compile to a free
+/// function, and names that are Rust keywords, which become raw
+/// identifiers.
+fn rust_name(name: &str) -> String {
+    match name {
+        "self" => "self_".to_string(),
+        // These cannot be raw identifiers.
+        "Self" | "crate" | "super" => format!("{}_", name),
+        _ if RUST_KEYWORDS.contains(&name) => format!("r#{}", name),
+        _ => name.to_string(),
+    }
+}
+
+/// The words that Rust reserves, in the 2018 edition.
+const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do",
+    "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+/// Names the field of a variant; the fields of tuple variants are
+/// named by position, which Rust does not accept in braces.
+fn variant_field_name(name: &str) -> String {
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", name)
+    } else {
+        rust_name(name)
+    }
+}
+
+/// The enum held by the local `var_id`. mir-build only switches on,
+/// and reads the variant fields of, locals whose type is the enum of
+/// the pattern being matched.
+fn local_enum<'c>(c: &'c Context, f: &Function, var_id: VarId) -> &'c Enum {
+    match c.get_def_id_for_ty(f.local_decls[var_id].ty) {
+        Some(def_id) => match &c.definitions[def_id] {
+            Definition::Enum(e) => e,
+            _ => unreachable!("Variant of a value that is not an enum"),
+        },
+        None => unreachable!("Variant of a value that is not an enum"),
+    }
+}
+
+fn build_operand(c: &Context, f: &Function, operand: &Operand) -> String {
     match operand {
         Operand::ConstantInt(i) => format!("{}", i),
-        Operand::ConstantString(s) => format!("\"{}\"", s),
-        Operand::Copy(place) | Operand::Move(place) => match place {
-            Place::Local(var_id) => format!("{}", build_var_name(f, *var_id)),
-            _ => unimplemented!("Copy of non-local value"),
-        },
+        Operand::ConstantFloat(x) => format!("{:?}", x),
+        Operand::ConstantBool(b) => format!("{}", b),
+        Operand::ConstantString(s) => format!("String::from({:?})", s),
+        Operand::ConstantUnit => "()".into(),
+        Operand::Copy(place) => format!("{}.clone()", build_place(c, f, place)),
+        Operand::Move(place) => build_place(c, f, place),
+    }
+}
+
+fn build_place(c: &Context, f: &Function, place: &Place) -> String {
+    match place {
+        Place::Local(var_id) => build_var_name(f, *var_id),
+        Place::Field(var_id, field_name) => {
+            format!("{}.{}", build_var_name(f, *var_id), rust_name(field_name))
+        }
+        Place::VariantField(var_id, index, field_name) => {
+            let e = local_enum(c, f, *var_id);
+            format!(
+                "(match &{} {{ {}::{} {{ {}: x, .. }} => x.clone(), _ => unreachable!() }})",
+                build_var_name(f, *var_id),
+                rust_name(&e.name),
+                rust_name(&e.variants[*index].name),
+                variant_field_name(field_name)
+            )
+        }
+        // mir-build never produces statics.
+        Place::Static(_) => unreachable!("Use of static value"),
     }
 }
 
@@ -73,16 +147,27 @@ fn codegen_block(rust: &mut RustFile, c: &Context, f: &Function, b: &BasicBlock)
                         rust.output_raw(&format!(
                             "{}.{} = ",
                             build_var_name(f, *var_id),
-                            field_name
+                            rust_name(field_name)
                         ));
                     }
+                    // mir-build only reads variant fields, when matching.
+                    Place::VariantField(..) => unreachable!("Assignment into variant field"),
                 };
                 match rhs {
-                    Rvalue::Use(operand) => rust.output_raw(&build_operand(f, operand)),
+                    Rvalue::Use(operand) => rust.output_raw(&build_operand(c, f, operand)),
                     Rvalue::BinaryOp(bin_op, lhs, rhs) => {
                         let op = match bin_op {
                             BinOp::Add => "+",
                             BinOp::Sub => "-",
+                            BinOp::Mul => "*",
+                            BinOp::Div => "/",
+                            BinOp::Rem => "%",
+                            BinOp::Eq => "==",
+                            BinOp::Ne => "!=",
+                            BinOp::Lt => "<",
+                            BinOp::Le => "<=",
+                            BinOp::Gt => ">",
+                            BinOp::Ge => ">=",
                         };
 
                         rust.output_raw(&format!(
@@ -92,14 +177,22 @@ fn codegen_block(rust: &mut RustFile, c: &Context, f: &Function, b: &BasicBlock)
                             build_var_name(f, *rhs)
                         ));
                     }
+                    Rvalue::UnaryOp(un_op, operand) => {
+                        let op = match un_op {
+                            UnOp::Neg => "-",
+                            UnOp::Not => "!",
+                        };
+
+                        rust.output_raw(&format!("{}{}", op, build_var_name(f, *operand)));
+                    }
                     Rvalue::Call(def_id, args) => {
                         let mut processed_args = vec![];
                         for arg in args {
-                            processed_args.push(build_operand(f, arg));
+                            processed_args.push(build_operand(c, f, arg));
                         }
                         match &c.definitions[*def_id] {
                             Definition::Fn(f) => {
-                                rust.output_raw(&format!("{}(", f.name));
+                                rust.output_raw(&format!("{}(", rust_name(&f.name)));
                                 let mut first = true;
                                 for processed_arg in processed_args {
                                     if !first {
@@ -112,24 +205,33 @@ fn codegen_block(rust: &mut RustFile, c: &Context, f: &Function, b: &BasicBlock)
                                 rust.output_raw(")");
                             }
                             Definition::BuiltinFn(builtin_fn) => match builtin_fn {
+                                BuiltinFn::Print => {
+                                    rust.output_raw(&format!(
+                                        "println!(\"{{}}\", {})",
+                                        processed_args[0]
+                                    ));
+                                }
                                 BuiltinFn::StringInterpolate => {
-                                    rust.output_raw("format!(");
-                                    let mut first = true;
-                                    for processed_arg in processed_args {
-                                        if !first {
-                                            rust.output_raw(", ");
-                                        } else {
-                                            first = false;
+                                    // The format string must be a literal, not a
+                                    // `String`; mir-build always passes it as a
+                                    // constant.
+                                    match &args[0] {
+                                        Operand::ConstantString(s) => {
+                                            rust.output_raw(&format!("format!({:?}", s))
                                         }
-                                        rust.output_raw(&processed_arg);
+                                        _ => unreachable!("Interpolation without a format string"),
+                                    }
+                                    for processed_arg in processed_args.iter().skip(1) {
+                                        rust.output_raw(", ");
+                                        rust.output_raw(processed_arg);
                                     }
                                     rust.output_raw(")");
                                 }
                             },
                             Definition::Struct(s) => {
-                                rust.output_raw(&format!("{} {{", s.name));
+                                rust.output_raw(&format!("{} {{", rust_name(&s.name)));
                                 for i in 0..s.fields.len() {
-                                    rust.output_raw(&s.fields[i].name);
+                                    rust.output_raw(&rust_name(&s.fields[i].name));
                                     rust.output_raw(": ");
                                     rust.output_raw(&processed_args[i]);
                                     rust.output_raw(", ");
@@ -139,41 +241,91 @@ fn codegen_block(rust: &mut RustFile, c: &Context, f: &Function, b: &BasicBlock)
                             _ => {}
                         }
                     }
+                    Rvalue::Variant(def_id, index, args) => match &c.definitions[*def_id] {
+                        Definition::Enum(e) => {
+                            let variant = &e.variants[*index];
+                            rust.output_raw(&format!(
+                                "{}::{} {{",
+                                rust_name(&e.name),
+                                rust_name(&variant.name)
+                            ));
+                            for (field, arg) in variant.fields.iter().zip(args) {
+                                rust.output_raw(&variant_field_name(&field.name));
+                                rust.output_raw(": ");
+                                rust.output_raw(&build_operand(c, f, arg));
+                                rust.output_raw(", ");
+                            }
+                            rust.output_raw("}");
+                        }
+                        // mir-build makes variants of the enum that
+                        // declares them.
+                        _ => unreachable!("Variant of a definition that is not an enum"),
+                    },
                 }
                 rust.output_raw(";\n");
             }
             StatementKind::DebugPrint(place) => match place {
-                Place::Local(var_id) => {
-                    rust.output_raw(&format!(
-                        "println!(\"{{:?}}\", {});\n",
-                        build_var_name(f, *var_id)
-                    ));
-                }
                 Place::Static(_) => unimplemented!("Debug print of value that is not a local"),
-                Place::Field(var_id, field_name) => {
+                _ => {
                     rust.output_raw(&format!(
-                        "println!(\"{{:?}}\", {}.{});\n",
-                        build_var_name(f, *var_id),
-                        field_name
+                        "println!(\"{{:?}}\", {});\n",
+                        build_place(c, f, place)
                     ));
                 }
             },
         }
     }
-    match b.terminator {
+    match &b.terminator {
         Some(Terminator {
             kind: TerminatorKind::Return,
             ..
-        }) => match c.get_def_id_for_ty(f.local_decls[0].ty) {
-            Some(builtin_type::VOID) => rust.output_raw("return;\n"),
+        }) => match c.builtin_type_of(f.local_decls[0].ty) {
+            Some(BuiltinType::Unit) => rust.output_raw("return;\n"),
             _ => rust.output_raw(&format!("return {};\n", build_var_name(f, 0))),
         },
+        Some(Terminator {
+            kind: TerminatorKind::Goto(target),
+            ..
+        }) => rust.output_raw(&format!("_bb = {};\n", target)),
+        Some(Terminator {
+            kind: TerminatorKind::If(condition, if_true, if_false),
+            ..
+        }) => rust.output_raw(&format!(
+            "_bb = if {} {{ {} }} else {{ {} }};\n",
+            build_operand(c, f, condition),
+            if_true,
+            if_false
+        )),
+        Some(Terminator {
+            kind: TerminatorKind::Switch(place, targets),
+            ..
+        }) => {
+            let e = match place {
+                Place::Local(var_id) => local_enum(c, f, *var_id),
+                // mir-build copies what it matches on into a local.
+                _ => unreachable!("Switch on value that is not a local"),
+            };
+            rust.output_raw(&format!("_bb = match &{} {{\n", build_place(c, f, place)));
+            for (variant, target) in e.variants.iter().zip(targets) {
+                rust.output_raw(&format!(
+                    "{}::{} {{ .. }} => {},\n",
+                    rust_name(&e.name),
+                    rust_name(&variant.name),
+                    target
+                ));
+            }
+            rust.output_raw("};\n");
+        }
+        Some(Terminator {
+            kind: TerminatorKind::Unreachable,
+            ..
+        }) => rust.output_raw("unreachable!();\n"),
         None => {}
     }
 }
 
 fn codegen_fn(rust: &mut RustFile, c: &Context, f: &Function) {
-    rust.output_raw(&("fn ".to_string() + &f.name + "("));
+    rust.output_raw(&("fn ".to_string() + &rust_name(&f.name) + "("));
     let mut after_first = false;
     for param in f.local_decls.iter().skip(1).take(f.arg_count) {
         if after_first {
@@ -181,7 +333,7 @@ fn codegen_fn(rust: &mut RustFile, c: &Context, f: &Function) {
         } else {
             after_first = true;
         }
-        rust.output_raw(&param.name.clone().unwrap());
+        rust.output_raw(&rust_name(param.name.as_ref().unwrap()));
         rust.output_raw(": ");
         rust.output_raw(&build_type(c, param.ty));
     }
@@ -190,39 +342,89 @@ fn codegen_fn(rust: &mut RustFile, c: &Context, f: &Function) {
 
     rust.output_raw(" {\n");
 
-    for (idx, local_decl) in f.local_decls.iter().enumerate().skip(1 + f.arg_count) {
-        rust.output_raw(&format!(
-            "let {}: {};\n",
-            build_var_name(f, idx),
-            build_type(c, local_decl.ty)
-        ));
+    // With more than one block, we jump between the blocks in a loop,
+    // where the compiler cannot see that each local is assigned before
+    // it is used, so we start them out with a default value.
+    let jumps = f.basic_blocks.len() > 1;
+    let declare = |rust: &mut RustFile, idx: VarId| {
+        let name = build_var_name(f, idx);
+        let ty = build_type(c, f.local_decls[idx].ty);
+        if jumps {
+            rust.output_raw(&format!("let mut {}: {} = Default::default();\n", name, ty));
+        } else {
+            rust.output_raw(&format!("let {}: {};\n", name, ty));
+        }
+    };
+
+    for idx in (1 + f.arg_count)..f.local_decls.len() {
+        declare(rust, idx);
     }
 
-    match c.get_def_id_for_ty(f.local_decls[0].ty) {
-        Some(builtin_type::VOID) => {}
-        _ => {
-            rust.output_raw(&format!(
-                "let {}: {};\n",
-                build_var_name(f, 0),
-                build_type(c, f.local_decls[0].ty)
-            ));
-        }
+    match c.builtin_type_of(f.local_decls[0].ty) {
+        Some(BuiltinType::Unit) => {}
+        _ => declare(rust, 0),
     }
 
-    for block in &f.basic_blocks {
-        codegen_block(rust, c, f, block);
+    if jumps {
+        rust.output_raw("let mut _bb = 0;\nloop {\nmatch _bb {\n");
+        for (idx, block) in f.basic_blocks.iter().enumerate() {
+            rust.output_raw(&format!("{} => {{\n", idx));
+            codegen_block(rust, c, f, block);
+            rust.output_raw("}\n");
+        }
+        rust.output_raw("_ => unreachable!(),\n}\n}\n");
+    } else {
+        for block in &f.basic_blocks {
+            codegen_block(rust, c, f, block);
+        }
     }
 
     rust.output_raw("}\n");
 }
 
 fn codegen_struct(rust: &mut RustFile, c: &Context, s: &Struct) {
-    rust.output_raw(&format!("#[derive(Debug)]\n"));
-    rust.output_raw(&format!("struct {} {{\n", s.name));
+    rust.output_raw(&format!("#[derive(Clone, Debug, Default)]\n"));
+    rust.output_raw(&format!("struct {} {{\n", rust_name(&s.name)));
     for field in &s.fields {
-        rust.output_raw(&format!("{}: {},\n", field.name, build_type(c, field.ty)));
+        rust.output_raw(&format!("{}: {},\n", rust_name(&field.name), build_type(c, field.ty)));
+    }
+    rust.output_raw("}\n");
+}
+
+fn codegen_enum(rust: &mut RustFile, c: &Context, e: &Enum) {
+    rust.output_raw(&format!("#[derive(Clone, Debug)]\n"));
+    rust.output_raw(&format!("enum {} {{\n", rust_name(&e.name)));
+    for variant in &e.variants {
+        rust.output_raw(&format!("{} {{\n", rust_name(&variant.name)));
+        for field in &variant.fields {
+            rust.output_raw(&format!(
+                "{}: {},\n",
+                variant_field_name(&field.name),
+                build_type(c, field.ty)
+            ));
+        }
+        rust.output_raw("},\n");
     }
     rust.output_raw("}\n");
+
+    // Locals start out with a default value (see `codegen_fn`), so
+    // every type needs one; we use the first variant.
+    if let Some(variant) = e.variants.first() {
+        let name = rust_name(&e.name);
+        rust.output_raw(&format!("impl Default for {} {{\n", name));
+        rust.output_raw(&format!(
+            "fn default() -> Self {{\n{}::{} {{\n",
+            name,
+            rust_name(&variant.name)
+        ));
+        for field in &variant.fields {
+            rust.output_raw(&format!(
+                "{}: Default::default(),\n",
+                variant_field_name(&field.name)
+            ));
+        }
+        rust.output_raw("}\n}\n}\n");
+    }
 }
 
 pub fn codegen(rust: &mut RustFile, c: &Context) {
@@ -234,6 +436,9 @@ pub fn codegen(rust: &mut RustFile, c: &Context) {
             Definition::Struct(s) => {
                 codegen_struct(rust, c, s);
             }
+            Definition::Enum(e) => {
+                codegen_enum(rust, c, e);
+            }
             _ => {}
         }
     }
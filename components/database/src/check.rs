@@ -0,0 +1,124 @@
+//! The checks that both `lark build` and the IDE run over a set of
+//! files. They go in stages -- parsing, then the items' signatures,
+//! then the function bodies -- and stop at the first stage that
+//! reports errors, since later stages would mostly repeat them.
+
+use crate::LarkDatabase;
+use ast::def_id::{DefId, DefIdData};
+use ast::AstDatabase;
+use hir::HirDatabase;
+use intern::Untern;
+use parser::pos::Span;
+use parser::StringId;
+use type_check::diagnostic::Diagnostic;
+use type_check::TypeCheckDatabase;
+
+/// An error that `check` found in the file `file`. Its spans are
+/// relative to the start of the file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CheckError {
+    pub file: StringId,
+    pub kind: CheckErrorKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CheckErrorKind {
+    /// An error that is reported as just a message and a span.
+    Message { message: String, span: Span },
+
+    /// A type-check error, which comes with its own labels and notes.
+    TypeCheck(Diagnostic),
+}
+
+/// Checks the items in `files`, returning the errors of the first
+/// stage that has any.
+pub fn check(db: &LarkDatabase, files: &[StringId]) -> Vec<CheckError> {
+    let mut errors = vec![];
+
+    for &file in files {
+        for err in db.parse_errors(file).iter() {
+            errors.push(message(file, err.description.clone(), err.span));
+        }
+    }
+    if !errors.is_empty() {
+        return errors;
+    }
+
+    for &file in files {
+        for &def_id in db.defs_in_file(file).iter() {
+            let offset = def_offset(db, def_id);
+            for error_data in db.item_errors(def_id).iter() {
+                let span = error_data.span.shifted(offset);
+                errors.push(message(file, error_data.node.message(db), span));
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return errors;
+    }
+
+    for &file in files {
+        for &def_id in db.defs_in_file(file).iter() {
+            if !is_def(db, def_id) {
+                continue;
+            }
+
+            let offset = def_offset(db, def_id);
+            let fn_body = db.fn_body(def_id);
+            for error_data in fn_body.errors.iter() {
+                let span = error_data.span.shifted(offset);
+                errors.push(message(file, error_data.node.message(db), span));
+            }
+            if !fn_body.errors.is_empty() {
+                continue;
+            }
+
+            for type_error in db.base_type_check(def_id).errors() {
+                let mut diagnostic = type_error.diagnostic(db, &fn_body);
+                for label in &mut diagnostic.labels {
+                    label.span = label.span.shifted(offset);
+                }
+                errors.push(CheckError {
+                    file,
+                    kind: CheckErrorKind::TypeCheck(diagnostic),
+                });
+            }
+        }
+    }
+
+    errors
+}
+
+fn message(file: StringId, message: String, span: Span) -> CheckError {
+    CheckError {
+        file,
+        kind: CheckErrorKind::Message { message, span },
+    }
+}
+
+/// The offset in its file that the spans of the item, variant or
+/// field `def_id` are relative to; see `AstDatabase::item_offset`.
+pub fn def_offset(db: &LarkDatabase, def_id: DefId) -> u32 {
+    match def_id.untern(db) {
+        DefIdData::Item(item_id) => db.item_offset(item_id),
+        DefIdData::Variant { owner, .. }
+        | DefIdData::Field { owner, .. }
+        | DefIdData::TypeParameter { owner, .. } => def_offset(db, owner),
+        DefIdData::Builtin(_) => 0,
+    }
+}
+
+/// True if `def_id` is a function, which is the only kind of item
+/// with a body.
+fn is_def(db: &LarkDatabase, def_id: DefId) -> bool {
+    match def_id.untern(db) {
+        DefIdData::Item(item_id) => match &*db.ast_of_item(item_id) {
+            ast::ast::Item::Def(_) => true,
+            ast::ast::Item::Struct(_)
+            | ast::ast::Item::Enum(_)
+            | ast::ast::Item::Mod(_)
+            | ast::ast::Item::Use(_) => false,
+        },
+        _ => false,
+    }
+}
@@ -0,0 +1,137 @@
+//! The database that ties together all the query groups of the
+//! compiler. The CLI, the IDE and the tests all drive the pipeline
+//! through it.
+
+use ast::item_id::ItemIdTables;
+use ast::{HasParserState, ParserState};
+use intern::Has;
+use parser::StringId;
+use salsa::Database;
+use std::sync::Arc;
+use ty::interners::TyInternTables;
+
+pub mod check;
+
+/// The database that holds all the inputs and derived state for a
+/// compilation.
+#[derive(Default)]
+pub struct LarkDatabase {
+    runtime: salsa::runtime::Runtime<LarkDatabase>,
+    parser_state: ParserState,
+    item_id_tables: ItemIdTables,
+    ty_intern_tables: TyInternTables,
+}
+
+salsa::database_storage! {
+    pub struct LarkDatabaseStorage for LarkDatabase {
+        impl ast::AstDatabase {
+            fn input_files() for ast::InputFiles;
+            fn input_text() for ast::InputText;
+            fn visible_files() for ast::VisibleFiles;
+            fn parse_file() for ast::ParseFile;
+            fn parse_item_text() for ast::ParseItemText;
+            fn item_texts() for ast::ItemTexts;
+            fn item_text() for ast::ItemText;
+            fn ast_of_file() for ast::AstOfFile;
+            fn parse_errors() for ast::ParseErrors;
+            fn items_in_file() for ast::ItemsInFile;
+            fn ast_of_item() for ast::AstOfItem;
+            fn item_offset() for ast::ItemOffset;
+        }
+        impl hir::HirDatabase {
+            fn boolean_def_id() for hir::BooleanDefIdQuery;
+            fn unit_def_id() for hir::UnitDefIdQuery;
+            fn i32_def_id() for hir::I32DefIdQuery;
+            fn f64_def_id() for hir::F64DefIdQuery;
+            fn string_def_id() for hir::StringDefIdQuery;
+            fn module_file() for hir::ModuleFileQuery;
+            fn parent_module() for hir::ParentModuleQuery;
+            fn module_path() for hir::ModulePathQuery;
+            fn defs_in_file() for hir::DefsInFileQuery;
+            fn fn_body() for hir::FnBodyQuery;
+            fn members() for hir::MembersQuery;
+            fn member_def_id() for hir::MemberDefIdQuery;
+            fn ty() for hir::TyQuery;
+            fn signature() for hir::SignatureQuery;
+            fn item_errors() for hir::ItemErrorsQuery;
+            fn generic_declarations() for hir::GenericDeclarations;
+        }
+        impl type_check::TypeCheckDatabase {
+            fn base_type_check() for type_check::BaseTypeCheckQuery;
+        }
+    }
+}
+
+impl LarkDatabase {
+    /// Adds a file named `path` with the contents `text`, returning
+    /// the interned path. If there is already a file named `path`,
+    /// its text is replaced.
+    pub fn add_file(&self, path: &str, text: &str) -> StringId {
+        let path = self.intern_string(path);
+
+        let mut input_files = (*self.query(ast::InputFiles).get(())).clone();
+        if !input_files.contains(&path) {
+            input_files.push(path);
+            self.query(ast::InputFiles).set((), Arc::new(input_files));
+        }
+        self.update_file(path, text);
+
+        path
+    }
+
+    /// Replaces the text of the file `path`, which must have been
+    /// added already.
+    pub fn update_file(&self, path: StringId, text: &str) {
+        let text = self.intern_string(text);
+        self.query(ast::InputText).set(path, Some(text));
+    }
+
+    /// Removes the file `path`, if present.
+    pub fn remove_file(&self, path: StringId) {
+        let mut input_files = (*self.query(ast::InputFiles).get(())).clone();
+        input_files.retain(|&file| file != path);
+        self.query(ast::InputFiles).set((), Arc::new(input_files));
+        self.query(ast::InputText).set(path, None);
+    }
+
+    /// Makes the items of `files` visible from the file `path`, as if
+    /// they were its own.
+    pub fn set_visible_files(&self, path: StringId, files: Vec<StringId>) {
+        self.query(ast::VisibleFiles).set(path, Arc::new(files));
+    }
+
+    /// The files that have been added, in order.
+    pub fn files(&self) -> Arc<Vec<StringId>> {
+        self.query(ast::InputFiles).get(())
+    }
+}
+
+impl Database for LarkDatabase {
+    fn salsa_runtime(&self) -> &salsa::runtime::Runtime<LarkDatabase> {
+        &self.runtime
+    }
+}
+
+impl HasParserState for LarkDatabase {
+    fn parser_state(&self) -> &ParserState {
+        &self.parser_state
+    }
+}
+
+impl parser::program::LookupStringId for LarkDatabase {
+    fn lookup(&self, id: StringId) -> Arc<String> {
+        self.untern_string(id)
+    }
+}
+
+impl Has<ItemIdTables> for LarkDatabase {
+    fn intern_tables(&self) -> &ItemIdTables {
+        &self.item_id_tables
+    }
+}
+
+impl Has<TyInternTables> for LarkDatabase {
+    fn intern_tables(&self) -> &TyInternTables {
+        &self.ty_intern_tables
+    }
+}
@@ -1,7 +1,8 @@
 use mir::{
     BinOp, BuiltinFn, Context, DefId, Definition, Function, Operand, Place, Rvalue, Statement,
-    StatementKind,
+    StatementKind, Terminator, TerminatorKind, UnOp,
 };
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fmt;
 
@@ -9,8 +10,12 @@ use std::fmt;
 pub enum Value {
     Void,
     I32(i32),
+    F64(f64),
+    Bool(bool),
     Str(String),
     Struct(HashMap<String, Value>),
+    /// A value of an enum: the index of its variant, and its fields.
+    Variant(usize, HashMap<String, Value>),
     Reference(usize), // a reference into the value stack
 }
 
@@ -21,10 +26,13 @@ impl fmt::Display for Value {
             "{}",
             match self {
                 Value::I32(i) => i.to_string(),
+                Value::F64(x) => x.to_string(),
+                Value::Bool(b) => b.to_string(),
                 Value::Str(s) => s.clone(),
                 Value::Reference(r) => format!("reference to {}", r),
                 Value::Void => "<void>".into(),
                 Value::Struct(s) => format!("{:?}", s),
+                Value::Variant(index, fields) => format!("variant {} {:?}", index, fields),
             }
         )
     }
@@ -41,10 +49,23 @@ impl CallFrame {
     }
 }
 
+/// The field `field_name` of the enum value in the local `var_id`,
+/// which must be the variant `index`. mir-build only reads the fields
+/// of a variant after a `Switch` has checked for it.
+fn variant_field(frame: &CallFrame, var_id: usize, index: usize, field_name: &str) -> Value {
+    match &frame.locals[var_id] {
+        Value::Variant(variant, fields) if *variant == index => fields[field_name].clone(),
+        _ => unreachable!("Variant field access of a value of another variant"),
+    }
+}
+
 pub fn eval_operand(_context: &Context, frame: &mut CallFrame, operand: &Operand) -> Value {
     match operand {
         Operand::ConstantInt(i) => Value::I32(*i),
+        Operand::ConstantFloat(x) => Value::F64(*x),
+        Operand::ConstantBool(b) => Value::Bool(*b),
         Operand::ConstantString(s) => Value::Str(s.clone()),
+        Operand::ConstantUnit => Value::Void,
         Operand::Move(m) => match m {
             Place::Local(source_var_id) => frame.locals[*source_var_id].clone(),
             Place::Static(_) => unimplemented!("Moving from static data not currently supported"),
@@ -52,6 +73,9 @@ pub fn eval_operand(_context: &Context, frame: &mut CallFrame, operand: &Operand
                 Value::Struct(s) => s[field_name].clone(),
                 _ => unimplemented!("Field access of non-struct value"),
             },
+            Place::VariantField(source_var_id, index, field_name) => {
+                variant_field(frame, *source_var_id, *index, field_name)
+            }
         },
         Operand::Copy(m) => match m {
             Place::Local(source_var_id) => frame.locals[*source_var_id].clone(),
@@ -60,6 +84,9 @@ pub fn eval_operand(_context: &Context, frame: &mut CallFrame, operand: &Operand
                 Value::Struct(s) => s[field_name].clone(),
                 _ => unimplemented!("Field access of non-struct value"),
             },
+            Place::VariantField(source_var_id, index, field_name) => {
+                variant_field(frame, *source_var_id, *index, field_name)
+            }
         },
     }
 }
@@ -83,6 +110,10 @@ fn eval_rvalue(context: &Context, frame: &mut CallFrame, rvalue: &Rvalue) -> Val
                     let result = new_frame.locals[0].clone();
                     result
                 }
+                Definition::BuiltinFn(BuiltinFn::Print) => {
+                    println!("{}", eval_operand(context, frame, &args[0]));
+                    Value::Void
+                }
                 Definition::BuiltinFn(BuiltinFn::StringInterpolate) => {
                     let mut arg_eval = vec![];
                     for arg in args {
@@ -93,18 +124,25 @@ fn eval_rvalue(context: &Context, frame: &mut CallFrame, rvalue: &Rvalue) -> Val
 
                     match format_string {
                         Value::Str(s) => {
-                            let sections: Vec<&str> = s.split("{}").collect();
-                            let sections_len = sections.len();
-
+                            // Like Rust's `format!`: `{}` stands for the next
+                            // argument, and `{{` and `}}` for literal braces.
                             let mut result = String::new();
-
-                            for i in 0..(sections_len - 1) {
-                                result += sections[i];
-                                result += &format!("{}", arg_eval[i + 1]);
+                            let mut arguments = arg_eval[1..].iter();
+                            let mut chars = s.chars().peekable();
+                            while let Some(c) = chars.next() {
+                                match (c, chars.peek()) {
+                                    ('{', Some('}')) => {
+                                        chars.next();
+                                        result += &format!("{}", arguments.next().unwrap());
+                                    }
+                                    ('{', Some('{')) | ('}', Some('}')) => {
+                                        chars.next();
+                                        result.push(c);
+                                    }
+                                    _ => result.push(c),
+                                }
                             }
 
-                            result += sections[sections_len - 1];
-
                             Value::Str(result)
                         }
                         _ => unimplemented!("String interpolation without a format string"),
@@ -123,21 +161,86 @@ fn eval_rvalue(context: &Context, frame: &mut CallFrame, rvalue: &Rvalue) -> Val
                 _ => unimplemented!("Unsupported call of non-function"),
             }
         }
+        Rvalue::Variant(def_id, index, args) => match &context.definitions[*def_id] {
+            Definition::Enum(e) => {
+                let mut fields = HashMap::new();
+                for (field, arg) in e.variants[*index].fields.iter().zip(args) {
+                    fields.insert(field.name.clone(), eval_operand(context, frame, arg));
+                }
+                Value::Variant(*index, fields)
+            }
+            // mir-build makes variants of the enum that declares them.
+            _ => unreachable!("Variant of a definition that is not an enum"),
+        },
         Rvalue::BinaryOp(bin_op, lhs_var_id, rhs_var_id) => {
             let lhs = &frame.locals[*lhs_var_id];
             let rhs = &frame.locals[*rhs_var_id];
 
+            // The type checker only allows arithmetic on `i32`, `u32`
+            // and `f64`, and there are no `u32` values to evaluate,
+            // since literals are `i32`.
             match bin_op {
                 BinOp::Add => match (lhs, rhs) {
                     (Value::I32(lhs_i32), Value::I32(rhs_i32)) => Value::I32(lhs_i32 + rhs_i32),
-                    _ => unimplemented!("Unsupported add of non-integers"),
+                    (Value::F64(lhs_f64), Value::F64(rhs_f64)) => Value::F64(lhs_f64 + rhs_f64),
+                    _ => unreachable!("Unsupported add of non-integers"),
                 },
                 BinOp::Sub => match (lhs, rhs) {
                     (Value::I32(lhs_i32), Value::I32(rhs_i32)) => Value::I32(lhs_i32 - rhs_i32),
-                    _ => unimplemented!("Unsupported add of non-integers"),
+                    (Value::F64(lhs_f64), Value::F64(rhs_f64)) => Value::F64(lhs_f64 - rhs_f64),
+                    _ => unreachable!("Unsupported add of non-integers"),
+                },
+                BinOp::Mul => match (lhs, rhs) {
+                    (Value::I32(lhs_i32), Value::I32(rhs_i32)) => Value::I32(lhs_i32 * rhs_i32),
+                    (Value::F64(lhs_f64), Value::F64(rhs_f64)) => Value::F64(lhs_f64 * rhs_f64),
+                    _ => unreachable!("Unsupported multiply of non-numbers"),
                 },
+                BinOp::Div => match (lhs, rhs) {
+                    (Value::I32(lhs_i32), Value::I32(rhs_i32)) => Value::I32(lhs_i32 / rhs_i32),
+                    (Value::F64(lhs_f64), Value::F64(rhs_f64)) => Value::F64(lhs_f64 / rhs_f64),
+                    _ => unreachable!("Unsupported divide of non-numbers"),
+                },
+                BinOp::Rem => match (lhs, rhs) {
+                    (Value::I32(lhs_i32), Value::I32(rhs_i32)) => Value::I32(lhs_i32 % rhs_i32),
+                    (Value::F64(lhs_f64), Value::F64(rhs_f64)) => Value::F64(lhs_f64 % rhs_f64),
+                    _ => unreachable!("Unsupported remainder of non-numbers"),
+                },
+                BinOp::Eq => Value::Bool(compare(lhs, rhs) == Some(Ordering::Equal)),
+                BinOp::Ne => Value::Bool(compare(lhs, rhs) != Some(Ordering::Equal)),
+                BinOp::Lt => Value::Bool(compare(lhs, rhs) == Some(Ordering::Less)),
+                BinOp::Le => Value::Bool(match compare(lhs, rhs) {
+                    Some(Ordering::Less) | Some(Ordering::Equal) => true,
+                    _ => false,
+                }),
+                BinOp::Gt => Value::Bool(compare(lhs, rhs) == Some(Ordering::Greater)),
+                BinOp::Ge => Value::Bool(match compare(lhs, rhs) {
+                    Some(Ordering::Greater) | Some(Ordering::Equal) => true,
+                    _ => false,
+                }),
             }
         }
+        Rvalue::UnaryOp(un_op, var_id) => match (un_op, &frame.locals[*var_id]) {
+            (UnOp::Neg, Value::I32(value)) => Value::I32(-value),
+            (UnOp::Neg, Value::F64(value)) => Value::F64(-value),
+            (UnOp::Not, Value::Bool(value)) => Value::Bool(!value),
+            // The type checker only allows negating `i32` and `f64`,
+            // and `!` on `bool`.
+            _ => unreachable!("Unsupported unary operation"),
+        },
+    }
+}
+
+/// Orders two values of the same type; `None` if they are unordered,
+/// like a NaN and any other float. The type checker only allows
+/// comparing built-in types other than `()`, and there are no `u32`
+/// values.
+fn compare(lhs: &Value, rhs: &Value) -> Option<Ordering> {
+    match (lhs, rhs) {
+        (Value::I32(lhs), Value::I32(rhs)) => Some(lhs.cmp(rhs)),
+        (Value::F64(lhs), Value::F64(rhs)) => lhs.partial_cmp(rhs),
+        (Value::Bool(lhs), Value::Bool(rhs)) => Some(lhs.cmp(rhs)),
+        (Value::Str(lhs), Value::Str(rhs)) => Some(lhs.cmp(rhs)),
+        _ => unreachable!("Unsupported comparison"),
     }
 }
 
@@ -156,6 +259,8 @@ pub fn eval_stmt(context: &Context, frame: &mut CallFrame, stmt: &Statement) {
                         _ => unimplemented!("Field access of non-struct value"),
                     }
                 }
+                // mir-build only reads variant fields, when matching.
+                Place::VariantField(..) => unreachable!("Assigning into variant field"),
             }
         }
         StatementKind::DebugPrint(place) => match place {
@@ -169,19 +274,70 @@ pub fn eval_stmt(context: &Context, frame: &mut CallFrame, stmt: &Statement) {
                 }
                 _ => unimplemented!("Field access of non-struct value"),
             },
+            Place::VariantField(source_var_id, index, field_name) => {
+                println!("{}", variant_field(frame, *source_var_id, *index, field_name));
+            }
         },
     }
 }
 
 pub fn eval_fn(context: &Context, frame: &mut CallFrame, fun: &Function) {
-    for block in &fun.basic_blocks {
+    let mut block_id = 0;
+    loop {
+        let block = &fun.basic_blocks[block_id];
         for stmt in &block.statements {
             eval_stmt(context, frame, stmt);
         }
+
+        block_id = match &block.terminator {
+            Some(Terminator {
+                kind: TerminatorKind::Return,
+                ..
+            })
+            | None => return,
+            Some(Terminator {
+                kind: TerminatorKind::Goto(target),
+                ..
+            }) => *target,
+            Some(Terminator {
+                kind: TerminatorKind::If(condition, if_true, if_false),
+                ..
+            }) => match eval_operand(context, frame, condition) {
+                Value::Bool(true) => *if_true,
+                Value::Bool(false) => *if_false,
+                // Conditions type-check as `bool`, and those that
+                // mir-build makes for patterns are `bool` too.
+                _ => unreachable!("Branch on non-boolean value"),
+            },
+            Some(Terminator {
+                kind: TerminatorKind::Switch(place, targets),
+                ..
+            }) => match place {
+                // mir-build only switches on a local holding a value of
+                // the enum whose variant a pattern names.
+                Place::Local(var_id) => match &frame.locals[*var_id] {
+                    Value::Variant(index, _) => targets[*index],
+                    _ => unreachable!("Switch on non-enum value"),
+                },
+                _ => unreachable!("Switch on value other than local variable"),
+            },
+            Some(Terminator {
+                kind: TerminatorKind::Unreachable,
+                ..
+            }) => panic!("reached unreachable block"),
+        };
     }
 }
 
-pub fn eval_context(context: &Context, starting_fn: DefId) {
+/// Runs the function `starting_fn`, which must take no arguments, and
+/// returns the value it produces.
+pub fn eval_context(context: &Context, starting_fn: DefId) -> Value {
+    eval_locals(context, starting_fn).swap_remove(0)
+}
+
+/// Runs `starting_fn`, which takes no arguments, and returns the values
+/// its locals end up with, starting with its return value.
+pub fn eval_locals(context: &Context, starting_fn: DefId) -> Vec<Value> {
     match context.definitions[starting_fn] {
         Definition::Fn(ref f) => {
             let mut frame = CallFrame::new();
@@ -192,6 +348,7 @@ pub fn eval_context(context: &Context, starting_fn: DefId) {
             }
 
             eval_fn(context, &mut frame, f);
+            frame.locals
         }
         _ => unimplemented!("Starting function is not a function definition"),
     }
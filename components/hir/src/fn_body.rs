@@ -0,0 +1,1150 @@
+//! Lowering from the AST of a `def` into its `hir::FnBody`.
+
+use crate::query_definitions;
+use crate::HirDatabase;
+use ast::ast as a;
+use ast::def_id::{DefId, DefIdData};
+use ast::item_id::ItemId;
+use indices::IndexVec;
+use intern::Untern;
+use parser::pos::{HasSpan, Span, Spanned};
+use parser::StringId;
+use prelude::BuiltinType;
+use std::sync::Arc;
+use ty::declaration::Declaration;
+
+crate fn lower(db: &impl HirDatabase, item_id: ItemId, def: &a::Def) -> crate::FnBody {
+    let mut lowering = FnBodyLowering {
+        db,
+        item_id,
+        scope: vec![],
+        loops: vec![],
+        expressions: IndexVec::new(),
+        places: IndexVec::new(),
+        perms: IndexVec::new(),
+        variables: IndexVec::new(),
+        identifiers: IndexVec::new(),
+        patterns: IndexVec::new(),
+        errors: IndexVec::new(),
+    };
+
+    let receiver = def.receiver.map(|receiver| {
+        let name = Spanned {
+            node: db.intern_string("self"),
+            span: receiver.span,
+        };
+        lowering.bind_variable(&name, receiver.span)
+    });
+
+    let arguments: Vec<_> = receiver
+        .into_iter()
+        .chain(
+            def.parameters
+                .iter()
+                .map(|parameter| lowering.bind_variable(&parameter.name, parameter.span)),
+        )
+        .collect();
+
+    let root_expression = lowering.lower_block(&def.body);
+
+    let FnBodyLowering {
+        expressions,
+        places,
+        perms,
+        variables,
+        identifiers,
+        patterns,
+        errors,
+        ..
+    } = lowering;
+
+    crate::FnBody {
+        arguments,
+        root_expression,
+        expressions,
+        places,
+        perms,
+        variables,
+        identifiers,
+        patterns,
+        errors,
+    }
+}
+
+struct FnBodyLowering<'me, DB: HirDatabase> {
+    db: &'me DB,
+
+    /// The item whose body we are lowering; names are resolved
+    /// relative to it.
+    item_id: ItemId,
+
+    /// The variables currently in scope, innermost last.
+    scope: Vec<(StringId, crate::Variable)>,
+
+    /// The loops we are currently inside of, innermost last.
+    loops: Vec<LoopScope>,
+
+    expressions: IndexVec<crate::Expression, Spanned<crate::ExpressionData>>,
+    places: IndexVec<crate::Place, Spanned<crate::PlaceData>>,
+    perms: IndexVec<crate::Perm, Spanned<crate::PermData>>,
+    variables: IndexVec<crate::Variable, Spanned<crate::VariableData>>,
+    identifiers: IndexVec<crate::Identifier, Spanned<crate::IdentifierData>>,
+    patterns: IndexVec<crate::Pattern, Spanned<crate::PatternData>>,
+    errors: IndexVec<crate::Error, Spanned<crate::ErrorData>>,
+}
+
+struct LoopScope {
+    label: Option<StringId>,
+
+    /// The `Loop` expression; `break` and `continue` refer to it.
+    expression: crate::Expression,
+
+    /// False for `while` and `for` loops, which always produce `()`.
+    allows_value: bool,
+}
+
+impl<DB> FnBodyLowering<'_, DB>
+where
+    DB: HirDatabase,
+{
+    fn add_expression(&mut self, node: crate::ExpressionData, span: Span) -> crate::Expression {
+        self.expressions.push(Spanned { node, span })
+    }
+
+    fn add_place(&mut self, node: crate::PlaceData, span: Span) -> crate::Place {
+        self.places.push(Spanned { node, span })
+    }
+
+    fn add_perm(&mut self, node: crate::PermData, span: Span) -> crate::Perm {
+        self.perms.push(Spanned { node, span })
+    }
+
+    fn add_identifier(&mut self, identifier: &a::Identifier) -> crate::Identifier {
+        self.identifiers.push(Spanned {
+            node: crate::IdentifierData {
+                text: identifier.node,
+            },
+            span: identifier.span,
+        })
+    }
+
+    /// Records an error and returns an expression standing in for
+    /// whatever we failed to lower.
+    fn error_expression(&mut self, node: crate::ErrorData, span: Span) -> crate::Expression {
+        let error = self.errors.push(Spanned { node, span });
+        self.add_expression(crate::ExpressionData::Error { error }, span)
+    }
+
+    fn add_pattern(&mut self, node: crate::PatternData, span: Span) -> crate::Pattern {
+        self.patterns.push(Spanned { node, span })
+    }
+
+    /// Records an error and returns a pattern standing in for
+    /// whatever we failed to lower.
+    fn error_pattern(&mut self, node: crate::ErrorData, span: Span) -> crate::Pattern {
+        let error = self.errors.push(Spanned { node, span });
+        self.add_pattern(crate::PatternData::Error { error }, span)
+    }
+
+    fn span(&self, expression: crate::Expression) -> Span {
+        self.expressions[expression].span
+    }
+
+    /// Creates a new variable named `name` and brings it into scope.
+    fn bind_variable(&mut self, name: &a::Identifier, span: Span) -> crate::Variable {
+        let identifier = self.add_identifier(name);
+        let variable = self.variables.push(Spanned {
+            node: crate::VariableData { name: identifier },
+            span,
+        });
+        self.scope.push((name.node, variable));
+        variable
+    }
+
+    /// Creates a variable that no expression can name, for values the
+    /// user did not name themselves, and brings it into scope.
+    fn hidden_variable(&mut self, span: Span) -> crate::Variable {
+        let name = Spanned {
+            node: self.db.intern_string("_"),
+            span,
+        };
+        self.bind_variable(&name, span)
+    }
+
+    fn lower_block(&mut self, block: &Spanned<a::Block>) -> crate::Expression {
+        let scope_len = self.scope.len();
+        let expression = self.lower_block_items(&block.node.expressions, block.span);
+        self.scope.truncate(scope_len);
+        expression
+    }
+
+    /// Lowers the remaining `items` of a block spanning `span`. Each
+    /// `let` scopes over the items that follow it.
+    fn lower_block_items(&mut self, items: &[a::BlockItem], span: Span) -> crate::Expression {
+        let (first, rest) = match items.split_first() {
+            Some(pair) => pair,
+            None => return self.add_expression(crate::ExpressionData::Unit {}, span),
+        };
+
+        match first {
+            a::BlockItem::Expr(expression) if rest.is_empty() => self.lower_expression(expression),
+
+            a::BlockItem::Expr(expression) => {
+                let first = self.lower_expression(expression);
+                self.lower_sequence(first, rest, span)
+            }
+
+            a::BlockItem::Decl(a::Declaration::Let(declaration)) => {
+                let initializer = match &declaration.init {
+                    Some(init) => self.lower_expression(init),
+                    None => self.error_expression(
+                        crate::ErrorData::Unimplemented,
+                        declaration.pattern.span,
+                    ),
+                };
+
+                let ty = declaration.ty.as_ref().map(|ty| self.lower_type(&ty.node));
+
+                match &declaration.pattern.node {
+                    a::Pattern::Identifier(name, _mode) => {
+                        self.lower_let(name, ty, initializer, rest, declaration.pattern.span, span)
+                    }
+
+                    // The initializer still has to have the type the
+                    // user wrote, so it goes in a variable that no
+                    // expression can name.
+                    a::Pattern::Underscore if ty.is_some() => {
+                        let name = Spanned {
+                            node: self.db.intern_string("_"),
+                            span: declaration.pattern.span,
+                        };
+                        self.lower_let(&name, ty, initializer, rest, declaration.pattern.span, span)
+                    }
+
+                    a::Pattern::Underscore => self.lower_sequence(initializer, rest, span),
+
+                    // Only `match` can test whether a value matches
+                    // the other patterns.
+                    _ => {
+                        self.error_expression(
+                            crate::ErrorData::Unimplemented,
+                            declaration.pattern.span,
+                        );
+                        self.lower_sequence(initializer, rest, span)
+                    }
+                }
+            }
+
+            a::BlockItem::Item(item) => {
+                let first = self.error_expression(crate::ErrorData::Unimplemented, item_span(item));
+                self.lower_sequence(first, rest, span)
+            }
+        }
+    }
+
+    /// Lowers `let <name>: <ty> = <initializer>`, where the pattern
+    /// spans `pattern_span`, followed by the remaining `items` of a
+    /// block spanning `span`.
+    fn lower_let(
+        &mut self,
+        name: &a::Identifier,
+        ty: Option<ty::Ty<Declaration>>,
+        initializer: crate::Expression,
+        items: &[a::BlockItem],
+        pattern_span: Span,
+        span: Span,
+    ) -> crate::Expression {
+        let var = self.bind_variable(name, pattern_span);
+        let body = self.lower_block_items(items, span);
+        let let_span = pattern_span.to(self.span(body));
+        self.add_expression(
+            crate::ExpressionData::Let {
+                var,
+                ty,
+                initializer,
+                body,
+            },
+            let_span,
+        )
+    }
+
+    /// Converts a type the user wrote in the body, recording an error
+    /// for each name in it that is not a type and each type with the
+    /// wrong number of type arguments.
+    fn lower_type(&mut self, ty: &a::Type) -> ty::Ty<Declaration> {
+        let mut errors = vec![];
+        query_definitions::type_errors(self.db, self.item_id, ty, &mut errors);
+        for error in errors {
+            self.errors.push(error);
+        }
+        query_definitions::declaration_ty(self.db, self.item_id, ty)
+    }
+
+    /// Lowers `first; rest...`.
+    fn lower_sequence(
+        &mut self,
+        first: crate::Expression,
+        rest: &[a::BlockItem],
+        span: Span,
+    ) -> crate::Expression {
+        let second = self.lower_block_items(rest, span);
+        let sequence_span = self.span(first).to(self.span(second));
+        self.add_expression(crate::ExpressionData::Sequence { first, second }, sequence_span)
+    }
+
+    fn lower_expression(&mut self, expression: &a::Expression) -> crate::Expression {
+        let span = expression.span();
+        match expression {
+            a::Expression::Block(block) => self.lower_block(block),
+
+            a::Expression::ConstructStruct(construct) => {
+                let entity = self.resolve(&construct.path);
+
+                let fields: Vec<_> = construct
+                    .fields
+                    .iter()
+                    .map(|field| match field {
+                        a::ConstructField::Shorthand(name) => {
+                            let identifier = self.add_identifier(name);
+                            (identifier, self.lower_variable_reference(name.node, name.span))
+                        }
+
+                        a::ConstructField::Longhand(field) => {
+                            let identifier = self.add_identifier(&field.name);
+                            let error =
+                                self.error_expression(crate::ErrorData::Unimplemented, field.span);
+                            (identifier, error)
+                        }
+                    })
+                    .collect();
+
+                // Lower the fields even if the struct name does not
+                // resolve, so that we report errors within them too.
+                let entity = match entity {
+                    Ok(def_id) => def_id,
+                    Err(error) => return error,
+                };
+
+                self.add_expression(
+                    crate::ExpressionData::Aggregate {
+                        entity,
+                        fields: Arc::new(fields),
+                    },
+                    span,
+                )
+            }
+
+            a::Expression::Call(call) => match &call.node.callee {
+                a::Callee::Identifier(callee) => {
+                    let callee = a::Path::new(vec![*callee]);
+                    self.lower_call(&callee, &call.node.arguments, span)
+                }
+
+                a::Callee::Path(callee) => self.lower_call(callee, &call.node.arguments, span),
+
+                a::Callee::Method(owner, method) => {
+                    let owner = self.lower_place(owner);
+                    let method = self.add_identifier(method);
+                    let arguments = self.lower_arguments(&call.node.arguments);
+                    self.add_expression(
+                        crate::ExpressionData::MethodCall {
+                            owner,
+                            method,
+                            arguments,
+                        },
+                        span,
+                    )
+                }
+            },
+
+            a::Expression::Field(_) => {
+                let place = self.lower_place(expression);
+                let perm = self.add_perm(crate::PermData::Default, span);
+                self.add_expression(crate::ExpressionData::Place { perm, place }, span)
+            }
+
+            a::Expression::SelfRef(_) => {
+                let text = self.db.intern_string("self");
+                self.lower_variable_reference(text, span)
+            }
+
+            a::Expression::If(if_) => self.lower_if(if_),
+
+            a::Expression::Loop(loop_) => self.lower_loop(loop_),
+
+            a::Expression::Break(break_) => {
+                let value = match &break_.node.value {
+                    Some(value) => Some(self.lower_expression(value)),
+                    None => None,
+                };
+
+                let target = match self.loop_target(
+                    crate::LoopControl::Break,
+                    &break_.node.label,
+                    break_.span,
+                ) {
+                    Ok(scope) => scope,
+                    Err(error) => return error,
+                };
+
+                let value = match value {
+                    Some(value) if !self.loops[target].allows_value => {
+                        return self.error_expression(crate::ErrorData::BreakWithValue, span)
+                    }
+                    Some(value) => value,
+                    None => self.add_expression(crate::ExpressionData::Unit {}, span),
+                };
+
+                let target = self.loops[target].expression;
+                self.add_expression(crate::ExpressionData::Break { target, value }, span)
+            }
+
+            a::Expression::Continue(continue_) => {
+                let target = match self.loop_target(
+                    crate::LoopControl::Continue,
+                    &continue_.node.label,
+                    continue_.span,
+                ) {
+                    Ok(scope) => self.loops[scope].expression,
+                    Err(error) => return error,
+                };
+                self.add_expression(crate::ExpressionData::Continue { target }, span)
+            }
+
+            a::Expression::Ref(name) => self.lower_variable_reference(name.node, name.span),
+
+            // A unit variant, like `Shape::Empty`, is called with no
+            // arguments.
+            a::Expression::Path(path) => self.lower_call(path, &[], span),
+
+            a::Expression::Match(match_) => {
+                let scrutinee = self.lower_place(&match_.node.scrutinee);
+                let arms = match_
+                    .node
+                    .arms
+                    .iter()
+                    .map(|arm| {
+                        // The variables bound by the pattern scope
+                        // over the body of the arm only.
+                        let scope_len = self.scope.len();
+                        let pattern = self.lower_pattern(&arm.pattern);
+                        let body = self.lower_expression(&arm.body);
+                        self.scope.truncate(scope_len);
+                        crate::MatchArm { pattern, body }
+                    })
+                    .collect();
+                self.add_expression(
+                    crate::ExpressionData::Match {
+                        scrutinee,
+                        arms: Arc::new(arms),
+                    },
+                    span,
+                )
+            }
+
+            a::Expression::Binary(operator, left, right) => {
+                let operator = match operator.node {
+                    a::Op::Add => crate::BinaryOperator::Add,
+                    a::Op::Sub => crate::BinaryOperator::Subtract,
+                    a::Op::Mul => crate::BinaryOperator::Multiply,
+                    a::Op::Div => crate::BinaryOperator::Divide,
+                    a::Op::Rem => crate::BinaryOperator::Remainder,
+                    a::Op::Equal => crate::BinaryOperator::Equal,
+                    a::Op::NotEqual => crate::BinaryOperator::NotEqual,
+                    a::Op::Less => crate::BinaryOperator::Less,
+                    a::Op::LessEqual => crate::BinaryOperator::LessEqual,
+                    a::Op::Greater => crate::BinaryOperator::Greater,
+                    a::Op::GreaterEqual => crate::BinaryOperator::GreaterEqual,
+                    a::Op::And => crate::BinaryOperator::And,
+                    a::Op::Or => crate::BinaryOperator::Or,
+                };
+                let left = self.lower_expression(left);
+                let right = self.lower_expression(right);
+                self.add_expression(
+                    crate::ExpressionData::Binary {
+                        operator,
+                        left,
+                        right,
+                    },
+                    span,
+                )
+            }
+
+            a::Expression::Unary(operator, value) => {
+                // `-2147483648` is `i32::MIN`, even though `2147483648`
+                // is out of range on its own, so a negated integer
+                // literal is checked as one negative literal.
+                if let (a::UnaryOp::Neg, a::Expression::Literal(a::Literal::Integer(integer))) =
+                    (operator.node, &**value)
+                {
+                    return match self.integer_literal(integer, true) {
+                        Ok(data) => {
+                            self.add_expression(crate::ExpressionData::Literal { data }, span)
+                        }
+                        Err(error) => self.error_expression(error, span),
+                    };
+                }
+
+                let operator = match operator.node {
+                    a::UnaryOp::Neg => crate::UnaryOperator::Negate,
+                    a::UnaryOp::Not => crate::UnaryOperator::Not,
+                };
+                let value = self.lower_expression(value);
+                self.add_expression(crate::ExpressionData::Unary { operator, value }, span)
+            }
+
+            a::Expression::Interpolation(elements, _) => {
+                let elements = elements
+                    .iter()
+                    .map(|element| match element {
+                        a::InterpolationElement::String(fragment) => {
+                            // Strip the quote or braces on either side.
+                            let text = self.db.untern_string(fragment.node);
+                            let start = if text.starts_with('"') { 1 } else { 2 };
+                            let end = if text.ends_with('"') { 1 } else { 2 };
+                            let value = unescape(&text[start..text.len() - end]);
+                            let value = self.db.intern_string(value.as_str());
+                            self.literal_expression(crate::LiteralKind::String, value, fragment.span)
+                        }
+
+                        a::InterpolationElement::Expression(expression) => {
+                            self.lower_expression(expression)
+                        }
+                    })
+                    .collect();
+                self.add_expression(
+                    crate::ExpressionData::Interpolation {
+                        elements: Arc::new(elements),
+                    },
+                    span,
+                )
+            }
+
+            a::Expression::Error(_) => self.error_expression(crate::ErrorData::SyntaxError, span),
+
+            a::Expression::Literal(literal) => match self.lower_literal(literal) {
+                Ok(data) => self.add_expression(crate::ExpressionData::Literal { data }, span),
+                Err(error) => self.error_expression(error, span),
+            },
+        }
+    }
+
+    /// Converts a literal to its value, or to the error explaining
+    /// why it has none.
+    fn lower_literal(
+        &mut self,
+        literal: &a::Literal,
+    ) -> Result<crate::LiteralData, crate::ErrorData> {
+        match literal {
+            a::Literal::String(value) => {
+                // The token text still includes the surrounding quotes.
+                let text = self.db.untern_string(value.node);
+                let value = unescape(&text[1..text.len() - 1]);
+                let value = self.db.intern_string(value.as_str());
+                Ok(crate::LiteralData { kind: crate::LiteralKind::String, value })
+            }
+
+            a::Literal::Integer(value) => self.integer_literal(value, false),
+
+            a::Literal::Float(value) => {
+                let text = self.db.untern_string(value.node).replace('_', "");
+                match text.parse::<f64>() {
+                    Ok(float) if float.is_finite() => {
+                        let value = self.db.intern_string(text.as_str());
+                        Ok(crate::LiteralData { kind: crate::LiteralKind::Float, value })
+                    }
+                    _ => Err(crate::ErrorData::LiteralOutOfRange {
+                        ty: BuiltinType::F64,
+                    }),
+                }
+            }
+
+            a::Literal::Bool(value) => {
+                let value = self.db.intern_string(if value.node { "true" } else { "false" });
+                Ok(crate::LiteralData { kind: crate::LiteralKind::Bool, value })
+            }
+        }
+    }
+
+    /// Converts an integer literal, negated if `negative`, to its
+    /// value, or to the error explaining why it has none.
+    fn integer_literal(
+        &mut self,
+        literal: &Spanned<StringId>,
+        negative: bool,
+    ) -> Result<crate::LiteralData, crate::ErrorData> {
+        let text = self.db.untern_string(literal.node);
+        let integer = integer_value(&text).map(|integer| {
+            let integer = i128::from(integer);
+            if negative {
+                -integer
+            } else {
+                integer
+            }
+        });
+        match integer {
+            Some(integer)
+                if i128::from(i32::min_value()) <= integer
+                    && integer <= i128::from(i32::max_value()) =>
+            {
+                let value = self.db.intern_string(integer.to_string().as_str());
+                Ok(crate::LiteralData { kind: crate::LiteralKind::Integer, value })
+            }
+            _ => Err(crate::ErrorData::LiteralOutOfRange {
+                ty: BuiltinType::I32,
+            }),
+        }
+    }
+
+    /// Lowers a call to the function, struct or variant at `callee`.
+    fn lower_call(
+        &mut self,
+        callee: &a::Path,
+        arguments: &[a::Expression],
+        span: Span,
+    ) -> crate::Expression {
+        let function = self.resolve(callee);
+        let arguments = self.lower_arguments(arguments);
+
+        let function = match function {
+            Ok(def_id) => def_id,
+            Err(error) => return error,
+        };
+
+        self.add_expression(
+            crate::ExpressionData::Call {
+                function,
+                arguments,
+            },
+            span,
+        )
+    }
+
+    /// Lowers a pattern, bringing the variables it binds into scope.
+    fn lower_pattern(&mut self, pattern: &Spanned<a::Pattern>) -> crate::Pattern {
+        let span = pattern.span;
+        match &pattern.node {
+            a::Pattern::Underscore => self.add_pattern(crate::PatternData::Wildcard, span),
+
+            a::Pattern::Identifier(name, _mode) => {
+                let var = self.bind_variable(name, span);
+                self.add_pattern(crate::PatternData::Binding { var }, span)
+            }
+
+            a::Pattern::Literal(literal) => match self.lower_literal(literal) {
+                Ok(data) => self.add_pattern(crate::PatternData::Literal { data }, span),
+                Err(error) => self.error_pattern(error, span),
+            },
+
+            a::Pattern::Path(path) => self.lower_constructor_pattern(path, vec![], span),
+
+            a::Pattern::Tuple(path, patterns) => {
+                let fields = patterns
+                    .iter()
+                    .enumerate()
+                    .map(|(index, pattern)| {
+                        let name = Spanned {
+                            node: self.db.intern_string(index.to_string().as_str()),
+                            span: pattern.span,
+                        };
+                        (self.add_identifier(&name), self.lower_pattern(pattern))
+                    })
+                    .collect();
+                self.lower_constructor_pattern(path, fields, span)
+            }
+
+            a::Pattern::Struct(path, fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|field| {
+                        let name = self.add_identifier(&field.name);
+                        let pattern = match &field.pattern {
+                            Some(pattern) => self.lower_pattern(pattern),
+
+                            // `Foo { a }` binds the field `a` to a
+                            // variable of the same name.
+                            None => {
+                                let var = self.bind_variable(&field.name, field.name.span);
+                                self.add_pattern(
+                                    crate::PatternData::Binding { var },
+                                    field.name.span,
+                                )
+                            }
+                        };
+                        (name, pattern)
+                    })
+                    .collect();
+                self.lower_constructor_pattern(path, fields, span)
+            }
+        }
+    }
+
+    /// Lowers a pattern matching the struct or variant at `path`. The
+    /// patterns for its `fields` are lowered first, so that their
+    /// variables are in scope even if `path` does not resolve.
+    fn lower_constructor_pattern(
+        &mut self,
+        path: &a::Path,
+        fields: Vec<(crate::Identifier, crate::Pattern)>,
+        span: Span,
+    ) -> crate::Pattern {
+        let entity = match self.resolve_path(path) {
+            Ok(def_id) => def_id,
+            Err(error) => return self.error_pattern(error.node, error.span),
+        };
+
+        if !self.is_constructor(entity) {
+            let name = path.components.last().expect("empty path");
+            return self.error_pattern(
+                crate::ErrorData::NotAConstructor { text: name.node },
+                path.span(),
+            );
+        }
+
+        self.add_pattern(
+            crate::PatternData::Constructor {
+                entity,
+                fields: Arc::new(fields),
+            },
+            span,
+        )
+    }
+
+    /// True if `def_id` is a struct or a variant, which patterns can
+    /// match.
+    fn is_constructor(&self, def_id: DefId) -> bool {
+        match def_id.untern(self.db) {
+            DefIdData::Variant { .. } => true,
+            DefIdData::Item(item_id) => match &*self.db.ast_of_item(item_id) {
+                a::Item::Struct(_) => true,
+                a::Item::Enum(_) | a::Item::Def(_) | a::Item::Mod(_) | a::Item::Use(_) => {
+                    false
+                }
+            },
+            DefIdData::Builtin(_) | DefIdData::Field { .. } | DefIdData::TypeParameter { .. } => {
+                false
+            }
+        }
+    }
+
+    /// Lowers an `if`; a missing `else` behaves like an empty block.
+    fn lower_if(&mut self, if_: &Spanned<a::If>) -> crate::Expression {
+        match &if_.node {
+            a::If::If(condition, block, els) => {
+                let condition = self.lower_expression(condition);
+                let if_true = self.lower_block(block);
+                let if_false = self.lower_else(els, if_.span);
+                self.add_expression(
+                    crate::ExpressionData::If {
+                        condition,
+                        if_true,
+                        if_false,
+                    },
+                    if_.span,
+                )
+            }
+
+            // `if let P = e { a } else { b }` is `match e { P => a, _ => b }`,
+            // so a pattern that always matches makes the `_` arm
+            // unreachable, as it would be in the `match`.
+            a::If::IfLet(pattern, value, block, els) => {
+                let scrutinee = self.lower_place(value);
+
+                // The variables bound by the pattern scope over the
+                // block only.
+                let scope_len = self.scope.len();
+                let pattern = self.lower_pattern(pattern);
+                let if_true = self.lower_block(block);
+                self.scope.truncate(scope_len);
+
+                let if_false = self.lower_else(els, if_.span);
+                let wildcard = self.add_pattern(crate::PatternData::Wildcard, self.span(if_false));
+                let arms = vec![
+                    crate::MatchArm {
+                        pattern,
+                        body: if_true,
+                    },
+                    crate::MatchArm {
+                        pattern: wildcard,
+                        body: if_false,
+                    },
+                ];
+                self.add_expression(
+                    crate::ExpressionData::Match {
+                        scrutinee,
+                        arms: Arc::new(arms),
+                    },
+                    if_.span,
+                )
+            }
+        }
+    }
+
+    /// Lowers the `else` of an `if` spanning `span`; without one, the
+    /// `if` produces `()` when it does not run its block.
+    fn lower_else(&mut self, els: &Option<a::ChainedElse>, span: Span) -> crate::Expression {
+        match els {
+            Some(a::ChainedElse::Block(block)) => self.lower_block(block),
+            Some(a::ChainedElse::If(if_)) => self.lower_if(if_),
+            None => self.add_expression(crate::ExpressionData::Unit {}, span),
+        }
+    }
+
+    /// Lowers a loop. A `while` loop becomes a `loop` whose body is
+    /// `if <condition> { <body> } else { break }`, and a `for` loop is
+    /// lowered as described at `lower_for_body`.
+    fn lower_loop(&mut self, loop_: &Spanned<a::Loop>) -> crate::Expression {
+        let span = loop_.span;
+
+        // The range of a `for` loop is evaluated once, before the
+        // loop, into variables that no expression can name.
+        let scope_len = self.scope.len();
+        let range = match &loop_.node.kind {
+            a::LoopKind::For(_, start, end) => {
+                let start = self.lower_expression(start);
+                let end = self.lower_expression(end);
+                let counter = self.hidden_variable(span);
+                let limit = self.hidden_variable(span);
+                Some(((counter, start), (limit, end)))
+            }
+            a::LoopKind::Loop | a::LoopKind::While(_) => None,
+        };
+
+        // `break` and `continue` in the body refer to the loop, so we
+        // need its index before lowering the body; the placeholder is
+        // replaced below.
+        let expression = self.add_expression(crate::ExpressionData::Unit {}, span);
+        self.loops.push(LoopScope {
+            label: loop_.node.label.map(|label| label.node),
+            expression,
+            allows_value: match loop_.node.kind {
+                a::LoopKind::Loop => true,
+                a::LoopKind::While(_) | a::LoopKind::For(..) => false,
+            },
+        });
+
+        let body = match &loop_.node.kind {
+            a::LoopKind::Loop => self.lower_block(&loop_.node.body),
+            a::LoopKind::While(condition) => {
+                let condition = self.lower_expression(condition);
+                let if_true = self.lower_block(&loop_.node.body);
+                let if_false = self.lower_break(expression, span);
+                self.add_expression(
+                    crate::ExpressionData::If {
+                        condition,
+                        if_true,
+                        if_false,
+                    },
+                    span,
+                )
+            }
+            a::LoopKind::For(pattern, ..) => {
+                let ((counter, _), (limit, _)) = range.expect("`for` without a range");
+                self.lower_for_body(counter, limit, pattern, &loop_.node.body, expression, span)
+            }
+        };
+
+        self.loops.pop();
+        self.expressions[expression].node = crate::ExpressionData::Loop { body };
+
+        let expression = match range {
+            Some(((counter, start), (limit, end))) => {
+                let body = self.add_expression(
+                    crate::ExpressionData::Let {
+                        var: limit,
+                        ty: None,
+                        initializer: end,
+                        body: expression,
+                    },
+                    span,
+                );
+                self.add_expression(
+                    crate::ExpressionData::Let {
+                        var: counter,
+                        ty: None,
+                        initializer: start,
+                        body,
+                    },
+                    span,
+                )
+            }
+            None => expression,
+        };
+        self.scope.truncate(scope_len);
+        expression
+    }
+
+    /// Lowers the body of `for <pattern> in <start>..<end> <body>`,
+    /// where `counter` starts out holding `<start>` and `limit` holds
+    /// `<end>`, as
+    ///
+    /// ```ignore
+    /// if counter < limit {
+    ///     let current = counter;
+    ///     counter = counter + 1;
+    ///     match current { <pattern> => <body> }
+    /// } else {
+    ///     break
+    /// }
+    /// ```
+    ///
+    /// The counter moves on before the body runs, so `continue` does
+    /// not need to. A pattern that could fail to match is reported by
+    /// the checks on `match`.
+    fn lower_for_body(
+        &mut self,
+        counter: crate::Variable,
+        limit: crate::Variable,
+        pattern: &Spanned<a::Pattern>,
+        body: &Spanned<a::Block>,
+        target: crate::Expression,
+        span: Span,
+    ) -> crate::Expression {
+        let left = self.variable_expression(counter, span);
+        let right = self.variable_expression(limit, span);
+        let condition = self.add_expression(
+            crate::ExpressionData::Binary {
+                operator: crate::BinaryOperator::Less,
+                left,
+                right,
+            },
+            span,
+        );
+
+        let initializer = self.variable_expression(counter, span);
+        let current = self.hidden_variable(pattern.span);
+
+        let left = self.variable_expression(counter, span);
+        let value = self.db.intern_string("1");
+        let right = self.add_expression(
+            crate::ExpressionData::Literal {
+                data: crate::LiteralData {
+                    kind: crate::LiteralKind::Integer,
+                    value,
+                },
+            },
+            span,
+        );
+        let value = self.add_expression(
+            crate::ExpressionData::Binary {
+                operator: crate::BinaryOperator::Add,
+                left,
+                right,
+            },
+            span,
+        );
+        let place = self.add_place(crate::PlaceData::Variable(counter), span);
+        let first = self.add_expression(crate::ExpressionData::Assignment { place, value }, span);
+
+        // The variables bound by the pattern scope over the body only.
+        let scope_len = self.scope.len();
+        let scrutinee = self.add_place(crate::PlaceData::Variable(current), pattern.span);
+        let pattern = self.lower_pattern(pattern);
+        let body = self.lower_block(body);
+        self.scope.truncate(scope_len);
+        let second = self.add_expression(
+            crate::ExpressionData::Match {
+                scrutinee,
+                arms: Arc::new(vec![crate::MatchArm { pattern, body }]),
+            },
+            span,
+        );
+
+        let body = self.add_expression(crate::ExpressionData::Sequence { first, second }, span);
+        let if_true = self.add_expression(
+            crate::ExpressionData::Let {
+                var: current,
+                ty: None,
+                initializer,
+                body,
+            },
+            span,
+        );
+        let if_false = self.lower_break(target, span);
+        self.add_expression(
+            crate::ExpressionData::If {
+                condition,
+                if_true,
+                if_false,
+            },
+            span,
+        )
+    }
+
+    /// A `break` out of the loop `target` that produces `()`.
+    fn lower_break(&mut self, target: crate::Expression, span: Span) -> crate::Expression {
+        let value = self.add_expression(crate::ExpressionData::Unit {}, span);
+        self.add_expression(crate::ExpressionData::Break { target, value }, span)
+    }
+
+    /// Finds the loop that a `break` or `continue` with the given
+    /// `label` refers to, as an index into `self.loops`.
+    fn loop_target(
+        &mut self,
+        keyword: crate::LoopControl,
+        label: &Option<a::Identifier>,
+        span: Span,
+    ) -> Result<usize, crate::Expression> {
+        match label {
+            Some(label) => match self
+                .loops
+                .iter()
+                .rposition(|scope| scope.label == Some(label.node))
+            {
+                Some(index) => Ok(index),
+                None => Err(self.error_expression(
+                    crate::ErrorData::UnknownLabel { text: label.node },
+                    label.span,
+                )),
+            },
+
+            None if self.loops.is_empty() => {
+                Err(self.error_expression(crate::ErrorData::OutsideLoop { keyword }, span))
+            }
+
+            None => Ok(self.loops.len() - 1),
+        }
+    }
+
+    fn literal_expression(
+        &mut self,
+        kind: crate::LiteralKind,
+        value: StringId,
+        span: Span,
+    ) -> crate::Expression {
+        self.add_expression(
+            crate::ExpressionData::Literal {
+                data: crate::LiteralData { kind, value },
+            },
+            span,
+        )
+    }
+
+    /// Lowers a reference to the local variable `name`.
+    fn lower_variable_reference(&mut self, text: StringId, span: Span) -> crate::Expression {
+        match self.variable(text) {
+            Some(variable) => self.variable_expression(variable, span),
+            None => self.error_expression(crate::ErrorData::UnknownIdentifier { text }, span),
+        }
+    }
+
+    /// An expression that reads `variable`.
+    fn variable_expression(&mut self, variable: crate::Variable, span: Span) -> crate::Expression {
+        let place = self.add_place(crate::PlaceData::Variable(variable), span);
+        let perm = self.add_perm(crate::PermData::Default, span);
+        self.add_expression(crate::ExpressionData::Place { perm, place }, span)
+    }
+
+    /// The variable in scope named `text`, if any.
+    fn variable(&self, text: StringId) -> Option<crate::Variable> {
+        self.scope
+            .iter()
+            .rev()
+            .find(|(name, _)| *name == text)
+            .map(|&(_, variable)| variable)
+    }
+
+    /// Lowers `expression` as a place, like the `a.b` in `a.b.c`.
+    /// Expressions that do not name a place are evaluated into a
+    /// temporary.
+    fn lower_place(&mut self, expression: &a::Expression) -> crate::Place {
+        let span = expression.span();
+        let variable = match expression {
+            a::Expression::Ref(name) => self.variable(name.node),
+            a::Expression::SelfRef(_) => self.variable(self.db.intern_string("self")),
+            a::Expression::Field(field) => {
+                let owner = self.lower_place(&field.node.owner);
+                let name = self.add_identifier(&field.node.name);
+                return self.add_place(crate::PlaceData::Field { owner, name }, span);
+            }
+            _ => None,
+        };
+
+        match variable {
+            Some(variable) => self.add_place(crate::PlaceData::Variable(variable), span),
+            None => {
+                let value = self.lower_expression(expression);
+                self.add_place(crate::PlaceData::Temporary(value), span)
+            }
+        }
+    }
+
+    fn lower_arguments(&mut self, arguments: &[a::Expression]) -> Arc<Vec<crate::Expression>> {
+        Arc::new(
+            arguments
+                .iter()
+                .map(|argument| self.lower_expression(argument))
+                .collect(),
+        )
+    }
+
+    /// Resolves `path` to an item or variant; if that fails, records
+    /// an error and returns the expression standing in for it.
+    fn resolve(&mut self, path: &a::Path) -> Result<DefId, crate::Expression> {
+        self.resolve_path(path)
+            .map_err(|error| self.error_expression(error.node, error.span))
+    }
+
+    /// Resolves `path` to an item, or to a variant of an enum, like
+    /// `Shape::Circle`. Modules are not values, so a path naming one
+    /// is an error.
+    fn resolve_path(&self, path: &a::Path) -> Result<DefId, Spanned<crate::ErrorData>> {
+        let def_id = crate::query_definitions::resolve_path(self.db, self.item_id, path)?;
+        if crate::query_definitions::is_module(self.db, def_id) {
+            let name = path.components.last().expect("empty path");
+            return Err(Spanned {
+                node: crate::ErrorData::ModuleAsValue { text: name.node },
+                span: path.span(),
+            });
+        }
+
+        Ok(def_id)
+    }
+}
+
+fn item_span(item: &a::Item) -> Span {
+    match item {
+        a::Item::Struct(s) => s.span,
+        a::Item::Enum(e) => e.span,
+        a::Item::Def(d) => d.span,
+        a::Item::Mod(m) => m.span,
+        a::Item::Use(u) => u.span,
+    }
+}
+
+/// The text of a string literal from its source `text`, replacing
+/// escapes like `\{` with the character they stand for. A backslash
+/// before any other character is kept as it is.
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => result.push('\n'),
+                Some('t') => result.push('\t'),
+                Some(c @ '\\') | Some(c @ '"') | Some(c @ '{') | Some(c @ '}') => result.push(c),
+                Some(other) => {
+                    result.push('\\');
+                    result.push(other);
+                }
+                None => result.push('\\'),
+            },
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+/// The value of the integer literal `text`, like `1_000` or `0xff`, or
+/// `None` if it does not fit in a `u64`.
+fn integer_value(text: &str) -> Option<u64> {
+    let (digits, radix) = if text.starts_with("0x") {
+        (&text[2..], 16)
+    } else if text.starts_with("0b") {
+        (&text[2..], 2)
+    } else {
+        (text, 10)
+    };
+    u64::from_str_radix(&digits.replace('_', ""), radix).ok()
+}
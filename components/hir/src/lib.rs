@@ -5,31 +5,89 @@
 #![feature(const_let)]
 #![feature(macro_at_most_once_rep)]
 
+use ast::def_id::DefId;
 use ast::AstDatabase;
 use indices::{IndexVec, U32Index};
-use mir::DefId;
+use intern::Has;
 use parser::pos::{Span, Spanned};
 use parser::StringId;
+use prelude::BuiltinType;
 use std::sync::Arc;
 use ty::declaration::Declaration;
+use ty::interners::TyInternTables;
 
+mod fn_body;
 mod query_definitions;
 
 salsa::query_group! {
-    pub trait HirDatabase: AstDatabase {
+    pub trait HirDatabase: AstDatabase + Has<TyInternTables> {
         /// Get the def-id for the built-in boolean type.
         fn boolean_def_id(key: ()) -> DefId {
             type BooleanDefIdQuery;
             use fn query_definitions::boolean_def_id;
         }
 
+        /// Get the def-id for the built-in unit type `()`.
+        fn unit_def_id(key: ()) -> DefId {
+            type UnitDefIdQuery;
+            use fn query_definitions::unit_def_id;
+        }
+
+        /// Get the def-id for the built-in `i32` type.
+        fn i32_def_id(key: ()) -> DefId {
+            type I32DefIdQuery;
+            use fn query_definitions::i32_def_id;
+        }
+
+        /// Get the def-id for the built-in `f64` type.
+        fn f64_def_id(key: ()) -> DefId {
+            type F64DefIdQuery;
+            use fn query_definitions::f64_def_id;
+        }
+
+        /// Get the def-id for the built-in `String` type.
+        fn string_def_id(key: ()) -> DefId {
+            type StringDefIdQuery;
+            use fn query_definitions::string_def_id;
+        }
+
+        /// Get the file that holds the items of the module declared by
+        /// the `mod` item `key`, if that file is one of the inputs.
+        fn module_file(key: DefId) -> Option<StringId> {
+            type ModuleFileQuery;
+            use fn query_definitions::module_file;
+        }
+
+        /// Get the `mod` item whose items are in the file `path`, or
+        /// `None` if the file is the root of a program.
+        fn parent_module(path: StringId) -> Option<DefId> {
+            type ParentModuleQuery;
+            use fn query_definitions::parent_module;
+        }
+
+        /// Get the names of the modules from the root of the program
+        /// down to the one whose items are in the file `path`. This is
+        /// empty for the root itself.
+        fn module_path(path: StringId) -> Arc<Vec<StringId>> {
+            type ModulePathQuery;
+            use fn query_definitions::module_path;
+        }
+
+        /// Get the def-ids of the items in a file, each followed by
+        /// the methods defined within it.
+        fn defs_in_file(path: StringId) -> Arc<Vec<DefId>> {
+            type DefsInFileQuery;
+            use fn query_definitions::defs_in_file;
+        }
+
         /// Get the fn-body for a given def-id.
         fn fn_body(key: DefId) -> Arc<FnBody> {
             type FnBodyQuery;
             use fn query_definitions::fn_body;
         }
 
-        /// Get the list of member names and their def-ids for a given struct.
+        /// Get the list of member names and their def-ids for a given
+        /// struct, enum or variant.
         fn members(key: DefId) -> Arc<Vec<Member>> {
             type MembersQuery;
             use fn query_definitions::members;
@@ -48,11 +106,19 @@ salsa::query_group! {
         }
 
         /// Get the signature of a method or function -- defined for fields and structs.
+        /// The first input of a method is its `self` parameter.
         fn signature(key: DefId) -> ty::Signature<Declaration> {
             type SignatureQuery;
             use fn query_definitions::signature;
         }
 
+        /// Get the errors found while resolving the declaration of an
+        /// item -- e.g., types that name nothing.
+        fn item_errors(key: DefId) -> Arc<Vec<Spanned<ErrorData>>> {
+            type ItemErrorsQuery;
+            use fn query_definitions::item_errors;
+        }
+
         /// Get the generic declarations from a particular item.
         fn generic_declarations(key: DefId) -> Arc<ty::GenericDeclarations> {
             type GenericDeclarations;
@@ -61,10 +127,25 @@ salsa::query_group! {
     }
 }
 
+/// The files that may hold the items of `mod name` when it appears in
+/// the file `path`, in the order we look for them: `name.lark` next to
+/// `path`, then `name/mod.lark`.
+pub fn module_file_candidates(path: &str, name: &str) -> Vec<String> {
+    let dir = match path.rfind('/') {
+        Some(index) => &path[..=index],
+        None => "",
+    };
+    vec![
+        format!("{}{}.lark", dir, name),
+        format!("{}{}/mod.lark", dir, name),
+    ]
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum MemberKind {
     Field,
     Method,
+    Variant,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
@@ -98,6 +179,12 @@ pub struct FnBody {
 
     /// Map each identifier index to its associated data.
     pub identifiers: IndexVec<Identifier, Spanned<IdentifierData>>,
+
+    /// Map each pattern index to its associated data.
+    pub patterns: IndexVec<Pattern, Spanned<PatternData>>,
+
+    /// Map each error index to its associated data.
+    pub errors: IndexVec<Error, Spanned<ErrorData>>,
 }
 
 /// Trait implemented by the various kinds of indices that reach into
@@ -189,6 +276,8 @@ define_meta_index! {
     (Perm, PermData, perms),
     (Variable, VariableData, variables),
     (Identifier, IdentifierData, identifiers),
+    (Pattern, PatternData, patterns),
+    (Error, ErrorData, errors),
 }
 
 indices::index_type! {
@@ -197,9 +286,11 @@ indices::index_type! {
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum ExpressionData {
-    /// `let <var> = <initializer> in <body>`
+    /// `let <var>: <ty> = <initializer> in <body>`, where `ty` is
+    /// the type the user wrote, if any.
     Let {
         var: Variable,
+        ty: Option<ty::Ty<Declaration>>,
         initializer: Expression,
         body: Expression,
     },
@@ -210,6 +301,19 @@ pub enum ExpressionData {
     /// `<place> = <value>`
     Assignment { place: Place, value: Expression },
 
+    /// `foo(<args>)` where `foo` names a function (or a struct,
+    /// which is called with one argument per field)
+    Call {
+        function: DefId,
+        arguments: Arc<Vec<Expression>>,
+    },
+
+    /// `Foo { a: <expr>, b: <expr> }`
+    Aggregate {
+        entity: DefId,
+        fields: Arc<Vec<(Identifier, Expression)>>,
+    },
+
     /// `<place>.method(<args>)`
     MethodCall {
         owner: Place,
@@ -230,8 +334,149 @@ pub enum ExpressionData {
         if_false: Expression,
     },
 
+    /// `match <scrutinee> { <arms> }`
+    Match {
+        scrutinee: Place,
+        arms: Arc<Vec<MatchArm>>,
+    },
+
+    /// `loop { <body> }`; `while` loops are lowered into this too.
+    Loop { body: Expression },
+
+    /// `break <value>`, which exits the loop `target` and makes
+    /// `value` the result of that loop.
+    Break { target: Expression, value: Expression },
+
+    /// `continue`, which starts the next iteration of the loop
+    /// `target`.
+    Continue { target: Expression },
+
+    /// `<left> <operator> <right>`
+    Binary {
+        operator: BinaryOperator,
+        left: Expression,
+        right: Expression,
+    },
+
+    /// `<operator> <value>`
+    Unary {
+        operator: UnaryOperator,
+        value: Expression,
+    },
+
+    /// `"foo"` and other literals
+    Literal { data: LiteralData },
+
+    /// `"a {{b}} c"`: the string made by joining `elements`. The text
+    /// between the expressions becomes string literals.
+    Interpolation { elements: Arc<Vec<Expression>> },
+
     /// `()`
     Unit {},
+
+    /// Something went wrong while lowering this expression; the
+    /// error has already been recorded.
+    Error { error: Error },
+}
+
+/// `<pattern> => <body>`, one of the arms of a `match`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub body: Expression,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BinaryOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+
+    /// `&&`, which only evaluates its right operand if the left one is
+    /// `true`.
+    And,
+
+    /// `||`, which only evaluates its right operand if the left one is
+    /// `false`.
+    Or,
+}
+
+impl BinaryOperator {
+    /// How the operator is written, like `<=`.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinaryOperator::Add => "+",
+            BinaryOperator::Subtract => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+            BinaryOperator::Remainder => "%",
+            BinaryOperator::Equal => "==",
+            BinaryOperator::NotEqual => "!=",
+            BinaryOperator::Less => "<",
+            BinaryOperator::LessEqual => "<=",
+            BinaryOperator::Greater => ">",
+            BinaryOperator::GreaterEqual => ">=",
+            BinaryOperator::And => "&&",
+            BinaryOperator::Or => "||",
+        }
+    }
+
+    /// The operators that compare their operands, producing a `bool`.
+    pub fn is_comparison(self) -> bool {
+        match self {
+            BinaryOperator::Equal
+            | BinaryOperator::NotEqual
+            | BinaryOperator::Less
+            | BinaryOperator::LessEqual
+            | BinaryOperator::Greater
+            | BinaryOperator::GreaterEqual => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum UnaryOperator {
+    Negate,
+    Not,
+}
+
+impl UnaryOperator {
+    /// How the operator is written, like `!`.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            UnaryOperator::Negate => "-",
+            UnaryOperator::Not => "!",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LiteralData {
+    pub kind: LiteralKind,
+    pub value: StringId,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum LiteralKind {
+    String,
+
+    /// The value is in decimal, without separators.
+    Integer,
+
+    /// The value is the text of the literal, without separators.
+    Float,
+
+    /// The value is `true` or `false`.
+    Bool,
 }
 
 indices::index_type! {
@@ -258,6 +503,35 @@ pub enum PlaceData {
     Field { owner: Place, name: Identifier },
 }
 
+indices::index_type! {
+    pub struct Pattern { .. }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PatternData {
+    /// `_`, which matches anything.
+    Wildcard,
+
+    /// `x`, which matches anything and binds it to `var`.
+    Binding { var: Variable },
+
+    /// `"foo"` and other literals, which match only their value.
+    Literal { data: LiteralData },
+
+    /// `Foo { a: <pattern> }` or `Foo::Bar(<pattern>)`, which
+    /// matches the struct or variant `entity` if its fields match.
+    /// Fields without a pattern match anything. The fields of a tuple
+    /// variant are named `0`, `1` and so on.
+    Constructor {
+        entity: DefId,
+        fields: Arc<Vec<(Identifier, Pattern)>>,
+    },
+
+    /// Something went wrong while lowering this pattern; the error
+    /// has already been recorded.
+    Error { error: Error },
+}
+
 indices::index_type! {
     pub struct Variable { .. }
 }
@@ -275,3 +549,154 @@ indices::index_type! {
 pub struct IdentifierData {
     pub text: StringId,
 }
+
+indices::index_type! {
+    pub struct Error { .. }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorData {
+    /// A name that does not refer to any variable or item in scope.
+    UnknownIdentifier { text: StringId },
+
+    /// A path like `Shape::Circle` where `Shape` has no variant
+    /// `Circle`.
+    UnknownVariant { owner: StringId, text: StringId },
+
+    /// A pattern naming something other than a struct or variant.
+    NotAConstructor { text: StringId },
+
+    /// A path like `shapes::Square` where the module `shapes` has no
+    /// item `Square`.
+    UnknownItem { module: StringId, text: StringId },
+
+    /// A path that names an item that is not `pub` from outside the
+    /// module it is in.
+    PrivateItem { text: StringId },
+
+    /// A path that names a module where a value is expected.
+    ModuleAsValue { text: StringId },
+
+    /// `crate` on its own, rather than at the start of a longer path.
+    BareCrate,
+
+    /// A `mod` item whose file is not one of the inputs; `expected`
+    /// is where we looked for it first.
+    ModuleFileNotFound { name: StringId, expected: StringId },
+
+    /// A method marked `pub`; methods are visible wherever their
+    /// struct is.
+    PubMethod,
+
+    /// A type name that does not refer to any type in scope.
+    UnknownType { text: StringId },
+
+    /// A type given a different number of type arguments than it has
+    /// type parameters, like `Vec<i32, i32>`.
+    WrongNumberOfTypeArguments { expected: usize, found: usize },
+
+    /// A numeric literal whose value does not fit in its type.
+    LiteralOutOfRange { ty: BuiltinType },
+
+    /// A `break` or `continue` that is not inside a loop.
+    OutsideLoop { keyword: LoopControl },
+
+    /// A `break` or `continue` naming a label that no enclosing loop
+    /// has.
+    UnknownLabel { text: StringId },
+
+    /// A `break` with a value out of a `while` or `for` loop, which
+    /// always produce `()`.
+    BreakWithValue,
+
+    /// A `self` parameter on a `def` that is not a method.
+    ReceiverOutsideMethod,
+
+    /// A method without a `self` parameter.
+    MissingReceiver,
+
+    /// Valid syntax that we do not yet know how to lower.
+    Unimplemented,
+
+    /// Text that did not parse. `parse_errors` already explains what
+    /// is wrong with it.
+    SyntaxError,
+}
+
+/// The keywords that jump out of the body of a loop.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum LoopControl {
+    Break,
+    Continue,
+}
+
+impl LoopControl {
+    pub fn keyword(self) -> &'static str {
+        match self {
+            LoopControl::Break => "break",
+            LoopControl::Continue => "continue",
+        }
+    }
+}
+
+impl ErrorData {
+    /// Describes this error to the user.
+    pub fn message(self, db: &impl HirDatabase) -> String {
+        match self {
+            ErrorData::UnknownIdentifier { text } => {
+                format!("cannot find `{}` in this scope", db.untern_string(text))
+            }
+            ErrorData::UnknownVariant { owner, text } => format!(
+                "no variant named `{}` in `{}`",
+                db.untern_string(text),
+                db.untern_string(owner)
+            ),
+            ErrorData::NotAConstructor { text } => format!(
+                "expected a struct or variant, found `{}`",
+                db.untern_string(text)
+            ),
+            ErrorData::UnknownItem { module, text } => format!(
+                "cannot find `{}` in `{}`",
+                db.untern_string(text),
+                db.untern_string(module)
+            ),
+            ErrorData::PrivateItem { text } => format!("`{}` is private", db.untern_string(text)),
+            ErrorData::ModuleAsValue { text } => {
+                format!("expected a value, found module `{}`", db.untern_string(text))
+            }
+            ErrorData::BareCrate => "`crate` must be followed by `::` and a name".to_string(),
+            ErrorData::ModuleFileNotFound { name, expected } => format!(
+                "file not found for module `{}`: expected `{}`",
+                db.untern_string(name),
+                db.untern_string(expected)
+            ),
+            ErrorData::PubMethod => {
+                "methods cannot be `pub`; they are visible wherever their struct is".to_string()
+            }
+            ErrorData::UnknownType { text } => {
+                format!("cannot find type `{}` in this scope", db.untern_string(text))
+            }
+            ErrorData::WrongNumberOfTypeArguments { expected, found } => {
+                format!("expected {} type arguments, found {}", expected, found)
+            }
+            ErrorData::LiteralOutOfRange { ty } => {
+                format!("literal out of range for `{}`", ty.name())
+            }
+            ErrorData::OutsideLoop { keyword } => {
+                format!("`{}` outside of a loop", keyword.keyword())
+            }
+            ErrorData::UnknownLabel { text } => {
+                format!("use of undeclared label `{}`", db.untern_string(text))
+            }
+            ErrorData::BreakWithValue => {
+                "`break` with a value is only allowed in `loop`".to_string()
+            }
+            ErrorData::ReceiverOutsideMethod => {
+                "`self` parameter is only allowed in methods".to_string()
+            }
+            ErrorData::MissingReceiver => "methods must have a `self` parameter".to_string(),
+            ErrorData::Unimplemented => "this syntax is not yet supported".to_string(),
+            ErrorData::SyntaxError => "syntax error".to_string(),
+        }
+    }
+}
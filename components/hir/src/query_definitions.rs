@@ -1,19 +1,130 @@
 use crate::HirDatabase;
-use mir::DefId;
+use ast::ast as a;
+use ast::def_id::{DefId, DefIdData};
+use ast::item_id::{ItemId, ItemIdData};
+use intern::{Intern, Untern};
+use parser::pos::Spanned;
 use parser::StringId;
+use prelude::{BuiltinFn, BuiltinType};
 use std::sync::Arc;
 use ty::declaration::Declaration;
+use ty::{
+    BaseData, BaseKind, BoundVar, BoundVarOr, Erased, GenericDeclarations, GenericKind,
+    GenericTyDeclaration, Generics, TypeFamily,
+};
 
-crate fn boolean_def_id(_db: &impl HirDatabase, _key: ()) -> DefId {
-    unimplemented!()
+crate fn boolean_def_id(db: &impl HirDatabase, (): ()) -> DefId {
+    builtin_def_id(db, BuiltinType::Bool.name())
 }
 
-crate fn fn_body(_db: &impl HirDatabase, _key: DefId) -> Arc<crate::FnBody> {
-    unimplemented!()
+crate fn unit_def_id(db: &impl HirDatabase, (): ()) -> DefId {
+    builtin_def_id(db, BuiltinType::Unit.name())
 }
 
-crate fn members(_db: &impl HirDatabase, _key: DefId) -> Arc<Vec<crate::Member>> {
-    unimplemented!()
+crate fn i32_def_id(db: &impl HirDatabase, (): ()) -> DefId {
+    builtin_def_id(db, BuiltinType::I32.name())
+}
+
+crate fn f64_def_id(db: &impl HirDatabase, (): ()) -> DefId {
+    builtin_def_id(db, BuiltinType::F64.name())
+}
+
+crate fn string_def_id(db: &impl HirDatabase, (): ()) -> DefId {
+    builtin_def_id(db, BuiltinType::String.name())
+}
+
+fn builtin_def_id(db: &impl HirDatabase, name: &str) -> DefId {
+    DefIdData::Builtin(db.intern_string(name)).intern(db)
+}
+
+crate fn defs_in_file(db: &impl HirDatabase, path: StringId) -> Arc<Vec<DefId>> {
+    let mut def_ids = vec![];
+    for &item_id in db.items_in_file(path).iter() {
+        let def_id = DefIdData::Item(item_id).intern(db);
+        def_ids.push(def_id);
+        def_ids.extend(
+            db.members(def_id)
+                .iter()
+                .filter(|member| member.kind == crate::MemberKind::Method)
+                .map(|member| member.def_id),
+        );
+    }
+    Arc::new(def_ids)
+}
+
+crate fn fn_body(db: &impl HirDatabase, key: DefId) -> Arc<crate::FnBody> {
+    let item_id = match key.untern(db) {
+        DefIdData::Item(item_id) => item_id,
+        data => panic!("fn_body invoked on non-item `{:?}`", data),
+    };
+
+    match &*db.ast_of_item(item_id) {
+        a::Item::Def(def) => Arc::new(crate::fn_body::lower(db, item_id, def)),
+        a::Item::Struct(_) => panic!("fn_body invoked on a struct"),
+        a::Item::Enum(_) => panic!("fn_body invoked on an enum"),
+        a::Item::Mod(_) | a::Item::Use(_) => panic!("fn_body invoked on a module or import"),
+    }
+}
+
+crate fn members(db: &impl HirDatabase, key: DefId) -> Arc<Vec<crate::Member>> {
+    let item_id = match key.untern(db) {
+        DefIdData::Item(item_id) => item_id,
+
+        DefIdData::Variant { owner, name } => {
+            let fields = variant_fields(db, &variant(db, owner, name).1)
+                .into_iter()
+                .map(|(name, _)| crate::Member {
+                    name,
+                    kind: crate::MemberKind::Field,
+                    def_id: DefIdData::Field { owner: key, name }.intern(db),
+                })
+                .collect();
+            return Arc::new(fields);
+        }
+
+        DefIdData::Builtin(_) | DefIdData::Field { .. } | DefIdData::TypeParameter { .. } => {
+            return Arc::new(vec![]);
+        }
+    };
+
+    match &*db.ast_of_item(item_id) {
+        a::Item::Struct(s) => {
+            let fields = s.fields.iter().map(|field| {
+                let name = field.name.node;
+                crate::Member {
+                    name,
+                    kind: crate::MemberKind::Field,
+                    def_id: DefIdData::Field { owner: key, name }.intern(db),
+                }
+            });
+
+            let methods = s.methods.iter().map(|method| {
+                let name = method.name.node;
+                crate::Member {
+                    name,
+                    kind: crate::MemberKind::Method,
+                    def_id: DefIdData::Item(method_item_id(db, item_id, name)).intern(db),
+                }
+            });
+
+            Arc::new(fields.chain(methods).collect())
+        }
+
+        a::Item::Enum(e) => {
+            let variants = e.variants.iter().map(|variant| {
+                let name = variant.name.node;
+                crate::Member {
+                    name,
+                    kind: crate::MemberKind::Variant,
+                    def_id: DefIdData::Variant { owner: key, name }.intern(db),
+                }
+            });
+
+            Arc::new(variants.collect())
+        }
+
+        a::Item::Def(_) | a::Item::Mod(_) | a::Item::Use(_) => Arc::new(vec![]),
+    }
 }
 
 crate fn member_def_id(
@@ -32,14 +143,765 @@ crate fn member_def_id(
         .next()
 }
 
-crate fn ty(_db: &impl HirDatabase, _key: DefId) -> ty::Ty<Declaration> {
-    unimplemented!()
+crate fn ty(db: &impl HirDatabase, key: DefId) -> ty::Ty<Declaration> {
+    match key.untern(db) {
+        DefIdData::Builtin(name) => match BuiltinType::from_name(&db.untern_string(name)) {
+            Some(_) => named_ty(db, key),
+            None => error_ty(db),
+        },
+
+        // A struct's or enum's type is applied to its own type
+        // parameters, like `Vec<T>`.
+        DefIdData::Item(item_id) => {
+            let generics = match &*db.ast_of_item(item_id) {
+                a::Item::Struct(s) => s.generics.len(),
+                a::Item::Enum(e) => e.generics.len(),
+                a::Item::Def(_) | a::Item::Mod(_) | a::Item::Use(_) => return error_ty(db),
+            };
+            let generics = (0..generics)
+                .map(|index| GenericKind::Ty(bound_var_ty(db, BoundVar::new(index))))
+                .collect();
+            applied_ty(db, key, generics)
+        }
+
+        // A variant constructs a value of its enum's type.
+        DefIdData::Variant { owner, .. } => db.ty(owner),
+
+        DefIdData::TypeParameter { owner, name } => match bound_var(db, owner, name) {
+            Some(var) => bound_var_ty(db, var),
+            None => panic!("no type parameter named `{}`", db.untern_string(name)),
+        },
+
+        DefIdData::Field { owner, name } => match owner.untern(db) {
+            DefIdData::Item(owner_item_id) => match &*db.ast_of_item(owner_item_id) {
+                a::Item::Struct(s) => match s.fields.iter().find(|f| f.name.node == name) {
+                    Some(field) => declaration_ty(db, owner_item_id, &field.ty.node),
+                    None => panic!("no field named `{}`", db.untern_string(name)),
+                },
+                a::Item::Enum(_) => panic!("field owned by an enum"),
+                a::Item::Def(_) | a::Item::Mod(_) | a::Item::Use(_) => {
+                    panic!("field owned by a non-struct")
+                }
+            },
+
+            DefIdData::Variant {
+                owner: enum_def_id,
+                name: variant_name,
+            } => {
+                let (enum_item_id, variant) = variant(db, enum_def_id, variant_name);
+                match variant_fields(db, &variant)
+                    .into_iter()
+                    .find(|&(field_name, _)| field_name == name)
+                {
+                    Some((_, ty)) => declaration_ty(db, enum_item_id, &ty),
+                    None => panic!("no field named `{}`", db.untern_string(name)),
+                }
+            }
+
+            data => panic!("field owned by `{:?}`", data),
+        },
+    }
+}
+
+crate fn signature(db: &impl HirDatabase, key: DefId) -> ty::Signature<Declaration> {
+    let item_id = match key.untern(db) {
+        DefIdData::Item(item_id) => item_id,
+        DefIdData::Builtin(name) => return builtin_signature(db, name),
+
+        // Variants are "called" with one argument per field, like
+        // structs.
+        DefIdData::Variant { .. } => return constructor_signature(db, key),
+        data => panic!("signature invoked on non-item `{:?}`", data),
+    };
+
+    match &*db.ast_of_item(item_id) {
+        a::Item::Def(def) => {
+            let receiver = def.receiver.map(|_| match owner_of(db, item_id) {
+                Some(owner) => db.ty(DefIdData::Item(owner).intern(db)),
+                None => error_ty(db),
+            });
+            let inputs = receiver
+                .into_iter()
+                .chain(
+                    def.parameters
+                        .iter()
+                        .map(|parameter| declaration_ty(db, item_id, &parameter.ty.node)),
+                )
+                .collect();
+            let output = match &def.ret {
+                Some(ty) => declaration_ty(db, item_id, &ty.node),
+                None => named_ty(db, db.unit_def_id(())),
+            };
+            ty::Signature {
+                inputs: Arc::new(inputs),
+                output,
+            }
+        }
+
+        // Structs are "called" with one argument per field.
+        a::Item::Struct(_) => constructor_signature(db, key),
+
+        // Only the variants of an enum construct it, and nothing
+        // constructs a module.
+        a::Item::Enum(_) | a::Item::Mod(_) | a::Item::Use(_) => ty::Signature {
+            inputs: Arc::new(vec![]),
+            output: error_ty(db),
+        },
+    }
+}
+
+/// The signature of the struct or variant `key`: one input per field,
+/// producing a value of its type.
+fn constructor_signature(db: &impl HirDatabase, key: DefId) -> ty::Signature<Declaration> {
+    let inputs = db
+        .members(key)
+        .iter()
+        .filter(|member| member.kind == crate::MemberKind::Field)
+        .map(|member| db.ty(member.def_id))
+        .collect();
+    ty::Signature {
+        inputs: Arc::new(inputs),
+        output: db.ty(key),
+    }
+}
+
+fn builtin_signature(db: &impl HirDatabase, name: StringId) -> ty::Signature<Declaration> {
+    let signature = BuiltinFn::from_name(&db.untern_string(name)).and_then(|f| f.signature());
+    let (inputs, output) = match signature {
+        Some(signature) => signature,
+        None => panic!("signature invoked on `{}`", db.untern_string(name)),
+    };
+
+    let builtin_ty = |builtin: BuiltinType| named_ty(db, builtin_def_id(db, builtin.name()));
+    ty::Signature {
+        inputs: Arc::new(inputs.iter().map(|&input| builtin_ty(input)).collect()),
+        output: builtin_ty(output),
+    }
+}
+
+crate fn item_errors(db: &impl HirDatabase, key: DefId) -> Arc<Vec<Spanned<crate::ErrorData>>> {
+    let item_id = match key.untern(db) {
+        DefIdData::Item(item_id) => item_id,
+        DefIdData::Builtin(_)
+        | DefIdData::Field { .. }
+        | DefIdData::Variant { .. }
+        | DefIdData::TypeParameter { .. } => {
+            return Arc::new(vec![]);
+        }
+    };
+
+    let item = db.ast_of_item(item_id);
+    let mut errors = vec![];
+    let types: Vec<&a::Type> = match &*item {
+        a::Item::Struct(s) => {
+            for method in s.methods.iter() {
+                if method.receiver.is_none() {
+                    errors.push(Spanned {
+                        node: crate::ErrorData::MissingReceiver,
+                        span: method.name.span,
+                    });
+                }
+                if let a::Visibility::Public(span) = method.visibility {
+                    errors.push(Spanned {
+                        node: crate::ErrorData::PubMethod,
+                        span,
+                    });
+                }
+            }
+
+            s.fields.iter().map(|field| &field.ty.node).collect()
+        }
+
+        a::Item::Enum(e) => e
+            .variants
+            .iter()
+            .flat_map(|variant| match &variant.fields {
+                a::VariantFields::Unit => vec![],
+                a::VariantFields::Tuple(types) => types.iter().map(|ty| &ty.node).collect(),
+                a::VariantFields::Struct(fields) => {
+                    fields.iter().map(|field| &field.ty.node).collect()
+                }
+            })
+            .collect(),
+
+        a::Item::Def(def) => {
+            if let (Some(receiver), None) = (def.receiver, owner_of(db, item_id)) {
+                errors.push(Spanned {
+                    node: crate::ErrorData::ReceiverOutsideMethod,
+                    span: receiver.span,
+                });
+            }
+
+            def.parameters
+                .iter()
+                .map(|parameter| &parameter.ty.node)
+                .chain(def.ret.iter().map(|ty| &ty.node))
+                .collect()
+        }
+
+        a::Item::Mod(m) => {
+            if db.module_file(key).is_none() {
+                let input_file = item_id.untern(db).input_file;
+                let candidates = crate::module_file_candidates(
+                    &db.untern_string(input_file),
+                    &db.untern_string(m.name.node),
+                );
+                errors.push(Spanned {
+                    node: crate::ErrorData::ModuleFileNotFound {
+                        name: m.name.node,
+                        expected: db.intern_string(candidates[0].as_str()),
+                    },
+                    span: m.name.span,
+                });
+            }
+            vec![]
+        }
+
+        a::Item::Use(_) => {
+            if let Err(error) = resolve_use(db, item_id, &mut vec![]) {
+                errors.push(error);
+            }
+            vec![]
+        }
+    };
+
+    for ty in types {
+        type_errors(db, item_id, ty, &mut errors);
+    }
+
+    Arc::new(errors)
+}
+
+/// Reports the names in `ty` that do not resolve to a type, and the
+/// types in it that are given the wrong number of type arguments.
+crate fn type_errors(
+    db: &impl HirDatabase,
+    scope: ItemId,
+    ty: &a::Type,
+    errors: &mut Vec<Spanned<crate::ErrorData>>,
+) {
+    match resolve_type_name(db, scope, ty.name.node) {
+        Some(def_id) => {
+            let expected = generic_count(db, def_id);
+            if ty.generics.len() != expected {
+                errors.push(Spanned {
+                    node: crate::ErrorData::WrongNumberOfTypeArguments {
+                        expected,
+                        found: ty.generics.len(),
+                    },
+                    span: ty.name.span,
+                });
+            }
+        }
+
+        None => errors.push(Spanned {
+            node: crate::ErrorData::UnknownType { text: ty.name.node },
+            span: ty.name.span,
+        }),
+    }
+
+    for generic in &ty.generics {
+        type_errors(db, scope, &generic.node, errors);
+    }
+}
+
+crate fn generic_declarations(db: &impl HirDatabase, key: DefId) -> Arc<GenericDeclarations> {
+    let (parent_item, generics) = match key.untern(db) {
+        DefIdData::Item(item_id) => {
+            let parent_item = owner_of(db, item_id).map(|owner| DefIdData::Item(owner).intern(db));
+            let generics = match &*db.ast_of_item(item_id) {
+                a::Item::Struct(s) => s.generics.clone(),
+                a::Item::Enum(e) => e.generics.clone(),
+                a::Item::Def(def) => def.generics.clone(),
+                a::Item::Mod(_) | a::Item::Use(_) => vec![],
+            };
+            (parent_item, generics)
+        }
+
+        // A variant has the type parameters of its enum.
+        DefIdData::Variant { owner, .. } => (Some(owner), vec![]),
+
+        DefIdData::Builtin(_) | DefIdData::Field { .. } | DefIdData::TypeParameter { .. } => {
+            (None, vec![])
+        }
+    };
+
+    let declarations = generics
+        .iter()
+        .map(|name| {
+            GenericKind::Ty(GenericTyDeclaration {
+                def_id: DefIdData::TypeParameter {
+                    owner: key,
+                    name: name.node,
+                }
+                .intern(db),
+                name: name.node,
+            })
+        })
+        .collect();
+
+    Arc::new(GenericDeclarations {
+        parent_item,
+        declarations,
+    })
+}
+
+/// The number of generic parameters of `def_id`, including those it
+/// inherits from its parent.
+fn generic_count(db: &impl HirDatabase, def_id: DefId) -> usize {
+    let GenericDeclarations {
+        parent_item,
+        declarations,
+    } = &*db.generic_declarations(def_id);
+    let inherited = parent_item.map_or(0, |parent| generic_count(db, parent));
+    inherited + declarations.len()
+}
+
+/// The bound variable for the type parameter `name` of `owner`. The
+/// parameters that an item inherits from its parent come first, as in
+/// the type checker's `placeholders_for`.
+fn bound_var(db: &impl HirDatabase, owner: DefId, name: StringId) -> Option<BoundVar> {
+    let GenericDeclarations {
+        parent_item,
+        declarations,
+    } = &*db.generic_declarations(owner);
+    let index = declarations.iter().position(|declaration| match declaration {
+        GenericKind::Ty(declaration) => declaration.name == name,
+    })?;
+    let inherited = parent_item.map_or(0, |parent| generic_count(db, parent));
+    Some(BoundVar::new(inherited + index))
+}
+
+crate fn module_file(db: &impl HirDatabase, key: DefId) -> Option<StringId> {
+    let item_id = match key.untern(db) {
+        DefIdData::Item(item_id) => item_id,
+        data => panic!("module_file invoked on non-item `{:?}`", data),
+    };
+    let name = match &*db.ast_of_item(item_id) {
+        a::Item::Mod(m) => m.name.node,
+        _ => panic!("module_file invoked on a non-module"),
+    };
+
+    let input_file = item_id.untern(db).input_file;
+    let input_files = db.input_files(());
+    crate::module_file_candidates(&db.untern_string(input_file), &db.untern_string(name))
+        .into_iter()
+        .map(|candidate| db.intern_string(candidate.as_str()))
+        .find(|candidate| input_files.contains(candidate))
+}
+
+crate fn parent_module(db: &impl HirDatabase, path: StringId) -> Option<DefId> {
+    for &input_file in db.input_files(()).iter() {
+        for &item_id in db.items_in_file(input_file).iter() {
+            if let a::Item::Mod(_) = &*db.ast_of_item(item_id) {
+                let def_id = DefIdData::Item(item_id).intern(db);
+                if db.module_file(def_id) == Some(path) {
+                    return Some(def_id);
+                }
+            }
+        }
+    }
+    None
+}
+
+crate fn module_path(db: &impl HirDatabase, path: StringId) -> Arc<Vec<StringId>> {
+    let mut names = vec![];
+    let mut file = path;
+    let mut seen = vec![path];
+    while let Some(parent) = db.parent_module(file) {
+        let item_id = match parent.untern(db) {
+            DefIdData::Item(item_id) => item_id,
+            data => panic!("module declared by non-item `{:?}`", data),
+        };
+        names.insert(0, item_id.untern(db).path[0]);
+        file = item_id.untern(db).input_file;
+
+        // Two files can declare each other as modules; neither is
+        // then inside a root, so we stop where we started.
+        if seen.contains(&file) {
+            break;
+        }
+        seen.push(file);
+    }
+    Arc::new(names)
+}
+
+/// The files of the modules that the file `path` is within, starting
+/// with `path` itself and ending with the root of the program.
+fn enclosing_files(db: &impl HirDatabase, path: StringId) -> Vec<StringId> {
+    let mut files = vec![path];
+    while let Some(parent) = db.parent_module(*files.last().unwrap()) {
+        let file = match parent.untern(db) {
+            DefIdData::Item(item_id) => item_id.untern(db).input_file,
+            data => panic!("module declared by non-item `{:?}`", data),
+        };
+        if files.contains(&file) {
+            break;
+        }
+        files.push(file);
+    }
+    files
+}
+
+/// True if `def_id` is a `mod` item.
+crate fn is_module(db: &impl HirDatabase, def_id: DefId) -> bool {
+    match def_id.untern(db) {
+        DefIdData::Item(item_id) => match &*db.ast_of_item(item_id) {
+            a::Item::Mod(_) => true,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// True if the item `item_id` can be named from the file `from`: it is
+/// `pub`, or `from` is within the module that the item is in.
+fn is_visible(db: &impl HirDatabase, item_id: ItemId, from: StringId) -> bool {
+    match db.ast_of_item(item_id).visibility() {
+        a::Visibility::Public(_) => true,
+        a::Visibility::Private => {
+            enclosing_files(db, from).contains(&item_id.untern(db).input_file)
+        }
+    }
+}
+
+/// Resolves a name that appears within the item `scope`: first
+/// against the items in the same file, including those it imports,
+/// then against those of the files visible from it, and then against
+/// the built-in functions.
+crate fn resolve_name(db: &impl HirDatabase, scope: ItemId, name: StringId) -> Option<DefId> {
+    resolve_name_in(db, scope, name, &mut vec![])
+}
+
+/// Like `resolve_name`, where `imports` are the `use` items that we
+/// are already in the middle of resolving.
+fn resolve_name_in(
+    db: &impl HirDatabase,
+    scope: ItemId,
+    name: StringId,
+    imports: &mut Vec<ItemId>,
+) -> Option<DefId> {
+    let input_file = scope.untern(db).input_file;
+    if let Some((_, def_id)) = item_in_file(db, input_file, name, imports) {
+        return def_id;
+    }
+
+    for &file in db.visible_files(input_file).iter() {
+        if let Some((_, def_id)) = item_in_file(db, file, name, imports) {
+            return def_id;
+        }
+    }
+
+    BuiltinFn::from_name(&db.untern_string(name)).map(|_| DefIdData::Builtin(name).intern(db))
+}
+
+/// The item named `name` in the file `path`, and what it refers to:
+/// the item itself or, for a `use`, the item it imports, if that
+/// resolves.
+fn item_in_file(
+    db: &impl HirDatabase,
+    path: StringId,
+    name: StringId,
+    imports: &mut Vec<ItemId>,
+) -> Option<(ItemId, Option<DefId>)> {
+    let item_id = db
+        .items_in_file(path)
+        .iter()
+        .find(|item_id| item_id.untern(db).path.last() == Some(&name))
+        .cloned()?;
+    let def_id = match &*db.ast_of_item(item_id) {
+        a::Item::Use(_) => resolve_use(db, item_id, imports).ok(),
+        _ => Some(DefIdData::Item(item_id).intern(db)),
+    };
+    Some((item_id, def_id))
+}
+
+/// Resolves the path of the `use` item `item_id` to the item it
+/// imports. `imports` are the `use` items that we are already in the
+/// middle of resolving, which cannot import one another.
+fn resolve_use(
+    db: &impl HirDatabase,
+    item_id: ItemId,
+    imports: &mut Vec<ItemId>,
+) -> Result<DefId, Spanned<crate::ErrorData>> {
+    let path = match &*db.ast_of_item(item_id) {
+        a::Item::Use(u) => u.path.clone(),
+        _ => panic!("resolve_use invoked on a non-import"),
+    };
+
+    if imports.contains(&item_id) {
+        let first = path.components[0];
+        return Err(Spanned {
+            node: crate::ErrorData::UnknownIdentifier { text: first.node },
+            span: first.span,
+        });
+    }
+
+    imports.push(item_id);
+    let result = resolve_path_in(db, item_id, &path, imports);
+    imports.pop();
+    result
+}
+
+/// Resolves a path that appears within the item `scope`, like `area`,
+/// `shapes::Circle`, `Shape::Circle` or `crate::main`. The first
+/// component is resolved like any name, except that `crate` stands for
+/// the root module; each one after that names an item of a module or a
+/// variant of an enum.
+crate fn resolve_path(
+    db: &impl HirDatabase,
+    scope: ItemId,
+    path: &a::Path,
+) -> Result<DefId, Spanned<crate::ErrorData>> {
+    resolve_path_in(db, scope, path, &mut vec![])
+}
+
+fn resolve_path_in(
+    db: &impl HirDatabase,
+    scope: ItemId,
+    path: &a::Path,
+    imports: &mut Vec<ItemId>,
+) -> Result<DefId, Spanned<crate::ErrorData>> {
+    let error = |node, span| Err(Spanned { node, span });
+    let from = scope.untern(db).input_file;
+    let (first, rest) = path.components.split_first().expect("empty path");
+
+    // The file of the module we are in, if the path so far names one,
+    // or else the item it names.
+    let mut current = if &*db.untern_string(first.node) == "crate" {
+        if rest.is_empty() {
+            return error(crate::ErrorData::BareCrate, first.span);
+        }
+        Err(*enclosing_files(db, from).last().unwrap())
+    } else {
+        match resolve_name_in(db, scope, first.node, imports) {
+            Some(def_id) => Ok(def_id),
+            None => {
+                return error(
+                    crate::ErrorData::UnknownIdentifier { text: first.node },
+                    first.span,
+                )
+            }
+        }
+    };
+
+    let mut owner = first;
+    for component in rest {
+        let module_file = match current {
+            Err(file) => Some(file),
+            Ok(def_id) if is_module(db, def_id) => db.module_file(def_id),
+            Ok(def_id) => {
+                let key = (def_id, crate::MemberKind::Variant, component.node);
+                current = match db.member_def_id(key) {
+                    Some(variant) => Ok(variant),
+                    None => {
+                        return error(
+                            crate::ErrorData::UnknownVariant {
+                                owner: owner.node,
+                                text: component.node,
+                            },
+                            component.span,
+                        )
+                    }
+                };
+                owner = component;
+                continue;
+            }
+        };
+
+        let found = module_file.and_then(|file| item_in_file(db, file, component.node, imports));
+        current = match found {
+            Some((item_id, _)) if !is_visible(db, item_id, from) => {
+                return error(
+                    crate::ErrorData::PrivateItem {
+                        text: component.node,
+                    },
+                    component.span,
+                )
+            }
+            Some((_, Some(def_id))) => Ok(def_id),
+            Some((_, None)) | None => {
+                return error(
+                    crate::ErrorData::UnknownItem {
+                        module: owner.node,
+                        text: component.node,
+                    },
+                    component.span,
+                )
+            }
+        };
+        owner = component;
+    }
+
+    match current {
+        Ok(def_id) => Ok(def_id),
+        Err(_) => unreachable!("a path of just `crate` is rejected above"),
+    }
+}
+
+/// Resolves a type name that appears within the item `scope`: first
+/// against the type parameters in scope, then against the structs and
+/// enums in the same file, then against the built-in types.
+fn resolve_type_name(db: &impl HirDatabase, scope: ItemId, name: StringId) -> Option<DefId> {
+    if let Some(def_id) = resolve_type_parameter(db, scope, name) {
+        return Some(def_id);
+    }
+
+    if let Some(def_id) = resolve_name(db, scope, name) {
+        return match def_id.untern(db) {
+            DefIdData::Item(item_id) => match &*db.ast_of_item(item_id) {
+                a::Item::Struct(_) | a::Item::Enum(_) => Some(def_id),
+                a::Item::Def(_) | a::Item::Mod(_) | a::Item::Use(_) => None,
+            },
+            _ => None,
+        };
+    }
+
+    BuiltinType::from_name(&db.untern_string(name)).map(|_| DefIdData::Builtin(name).intern(db))
+}
+
+/// The type parameter `name` of the item `scope` or, within a method,
+/// of the struct it is defined in.
+fn resolve_type_parameter(db: &impl HirDatabase, scope: ItemId, name: StringId) -> Option<DefId> {
+    let declarations = db.generic_declarations(DefIdData::Item(scope).intern(db));
+    let declaration = declarations
+        .declarations
+        .iter()
+        .map(|declaration| match declaration {
+            GenericKind::Ty(declaration) => declaration,
+        })
+        .find(|declaration| declaration.name == name);
+    match declaration {
+        Some(declaration) => Some(declaration.def_id),
+        None => owner_of(db, scope).and_then(|owner| resolve_type_parameter(db, owner, name)),
+    }
+}
+
+/// The enum `owner` and its variant `name`.
+fn variant(db: &impl HirDatabase, owner: DefId, name: StringId) -> (ItemId, a::Variant) {
+    let item_id = match owner.untern(db) {
+        DefIdData::Item(item_id) => item_id,
+        data => panic!("variant owned by non-item `{:?}`", data),
+    };
+    match &*db.ast_of_item(item_id) {
+        a::Item::Enum(e) => match e.variants.iter().find(|v| v.name.node == name) {
+            Some(variant) => (item_id, variant.clone()),
+            None => panic!("no variant named `{}`", db.untern_string(name)),
+        },
+        _ => panic!("variant owned by a non-enum"),
+    }
+}
+
+/// The names and declared types of the fields of `variant`; the
+/// fields of a tuple variant are named `0`, `1` and so on.
+fn variant_fields(db: &impl HirDatabase, variant: &a::Variant) -> Vec<(StringId, a::Type)> {
+    match &variant.fields {
+        a::VariantFields::Unit => vec![],
+        a::VariantFields::Tuple(types) => types
+            .iter()
+            .enumerate()
+            .map(|(index, ty)| (db.intern_string(index.to_string().as_str()), ty.node.clone()))
+            .collect(),
+        a::VariantFields::Struct(fields) => fields
+            .iter()
+            .map(|field| (field.name.node, field.ty.node.clone()))
+            .collect(),
+    }
+}
+
+/// The item id of the method `name` defined within the struct `owner`.
+fn method_item_id(db: &impl HirDatabase, owner: ItemId, name: StringId) -> ItemId {
+    let ItemIdData { input_file, path } = owner.untern(db);
+    let mut path = (*path).clone();
+    path.push(name);
+    ItemIdData {
+        input_file,
+        path: Arc::new(path),
+    }
+    .intern(db)
+}
+
+/// The struct that the method `item_id` is defined within, or `None`
+/// if it is a top-level item.
+fn owner_of(db: &impl HirDatabase, item_id: ItemId) -> Option<ItemId> {
+    let ItemIdData { input_file, path } = item_id.untern(db);
+    match &path[..] {
+        [owner, _] => Some(
+            ItemIdData {
+                input_file,
+                path: Arc::new(vec![*owner]),
+            }
+            .intern(db),
+        ),
+        _ => None,
+    }
+}
+
+/// Converts a type as the user wrote it in the item `scope`. Names
+/// that do not resolve to a type, and types with the wrong number of
+/// type arguments, become the error type; `item_errors` reports them.
+crate fn declaration_ty(db: &impl HirDatabase, scope: ItemId, ty: &a::Type) -> ty::Ty<Declaration> {
+    let def_id = match resolve_type_name(db, scope, ty.name.node) {
+        Some(def_id) => def_id,
+        None => return error_ty(db),
+    };
+
+    if ty.generics.len() != generic_count(db, def_id) {
+        return error_ty(db);
+    }
+
+    match def_id.untern(db) {
+        DefIdData::TypeParameter { .. } => db.ty(def_id),
+        _ => {
+            let generics = ty
+                .generics
+                .iter()
+                .map(|generic| GenericKind::Ty(declaration_ty(db, scope, &generic.node)))
+                .collect();
+            applied_ty(db, def_id, generics)
+        }
+    }
+}
+
+fn named_ty(db: &impl HirDatabase, def_id: DefId) -> ty::Ty<Declaration> {
+    applied_ty(db, def_id, Generics::empty())
+}
+
+/// The type `def_id` applied to the type arguments `generics`, like
+/// `Vec<i32>`.
+fn applied_ty(
+    db: &impl HirDatabase,
+    def_id: DefId,
+    generics: Generics<Declaration>,
+) -> ty::Ty<Declaration> {
+    ty::Ty {
+        perm: Erased,
+        base: Declaration::intern_base_data(
+            db,
+            BaseData {
+                kind: BaseKind::Named(def_id),
+                generics,
+            },
+        ),
+    }
 }
 
-crate fn signature(_db: &impl HirDatabase, _key: DefId) -> ty::Signature<Declaration> {
-    unimplemented!()
+fn bound_var_ty(db: &impl HirDatabase, var: BoundVar) -> ty::Ty<Declaration> {
+    ty::Ty {
+        perm: Erased,
+        base: BoundVarOr::BoundVar(var).intern(db),
+    }
 }
 
-crate fn generic_declarations(_db: &impl HirDatabase, _key: DefId) -> Arc<ty::GenericDeclarations> {
-    unimplemented!()
+fn error_ty(db: &impl HirDatabase) -> ty::Ty<Declaration> {
+    ty::Ty {
+        perm: Erased,
+        base: Declaration::intern_base_data(
+            db,
+            BaseData {
+                kind: BaseKind::Error,
+                generics: Generics::empty(),
+            },
+        ),
+    }
 }
@@ -0,0 +1,126 @@
+//! Converts compiler diagnostics into LSP diagnostics, and byte
+//! offsets into LSP positions and back.
+
+use languageserver_types::{
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, Location, NumberOrString,
+    Position, Range, Url,
+};
+use parser::pos::Span;
+
+/// Converts `diagnostic`, reported against the document `uri` whose
+/// contents are `text`, into an LSP diagnostic. The primary label
+/// becomes the range, secondary labels become related information,
+/// and the notes are appended to the message.
+pub fn lsp_diagnostic(
+    diagnostic: &type_check::diagnostic::Diagnostic,
+    uri: &Url,
+    text: &str,
+) -> Diagnostic {
+    let primary = diagnostic.labels.iter().find(|label| label.primary);
+    let range = primary
+        .map(|label| span_range(label.span, text))
+        .unwrap_or_default();
+
+    let mut message = diagnostic.message.clone();
+    if let Some(primary) = primary {
+        message.push_str(&format!(": {}", primary.message));
+    }
+    for note in &diagnostic.notes {
+        message.push_str(&format!("\n{}", note.trim_start()));
+    }
+
+    let related_information: Vec<_> = diagnostic
+        .labels
+        .iter()
+        .filter(|label| !label.primary)
+        .map(|label| DiagnosticRelatedInformation {
+            location: Location {
+                uri: uri.clone(),
+                range: span_range(label.span, text),
+            },
+            message: label.message.clone(),
+        })
+        .collect();
+
+    Diagnostic {
+        range,
+        severity: Some(DiagnosticSeverity::Error),
+        code: Some(NumberOrString::String(diagnostic.code.to_string())),
+        source: Some("lark".to_string()),
+        message,
+        related_information: if related_information.is_empty() {
+            None
+        } else {
+            Some(related_information)
+        },
+    }
+}
+
+/// An LSP error diagnostic with no code, for errors that are reported
+/// as just a message and a span.
+pub fn lsp_error(message: String, span: Span, text: &str) -> Diagnostic {
+    Diagnostic {
+        range: span_range(span, text),
+        severity: Some(DiagnosticSeverity::Error),
+        code: None,
+        source: Some("lark".to_string()),
+        message,
+        related_information: None,
+    }
+}
+
+/// The LSP range covered by `span` within `text`.
+pub fn span_range(span: Span, text: &str) -> Range {
+    match span {
+        Span::Real(span) => Range::new(
+            offset_position(span.start().to_usize(), text),
+            offset_position(span.end().to_usize(), text),
+        ),
+        Span::EOF => {
+            let end = offset_position(text.len(), text);
+            Range::new(end, end)
+        }
+        Span::Synthetic => Range::default(),
+    }
+}
+
+/// The LSP position of the byte `offset` within `text`. LSP counts
+/// characters in UTF-16 code units. An offset past the end of the text
+/// is clamped to it, and one inside a character moves back to where
+/// that character starts.
+pub fn offset_position(offset: usize, text: &str) -> Position {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+
+    let before = &text[..offset];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let character = before[line_start..].encode_utf16().count();
+    Position::new(line as u64, character as u64)
+}
+
+/// The byte offset of the LSP `position` within `text`, the inverse
+/// of `offset_position`. Positions past the end of a line or of the
+/// text are clamped to it.
+pub fn position_offset(position: Position, text: &str) -> usize {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        match text[line_start..].find('\n') {
+            Some(newline) => line_start += newline + 1,
+            None => return text.len(),
+        }
+    }
+
+    let line = &text[line_start..];
+    let line = &line[..line.find('\n').unwrap_or(line.len())];
+    let mut units = 0;
+    for (index, c) in line.char_indices() {
+        if units >= position.character {
+            return line_start + index;
+        }
+        units += c.len_utf16() as u64;
+    }
+    line_start + line.len()
+}
@@ -5,6 +5,8 @@ use std::io::prelude::{Read, Write};
 use std::sync::mpsc::Sender;
 use task_manager::{self, Actor, LspRequest, LspResponse, MsgToManager};
 
+pub mod diagnostics;
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "method")]
 #[allow(non_camel_case_types)]
@@ -32,6 +34,16 @@ enum LSPCommand {
         id: usize,
         params: languageserver_types::CompletionParams,
     },
+    #[serde(rename = "textDocument/formatting")]
+    formatting {
+        id: usize,
+        params: languageserver_types::DocumentFormattingParams,
+    },
+    #[serde(rename = "textDocument/rangeFormatting")]
+    rangeFormatting {
+        id: usize,
+        params: languageserver_types::DocumentRangeFormattingParams,
+    },
     #[serde(rename = "$/cancelRequest")]
     cancelRequest {
         params: languageserver_types::CancelParams,
@@ -59,12 +71,36 @@ impl<T> LSPJsonRPC<T> {
     }
 }
 
+/// A message from the server that is not a response to a request.
+#[derive(Debug, Serialize, Deserialize)]
+struct LSPJsonRPCNotification<T> {
+    jsonrpc: String,
+    method: String,
+    params: T,
+}
+impl<T> LSPJsonRPCNotification<T> {
+    pub fn new(method: &str, params: T) -> LSPJsonRPCNotification<T> {
+        LSPJsonRPCNotification {
+            jsonrpc: "2.0".into(),
+            method: method.into(),
+            params,
+        }
+    }
+}
+
 fn send_result<T: Serialize>(id: usize, result: T) {
     let response = LSPJsonRPC::new(id, result);
-    let response_raw = serde_json::to_string(&response).unwrap();
+    send_raw(&serde_json::to_string(&response).unwrap());
+}
 
-    print!("Content-Length: {}\r\n\r\n", response_raw.len());
-    print!("{}", response_raw);
+fn send_notification<T: Serialize>(method: &str, params: T) {
+    let notification = LSPJsonRPCNotification::new(method, params);
+    send_raw(&serde_json::to_string(&notification).unwrap());
+}
+
+fn send_raw(message: &str) {
+    print!("Content-Length: {}\r\n\r\n", message.len());
+    print!("{}", message);
     let _ = io::stdout().flush();
 }
 
@@ -99,11 +135,17 @@ impl Actor for LspResponder {
             LspResponse::Completions(id, completions) => {
                 let mut completion_items = vec![];
 
-                for completion in completions {
-                    completion_items.push(languageserver_types::CompletionItem::new_simple(
-                        completion.0,
-                        completion.1,
-                    ));
+                for (name, detail, docs) in completions {
+                    let mut item = languageserver_types::CompletionItem::new_simple(name, detail);
+                    item.documentation = docs.map(|docs| {
+                        languageserver_types::Documentation::MarkupContent(
+                            languageserver_types::MarkupContent {
+                                kind: languageserver_types::MarkupKind::Markdown,
+                                value: docs,
+                            },
+                        )
+                    });
+                    completion_items.push(item);
                 }
 
                 let result = languageserver_types::CompletionList {
@@ -113,6 +155,14 @@ impl Actor for LspResponder {
 
                 send_result(id, result);
             }
+            LspResponse::Edits(id, edits) => {
+                send_result(id, edits);
+            }
+            LspResponse::Diagnostics(uri, diagnostics) => {
+                let params = languageserver_types::PublishDiagnosticsParams::new(uri, diagnostics);
+
+                send_notification("textDocument/publishDiagnostics", params);
+            }
             LspResponse::Initialized(id) => {
                 let result = languageserver_types::InitializeResult {
                     capabilities: languageserver_types::ServerCapabilities {
@@ -136,8 +186,8 @@ impl Actor for LspResponder {
                         workspace_symbol_provider: None,
                         code_action_provider: None,
                         code_lens_provider: None,
-                        document_formatting_provider: None,
-                        document_range_formatting_provider: None,
+                        document_formatting_provider: Some(true),
+                        document_range_formatting_provider: Some(true),
                         document_on_type_formatting_provider: None,
                         rename_provider: None,
                         color_provider: None,
@@ -177,25 +227,55 @@ pub fn lsp_serve(send_to_manager_channel: Sender<task_manager::MsgToManager>) {
                             eprintln!("Initialized received");
                         }
                         Ok(LSPCommand::didOpen { params }) => {
-                            eprintln!("didOpen: {:#?}", params);
+                            let _ = send_to_manager_channel.send(MsgToManager::LspRequest(
+                                LspRequest::OpenFile(
+                                    params.text_document.uri,
+                                    params.text_document.text,
+                                ),
+                            ));
                         }
                         Ok(LSPCommand::didChange { params }) => {
-                            eprintln!("didChange: {:#?}", params);
+                            let _ = send_to_manager_channel.send(MsgToManager::LspRequest(
+                                LspRequest::EditFile(
+                                    params.text_document.uri,
+                                    params.content_changes,
+                                ),
+                            ));
                         }
                         Ok(LSPCommand::hover { id, params }) => {
                             eprintln!("hover: id={} {:#?}", id, params);
 
-                            //FIXME: this is using a fake position
                             let _ = send_to_manager_channel.send(MsgToManager::LspRequest(
-                                LspRequest::TypeForPos(id, params.position.clone()),
+                                LspRequest::TypeForPos(
+                                    id,
+                                    params.text_document.uri,
+                                    params.position,
+                                ),
                             ));
                         }
                         Ok(LSPCommand::completion { id, params }) => {
                             eprintln!("completion: id={} {:#?}", id, params);
 
-                            //FIXME: this is using a fake position
                             let _ = send_to_manager_channel.send(MsgToManager::LspRequest(
-                                LspRequest::Completion(id, params.position.clone()),
+                                LspRequest::Completion(
+                                    id,
+                                    params.text_document.uri,
+                                    params.position,
+                                ),
+                            ));
+                        }
+                        Ok(LSPCommand::formatting { id, params }) => {
+                            let _ = send_to_manager_channel.send(MsgToManager::LspRequest(
+                                LspRequest::Formatting(id, params.text_document.uri, None),
+                            ));
+                        }
+                        Ok(LSPCommand::rangeFormatting { id, params }) => {
+                            let _ = send_to_manager_channel.send(MsgToManager::LspRequest(
+                                LspRequest::Formatting(
+                                    id,
+                                    params.text_document.uri,
+                                    Some(params.range),
+                                ),
                             ));
                         }
                         Ok(LSPCommand::completionItemResolve { id, params }) => {
@@ -0,0 +1,862 @@
+//! Lowers the type-checked HIR of a set of items into a `mir::Context`,
+//! which can then be handed to `codegen` or `eval`.
+
+#![feature(crate_visibility_modifier)]
+#![feature(in_band_lifetimes)]
+
+use ast::def_id::{DefId, DefIdData};
+use hir::HirDatabase;
+use intern::Untern;
+use map::FxIndexMap;
+use mir::{
+    BinOp, BlockId, BuiltinFn, BuiltinType, Context, Definition, Enum, Function, LocalDecl,
+    Operand, Place, Rvalue, StatementKind, Struct, TerminatorKind, UnOp, VarId, Variant,
+};
+use std::sync::Arc;
+use ty::base_inferred::BaseInferred;
+use ty::declaration::Declaration;
+use ty::{BaseKind, BoundVarOr, TypeFamily};
+use type_check::{TypeCheckDatabase, TypeCheckResults};
+
+/// The MIR for a set of items.
+pub struct LoweredItems {
+    pub context: Context,
+
+    /// Maps each item to the index of its MIR definition.
+    pub def_ids: FxIndexMap<DefId, mir::DefId>,
+}
+
+/// An item that could not be lowered.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Error {
+    pub def_id: DefId,
+    pub message: String,
+}
+
+/// Lowers `items` into MIR. The items are expected to have type-checked
+/// without errors. Modules and imports have nothing to lower, so they
+/// are skipped.
+pub fn lower_items(
+    db: &impl TypeCheckDatabase,
+    items: &[DefId],
+) -> Result<LoweredItems, Vec<Error>> {
+    let items: Vec<_> = items
+        .iter()
+        .cloned()
+        .filter(|&def_id| match def_id.untern(db) {
+            DefIdData::Item(item_id) => match &*db.ast_of_item(item_id) {
+                ast::ast::Item::Mod(_) | ast::ast::Item::Use(_) => false,
+                _ => true,
+            },
+            _ => true,
+        })
+        .collect();
+
+    let mut lowering = ItemsLowering {
+        db,
+        context: Context::new(),
+        def_ids: FxIndexMap::default(),
+        errors: vec![],
+    };
+
+    // Reserve a MIR index for each item up front, so that items can
+    // refer to one another regardless of order.
+    for &def_id in &items {
+        let index = lowering.context.add_definition(Definition::Error);
+        lowering.def_ids.insert(def_id, index);
+    }
+
+    for &def_id in &items {
+        let definition = lowering.lower_item(def_id);
+        let index = lowering.def_ids[&def_id];
+        lowering.context.definitions[index] = definition;
+    }
+
+    let ItemsLowering {
+        context,
+        def_ids,
+        errors,
+        ..
+    } = lowering;
+
+    if errors.is_empty() {
+        Ok(LoweredItems { context, def_ids })
+    } else {
+        Err(errors)
+    }
+}
+
+struct ItemsLowering<'db, DB: TypeCheckDatabase> {
+    db: &'db DB,
+    context: Context,
+    def_ids: FxIndexMap<DefId, mir::DefId>,
+    errors: Vec<Error>,
+}
+
+impl<DB> ItemsLowering<'_, DB>
+where
+    DB: TypeCheckDatabase,
+{
+    fn lower_item(&mut self, def_id: DefId) -> Definition {
+        let item_id = match def_id.untern(self.db) {
+            DefIdData::Item(item_id) => item_id,
+            data => panic!("cannot lower non-item `{:?}` to MIR", data),
+        };
+
+        let item = self.db.ast_of_item(item_id);
+
+        // Items are named after their module and their path, like
+        // `shapes__Point__sum`, so that methods cannot clash with
+        // top-level functions, nor items with those of other modules.
+        let module_path = self.db.module_path(item_id.untern(self.db).input_file);
+        let name: Vec<_> = module_path
+            .iter()
+            .chain(item_id.untern(self.db).path.iter())
+            .map(|&text| self.db.untern_string(text).to_string())
+            .collect();
+        let name = mangle(&name);
+
+        match &*item {
+            ast::ast::Item::Struct(_) => {
+                let mut s = Struct::new(name);
+                let fields = self.db.members(def_id);
+                let fields = fields
+                    .iter()
+                    .filter(|member| member.kind == hir::MemberKind::Field);
+                for member in fields {
+                    let ty = self.declaration_ty(def_id, self.db.ty(member.def_id));
+                    s = s.field(self.db.untern_string(member.name).to_string(), ty);
+                }
+                Definition::Struct(s)
+            }
+
+            ast::ast::Item::Enum(_) => {
+                let mut e = Enum::new(name);
+                let variants = self.db.members(def_id);
+                let variants = variants
+                    .iter()
+                    .filter(|member| member.kind == hir::MemberKind::Variant);
+                for variant in variants {
+                    let mut v = Variant::new(self.db.untern_string(variant.name).to_string());
+                    let fields = self.db.members(variant.def_id);
+                    let fields = fields
+                        .iter()
+                        .filter(|member| member.kind == hir::MemberKind::Field);
+                    for member in fields {
+                        let ty = self.declaration_ty(def_id, self.db.ty(member.def_id));
+                        v = v.field(self.db.untern_string(member.name).to_string(), ty);
+                    }
+                    e = e.variant(v);
+                }
+                Definition::Enum(e)
+            }
+
+            ast::ast::Item::Def(_) => Definition::Fn(self.lower_fn(def_id, name)),
+
+            ast::ast::Item::Mod(_) | ast::ast::Item::Use(_) => {
+                panic!("cannot lower `{}` to MIR", name)
+            }
+        }
+    }
+
+    fn lower_fn(&mut self, def_id: DefId, name: String) -> Function {
+        let fn_body = self.db.fn_body(def_id);
+        let results = self.db.base_type_check(def_id);
+        let signature = self.db.signature(def_id);
+
+        let return_ty = self.declaration_ty(def_id, signature.output);
+        let args = fn_body
+            .arguments
+            .iter()
+            .zip(signature.inputs.iter())
+            .map(|(&argument, &input)| {
+                let name = self.variable_name(&fn_body, argument);
+                LocalDecl::new(self.declaration_ty(def_id, input), Some(name))
+            })
+            .collect();
+
+        let mut function = Function::new(return_ty, args, name);
+        let block = function.new_block();
+
+        let mut lowering = FnLowering {
+            items: self,
+            def_id,
+            fn_body: fn_body.clone(),
+            results,
+            function,
+            block,
+            locals: FxIndexMap::default(),
+            loops: FxIndexMap::default(),
+        };
+
+        for (index, &argument) in fn_body.arguments.iter().enumerate() {
+            lowering.locals.insert(argument, index + 1);
+        }
+
+        let result = lowering.lower_expression(fn_body.root_expression);
+        if !lowering.items.is_void(return_ty) {
+            lowering.assign(Place::Local(0), Rvalue::Use(result));
+        }
+        lowering.terminate(TerminatorKind::Return);
+
+        lowering.function
+    }
+
+    fn variable_name(&self, fn_body: &hir::FnBody, variable: hir::Variable) -> String {
+        let identifier = fn_body[variable].name;
+        self.db.untern_string(fn_body[identifier].text).to_string()
+    }
+
+    fn is_void(&self, ty: mir::Ty) -> bool {
+        self.context.builtin_type_of(ty) == Some(BuiltinType::Unit)
+    }
+
+    /// The MIR type for a type from the declaration of `owner`.
+    fn declaration_ty(&mut self, owner: DefId, ty: ty::Ty<Declaration>) -> mir::Ty {
+        match ty.base.untern(self.db) {
+            BoundVarOr::Known(base_data) => self.base_kind_ty(owner, base_data.kind),
+            BoundVarOr::BoundVar(_) => self.unsupported_ty(owner),
+        }
+    }
+
+    /// The MIR type for a type inferred within the body of `owner`.
+    fn inferred_ty(&mut self, owner: DefId, ty: ty::Ty<BaseInferred>) -> mir::Ty {
+        let base_data = ty.base.untern(self.db);
+        self.base_kind_ty(owner, base_data.kind)
+    }
+
+    fn base_kind_ty<F: TypeFamily>(&mut self, owner: DefId, kind: BaseKind<F>) -> mir::Ty {
+        let def_id = match kind {
+            BaseKind::Named(def_id) => def_id,
+            BaseKind::Placeholder(_) | BaseKind::Error => return self.unsupported_ty(owner),
+        };
+
+        let index = match def_id.untern(self.db) {
+            DefIdData::Builtin(name) => BuiltinType::from_name(&self.db.untern_string(name))
+                .map(|builtin| self.context.builtin_type(builtin)),
+            DefIdData::Item(_) => self.def_ids.get(&def_id).cloned(),
+            DefIdData::Field { .. }
+            | DefIdData::Variant { .. }
+            | DefIdData::TypeParameter { .. } => None,
+        };
+
+        match index {
+            Some(index) => self.context.simple_type_for_def_id(index),
+            None => self.unsupported_ty(owner),
+        }
+    }
+
+    /// The MIR definition to call for the function `def_id`.
+    fn function_index(&self, def_id: DefId) -> mir::DefId {
+        match def_id.untern(self.db) {
+            DefIdData::Builtin(name) => match BuiltinFn::from_name(&self.db.untern_string(name)) {
+                Some(builtin) => self.context.builtin_fn(builtin),
+                None => panic!("call to non-function `{}`", self.db.untern_string(name)),
+            },
+            _ => self.def_ids[&def_id],
+        }
+    }
+
+    /// The variants of the enum `owner`, in declaration order.
+    fn variants(&self, owner: DefId) -> Vec<DefId> {
+        self.db
+            .members(owner)
+            .iter()
+            .filter(|member| member.kind == hir::MemberKind::Variant)
+            .map(|member| member.def_id)
+            .collect()
+    }
+
+    /// If `def_id` is a variant, the enum that declares it and its
+    /// index among the variants of that enum.
+    fn variant_index(&self, def_id: DefId) -> Option<(DefId, usize)> {
+        match def_id.untern(self.db) {
+            DefIdData::Variant { owner, .. } => {
+                let index = self.variants(owner).iter().position(|&v| v == def_id);
+                Some((owner, index.unwrap()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Makes a value of the struct or variant `entity`, or calls the
+    /// function `entity`, with `arguments`.
+    fn construct(&self, entity: DefId, arguments: Vec<Operand>) -> Rvalue {
+        match self.variant_index(entity) {
+            Some((owner, index)) => Rvalue::Variant(self.def_ids[&owner], index, arguments),
+            None => Rvalue::Call(self.function_index(entity), arguments),
+        }
+    }
+
+    fn unsupported_ty(&mut self, owner: DefId) -> mir::Ty {
+        let error = Error {
+            def_id: owner,
+            message: "cannot generate code for a type used here".to_string(),
+        };
+        if !self.errors.contains(&error) {
+            self.errors.push(error);
+        }
+        self.context.error_ty()
+    }
+}
+
+struct FnLowering<'me, 'db, DB: TypeCheckDatabase> {
+    items: &'me mut ItemsLowering<'db, DB>,
+    def_id: DefId,
+    fn_body: Arc<hir::FnBody>,
+    results: TypeCheckResults<BaseInferred>,
+    function: Function,
+
+    /// The block we are currently appending statements to.
+    block: BlockId,
+
+    /// Maps each HIR variable to its MIR local.
+    locals: FxIndexMap<hir::Variable, VarId>,
+
+    /// Maps each HIR loop we are inside of to its blocks.
+    loops: FxIndexMap<hir::Expression, LoopBlocks>,
+}
+
+#[derive(Copy, Clone)]
+struct LoopBlocks {
+    /// The first block of the loop body, where `continue` goes.
+    start: BlockId,
+
+    /// The block after the loop, where `break` goes.
+    exit: BlockId,
+
+    /// The local that `break` stores the value of the loop in.
+    result: VarId,
+}
+
+impl<DB> FnLowering<'_, '_, DB>
+where
+    DB: TypeCheckDatabase,
+{
+    fn expression_ty(&mut self, expression: hir::Expression) -> mir::Ty {
+        let ty = self.results.ty(expression);
+        self.items.inferred_ty(self.def_id, ty)
+    }
+
+    fn new_local(&mut self, ty: mir::Ty, name: Option<String>) -> VarId {
+        // Shadowed names would clash in the generated code, so only
+        // the first local with a given name keeps it. The variables
+        // named `_` hold values the user never named, like the counter
+        // of a `for` loop, and are temporaries like any other.
+        let name = name.filter(|name| {
+            name != "_"
+                && self
+                    .function
+                    .local_decls
+                    .iter()
+                    .all(|decl| decl.name.as_ref() != Some(name))
+        });
+        self.function.local_decls.push(LocalDecl::new(ty, name));
+        self.function.local_decls.len() - 1
+    }
+
+    fn assign(&mut self, place: Place, rvalue: Rvalue) {
+        self.function.basic_blocks[self.block].push_stmt(StatementKind::Assign(place, rvalue));
+    }
+
+    /// Ends the current block with `kind`.
+    fn terminate(&mut self, kind: TerminatorKind) {
+        self.function.basic_blocks[self.block].terminate(kind);
+    }
+
+    /// Ends the current block by jumping to `target`. Whatever we lower
+    /// next cannot be reached, but it still needs a block to go in.
+    fn jump_away(&mut self, target: BlockId) {
+        self.terminate(TerminatorKind::Goto(target));
+        self.block = self.function.new_block();
+    }
+
+    /// Lowers `expression`, storing its result into a fresh temporary
+    /// whose type is the type of the expression.
+    fn lower_to_temp(&mut self, expression: hir::Expression, rvalue: Rvalue) -> Operand {
+        let ty = self.expression_ty(expression);
+        let temp = self.function.new_temp(ty);
+        self.assign(Place::Local(temp), rvalue);
+        Operand::Move(Place::Local(temp))
+    }
+
+    /// Lowers `expression` into a local variable, reusing the local if
+    /// the expression is just a reference to one.
+    fn lower_to_local(&mut self, expression: hir::Expression) -> VarId {
+        match self.lower_expression(expression) {
+            Operand::Copy(Place::Local(var_id)) | Operand::Move(Place::Local(var_id)) => var_id,
+            operand => match self.lower_to_temp(expression, Rvalue::Use(operand)) {
+                Operand::Move(Place::Local(var_id)) => var_id,
+                _ => unreachable!(),
+            },
+        }
+    }
+
+    fn lower_expression(&mut self, expression: hir::Expression) -> Operand {
+        match self.fn_body[expression].clone() {
+            hir::ExpressionData::Let {
+                var,
+                ty: _,
+                initializer,
+                body,
+            } => {
+                let value = self.lower_expression(initializer);
+                let ty = self.results.ty(var);
+                let ty = self.items.inferred_ty(self.def_id, ty);
+                let name = self.items.variable_name(&self.fn_body, var);
+                let local = self.new_local(ty, Some(name));
+                self.assign(Place::Local(local), Rvalue::Use(value));
+                self.locals.insert(var, local);
+                self.lower_expression(body)
+            }
+
+            hir::ExpressionData::Place { perm: _, place } => Operand::Copy(self.lower_place(place)),
+
+            hir::ExpressionData::Assignment { place, value } => {
+                let value = self.lower_expression(value);
+                let place = self.lower_place(place);
+                self.assign(place, Rvalue::Use(value));
+                Operand::ConstantUnit
+            }
+
+            hir::ExpressionData::Call {
+                function,
+                arguments,
+            } => {
+                let arguments = arguments
+                    .iter()
+                    .map(|&argument| self.lower_expression(argument))
+                    .collect();
+                let rvalue = self.items.construct(function, arguments);
+                self.lower_to_temp(expression, rvalue)
+            }
+
+            hir::ExpressionData::Aggregate { entity, fields } => {
+                // Evaluate the fields in the order they were written, but
+                // pass them in the order they were declared.
+                let mut values: Vec<_> = fields
+                    .iter()
+                    .map(|&(identifier, value)| {
+                        let text = self.fn_body[identifier].text;
+                        (text, Some(self.lower_expression(value)))
+                    })
+                    .collect();
+                let arguments = self
+                    .items
+                    .db
+                    .members(entity)
+                    .iter()
+                    .filter(|member| member.kind == hir::MemberKind::Field)
+                    .map(|member| {
+                        values
+                            .iter_mut()
+                            .find(|(text, _)| *text == member.name)
+                            .and_then(|(_, value)| value.take())
+                            .unwrap()
+                    })
+                    .collect();
+                let rvalue = self.items.construct(entity, arguments);
+                self.lower_to_temp(expression, rvalue)
+            }
+
+            hir::ExpressionData::MethodCall {
+                owner,
+                method,
+                arguments,
+            } => {
+                let db = self.items.db;
+                let owner_def_id = match self.results.ty(owner).base.untern(db).kind {
+                    BaseKind::Named(def_id) => def_id,
+                    kind => panic!("method call on `{:?}`", kind),
+                };
+                let text = self.fn_body[method].text;
+                let method_def_id = db
+                    .member_def_id((owner_def_id, hir::MemberKind::Method, text))
+                    .unwrap_or_else(|| panic!("no method `{}`", db.untern_string(text)));
+
+                // The owner is passed as the `self` argument.
+                let owner = Operand::Copy(self.lower_place(owner));
+                let arguments = std::iter::once(owner)
+                    .chain(
+                        arguments
+                            .iter()
+                            .map(|&argument| self.lower_expression(argument)),
+                    )
+                    .collect();
+                let index = self.items.function_index(method_def_id);
+                self.lower_to_temp(expression, Rvalue::Call(index, arguments))
+            }
+
+            hir::ExpressionData::Sequence { first, second } => {
+                let _ = self.lower_expression(first);
+                self.lower_expression(second)
+            }
+
+            hir::ExpressionData::If {
+                condition,
+                if_true,
+                if_false,
+            } => {
+                let condition = self.lower_expression(condition);
+                self.lower_branch(
+                    expression,
+                    condition,
+                    |this| this.lower_expression(if_true),
+                    |this| this.lower_expression(if_false),
+                )
+            }
+
+            hir::ExpressionData::Loop { body } => {
+                let ty = self.expression_ty(expression);
+                let blocks = LoopBlocks {
+                    start: self.function.new_block(),
+                    exit: self.function.new_block(),
+                    result: self.function.new_temp(ty),
+                };
+                self.terminate(TerminatorKind::Goto(blocks.start));
+                self.loops.insert(expression, blocks);
+
+                self.block = blocks.start;
+                let _ = self.lower_expression(body);
+                self.terminate(TerminatorKind::Goto(blocks.start));
+
+                self.block = blocks.exit;
+                Operand::Move(Place::Local(blocks.result))
+            }
+
+            hir::ExpressionData::Break { target, value } => {
+                let value = self.lower_expression(value);
+                let blocks = self.loops[&target];
+                self.assign(Place::Local(blocks.result), Rvalue::Use(value));
+                self.jump_away(blocks.exit);
+                Operand::ConstantUnit
+            }
+
+            hir::ExpressionData::Continue { target } => {
+                let blocks = self.loops[&target];
+                self.jump_away(blocks.start);
+                Operand::ConstantUnit
+            }
+
+            // `a && b` is `if a { b } else { false }` and `a || b` is
+            // `if a { true } else { b }`, so `b` is only evaluated when
+            // it is needed.
+            hir::ExpressionData::Binary {
+                operator: hir::BinaryOperator::And,
+                left,
+                right,
+            } => {
+                let left = self.lower_expression(left);
+                self.lower_branch(
+                    expression,
+                    left,
+                    |this| this.lower_expression(right),
+                    |_| Operand::ConstantBool(false),
+                )
+            }
+
+            hir::ExpressionData::Binary {
+                operator: hir::BinaryOperator::Or,
+                left,
+                right,
+            } => {
+                let left = self.lower_expression(left);
+                self.lower_branch(
+                    expression,
+                    left,
+                    |_| Operand::ConstantBool(true),
+                    |this| this.lower_expression(right),
+                )
+            }
+
+            hir::ExpressionData::Binary {
+                operator,
+                left,
+                right,
+            } => {
+                let operator = match operator {
+                    hir::BinaryOperator::Add => BinOp::Add,
+                    hir::BinaryOperator::Subtract => BinOp::Sub,
+                    hir::BinaryOperator::Multiply => BinOp::Mul,
+                    hir::BinaryOperator::Divide => BinOp::Div,
+                    hir::BinaryOperator::Remainder => BinOp::Rem,
+                    hir::BinaryOperator::Equal => BinOp::Eq,
+                    hir::BinaryOperator::NotEqual => BinOp::Ne,
+                    hir::BinaryOperator::Less => BinOp::Lt,
+                    hir::BinaryOperator::LessEqual => BinOp::Le,
+                    hir::BinaryOperator::Greater => BinOp::Gt,
+                    hir::BinaryOperator::GreaterEqual => BinOp::Ge,
+                    hir::BinaryOperator::And | hir::BinaryOperator::Or => unreachable!(),
+                };
+                let left = self.lower_to_local(left);
+                let right = self.lower_to_local(right);
+                self.lower_to_temp(expression, Rvalue::BinaryOp(operator, left, right))
+            }
+
+            hir::ExpressionData::Unary { operator, value } => {
+                let operator = match operator {
+                    hir::UnaryOperator::Negate => UnOp::Neg,
+                    hir::UnaryOperator::Not => UnOp::Not,
+                };
+                let value = self.lower_to_local(value);
+                self.lower_to_temp(expression, Rvalue::UnaryOp(operator, value))
+            }
+
+            hir::ExpressionData::Literal { data } => self.lower_literal(data),
+
+            hir::ExpressionData::Interpolation { elements } => {
+                // The text between the expressions goes into the format
+                // string, with its braces doubled; each expression
+                // becomes a `{}` and an argument.
+                let mut format = String::new();
+                let mut arguments = vec![];
+                for &element in elements.iter() {
+                    match self.fn_body[element] {
+                        hir::ExpressionData::Literal {
+                            data:
+                                hir::LiteralData {
+                                    kind: hir::LiteralKind::String,
+                                    value,
+                                },
+                        } => {
+                            let text = self.items.db.untern_string(value);
+                            format.push_str(&text.replace('{', "{{").replace('}', "}}"));
+                        }
+                        _ => {
+                            format.push_str("{}");
+                            arguments.push(self.lower_expression(element));
+                        }
+                    }
+                }
+
+                let arguments = std::iter::once(Operand::ConstantString(format))
+                    .chain(arguments)
+                    .collect();
+                let index = self.items.context.builtin_fn(BuiltinFn::StringInterpolate);
+                self.lower_to_temp(expression, Rvalue::Call(index, arguments))
+            }
+
+            hir::ExpressionData::Match { scrutinee, arms } => {
+                let scrutinee = match self.lower_place(scrutinee) {
+                    Place::Local(var_id) => var_id,
+                    place => match self.lower_to_temp_place(scrutinee, place) {
+                        Place::Local(var_id) => var_id,
+                        _ => unreachable!(),
+                    },
+                };
+
+                // Each arm tests its pattern and falls through to the
+                // next arm if it does not match.
+                let ty = self.expression_ty(expression);
+                let result = self.function.new_temp(ty);
+                let join_block = self.function.new_block();
+                for arm in arms.iter() {
+                    let next_block = self.function.new_block();
+                    self.lower_pattern(arm.pattern, scrutinee, next_block);
+                    let value = self.lower_expression(arm.body);
+                    self.assign(Place::Local(result), Rvalue::Use(value));
+                    self.terminate(TerminatorKind::Goto(join_block));
+                    self.block = next_block;
+                }
+
+                // The type checker made sure the arms cover every value.
+                self.terminate(TerminatorKind::Unreachable);
+                self.block = join_block;
+                Operand::Move(Place::Local(result))
+            }
+
+            hir::ExpressionData::Unit {} => Operand::ConstantUnit,
+
+            hir::ExpressionData::Error { .. } => {
+                panic!("lowering a fn body that contains errors")
+            }
+        }
+    }
+
+    /// Branches on `condition`, lowering one arm with `if_true` and
+    /// the other with `if_false`. The result, which is the value of the
+    /// arm that ran, has the type of `expression`.
+    fn lower_branch(
+        &mut self,
+        expression: hir::Expression,
+        condition: Operand,
+        if_true: impl FnOnce(&mut Self) -> Operand,
+        if_false: impl FnOnce(&mut Self) -> Operand,
+    ) -> Operand {
+        let ty = self.expression_ty(expression);
+        let result = self.function.new_temp(ty);
+        let true_block = self.function.new_block();
+        let false_block = self.function.new_block();
+        let join_block = self.function.new_block();
+        self.terminate(TerminatorKind::If(condition, true_block, false_block));
+
+        self.block = true_block;
+        let value = if_true(self);
+        self.assign(Place::Local(result), Rvalue::Use(value));
+        self.terminate(TerminatorKind::Goto(join_block));
+
+        self.block = false_block;
+        let value = if_false(self);
+        self.assign(Place::Local(result), Rvalue::Use(value));
+        self.terminate(TerminatorKind::Goto(join_block));
+
+        self.block = join_block;
+        Operand::Move(Place::Local(result))
+    }
+
+    fn lower_literal(&self, data: hir::LiteralData) -> Operand {
+        let text = self.items.db.untern_string(data.value);
+        match data.kind {
+            hir::LiteralKind::String => Operand::ConstantString(text.to_string()),
+            hir::LiteralKind::Integer => Operand::ConstantInt(text.parse().unwrap()),
+            hir::LiteralKind::Float => Operand::ConstantFloat(text.parse().unwrap()),
+            hir::LiteralKind::Bool => Operand::ConstantBool(text.parse().unwrap()),
+        }
+    }
+
+    /// Tests whether the value in `local` matches `pattern`, binding
+    /// the variables of the pattern. We continue in the current block
+    /// if it matches, and jump to `otherwise` if it does not.
+    fn lower_pattern(&mut self, pattern: hir::Pattern, local: VarId, otherwise: BlockId) {
+        match self.fn_body[pattern].clone() {
+            hir::PatternData::Wildcard => {}
+
+            hir::PatternData::Binding { var } => {
+                let ty = self.pattern_ty(pattern);
+                let name = self.items.variable_name(&self.fn_body, var);
+                let binding = self.new_local(ty, Some(name));
+                self.assign(
+                    Place::Local(binding),
+                    Rvalue::Use(Operand::Copy(Place::Local(local))),
+                );
+                self.locals.insert(var, binding);
+            }
+
+            hir::PatternData::Literal { data } => {
+                let condition = match data.kind {
+                    hir::LiteralKind::Bool => match self.lower_literal(data) {
+                        Operand::ConstantBool(true) => Operand::Copy(Place::Local(local)),
+                        _ => {
+                            let ty = self.items.context.builtin_ty(BuiltinType::Bool);
+                            let temp = self.function.new_temp(ty);
+                            self.assign(Place::Local(temp), Rvalue::UnaryOp(UnOp::Not, local));
+                            Operand::Move(Place::Local(temp))
+                        }
+                    },
+                    _ => {
+                        let ty = self.pattern_ty(pattern);
+                        let constant = self.function.new_temp(ty);
+                        let value = self.lower_literal(data);
+                        self.assign(Place::Local(constant), Rvalue::Use(value));
+                        let ty = self.items.context.builtin_ty(BuiltinType::Bool);
+                        let temp = self.function.new_temp(ty);
+                        self.assign(
+                            Place::Local(temp),
+                            Rvalue::BinaryOp(BinOp::Eq, local, constant),
+                        );
+                        Operand::Move(Place::Local(temp))
+                    }
+                };
+                let matched = self.function.new_block();
+                self.terminate(TerminatorKind::If(condition, matched, otherwise));
+                self.block = matched;
+            }
+
+            hir::PatternData::Constructor { entity, fields } => {
+                let variant = self.items.variant_index(entity);
+                if let Some((owner, index)) = variant {
+                    let matched = self.function.new_block();
+                    let targets = (0..self.items.variants(owner).len())
+                        .map(|i| if i == index { matched } else { otherwise })
+                        .collect();
+                    self.terminate(TerminatorKind::Switch(Place::Local(local), targets));
+                    self.block = matched;
+                }
+
+                for &(identifier, field) in fields.iter() {
+                    if let hir::PatternData::Wildcard = self.fn_body[field] {
+                        continue;
+                    }
+
+                    let name = self.items.db.untern_string(self.fn_body[identifier].text);
+                    let place = match variant {
+                        Some((_, index)) => Place::VariantField(local, index, name.to_string()),
+                        None => Place::Field(local, name.to_string()),
+                    };
+                    let ty = self.pattern_ty(field);
+                    let temp = self.function.new_temp(ty);
+                    self.assign(Place::Local(temp), Rvalue::Use(Operand::Copy(place)));
+                    self.lower_pattern(field, temp, otherwise);
+                }
+            }
+
+            hir::PatternData::Error { .. } => panic!("lowering a fn body that contains errors"),
+        }
+    }
+
+    fn pattern_ty(&mut self, pattern: hir::Pattern) -> mir::Ty {
+        let ty = self.results.ty(pattern);
+        self.items.inferred_ty(self.def_id, ty)
+    }
+
+    fn lower_place(&mut self, place: hir::Place) -> Place {
+        match self.fn_body[place] {
+            hir::PlaceData::Variable(var) => Place::Local(self.locals[&var]),
+
+            hir::PlaceData::Temporary(expression) => Place::Local(self.lower_to_local(expression)),
+
+            hir::PlaceData::Field { owner, name } => {
+                let owner = match self.lower_place(owner) {
+                    Place::Local(var_id) => var_id,
+                    place => match self.lower_to_temp_place(owner, place) {
+                        Place::Local(var_id) => var_id,
+                        _ => unreachable!(),
+                    },
+                };
+                let name = self.items.db.untern_string(self.fn_body[name].text);
+                Place::Field(owner, name.to_string())
+            }
+        }
+    }
+
+    /// Copies the value of `place` (the lowering of the HIR place
+    /// `hir_place`) into a fresh temporary.
+    fn lower_to_temp_place(&mut self, hir_place: hir::Place, place: Place) -> Place {
+        let ty = self.results.ty(hir_place);
+        let ty = self.items.inferred_ty(self.def_id, ty);
+        let temp = self.function.new_temp(ty);
+        self.assign(Place::Local(temp), Rvalue::Use(Operand::Copy(place)));
+        Place::Local(temp)
+    }
+}
+
+/// Joins the parts of the path of an item with `__`. So that distinct
+/// paths get distinct names, an underscore that could be taken for
+/// part of a separator -- one that starts or ends a part, that is next
+/// to another underscore, or that comes before a `0` -- is written
+/// `_0`: then every `_` in the result is either followed by `0` and
+/// stands for an underscore, or starts a separator.
+fn mangle(parts: &[String]) -> String {
+    let parts: Vec<_> = parts
+        .iter()
+        .map(|part| {
+            let chars: Vec<char> = part.chars().collect();
+            let mut mangled = String::new();
+            for (i, &c) in chars.iter().enumerate() {
+                let plain = c != '_'
+                    || (i > 0
+                        && chars[i - 1] != '_'
+                        && i + 1 < chars.len()
+                        && chars[i + 1] != '_'
+                        && chars[i + 1] != '0');
+                if plain {
+                    mangled.push(c);
+                } else {
+                    mangled.push_str("_0");
+                }
+            }
+            mangled
+        })
+        .collect();
+    parts.join("__")
+}
@@ -1,5 +1,8 @@
+pub use prelude::{BuiltinFn, BuiltinType};
+
 pub type DefId = usize;
 pub type VarId = usize;
+pub type BlockId = usize;
 
 // Dummy for now
 #[derive(Copy, Clone, Debug)]
@@ -47,6 +50,12 @@ impl Function {
     pub fn push_block(&mut self, block: BasicBlock) {
         self.basic_blocks.push(block);
     }
+
+    /// Adds an empty block to the end of the function.
+    pub fn new_block(&mut self) -> BlockId {
+        self.push_block(BasicBlock::new());
+        self.basic_blocks.len() - 1
+    }
 }
 
 #[derive(Debug)]
@@ -69,6 +78,46 @@ impl Struct {
     }
 }
 
+#[derive(Debug)]
+pub struct Enum {
+    pub variants: Vec<Variant>,
+    pub name: String,
+}
+
+impl Enum {
+    pub fn variant(mut self, variant: Variant) -> Self {
+        self.variants.push(variant);
+        self
+    }
+
+    pub fn new(name: String) -> Self {
+        Enum {
+            name,
+            variants: vec![],
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Variant {
+    pub fields: Vec<Field>,
+    pub name: String,
+}
+
+impl Variant {
+    pub fn field(mut self, name: String, ty: Ty) -> Self {
+        self.fields.push(Field { ty, name });
+        self
+    }
+
+    pub fn new(name: String) -> Self {
+        Variant {
+            name,
+            fields: vec![],
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Field {
     pub ty: Ty,
@@ -125,6 +174,21 @@ pub struct Terminator {
 #[derive(Debug)]
 pub enum TerminatorKind {
     Return,
+
+    /// Continues with the given block.
+    Goto(BlockId),
+
+    /// Continues with the first block if the operand is `true`, and
+    /// with the second if it is `false`.
+    If(Operand, BlockId, BlockId),
+
+    /// Continues with the block for the variant that the enum in the
+    /// place holds: the first block for its first variant, and so on.
+    Switch(Place, Vec<BlockId>),
+
+    /// Ends a block that is never reached, like the one after the last
+    /// arm of a `match` that covers every value.
+    Unreachable,
 }
 
 #[derive(Debug)]
@@ -133,14 +197,21 @@ pub enum Place {
     Static(DefId),
     //FIXME: this is a simplifed projection for now
     Field(VarId, String),
+    /// A field of the given variant of the enum in the local, which
+    /// must hold that variant.
+    VariantField(VarId, usize, String),
 }
 
 #[derive(Debug)]
 pub enum Rvalue {
     Use(Operand),
     BinaryOp(BinOp, VarId, VarId),
+    UnaryOp(UnOp, VarId),
     //FIXME: MIR has this as a Terminator, presumably because stack can unwind
     Call(DefId, Vec<Operand>),
+    /// Makes the given variant of an enum from its fields, in the order
+    /// they are declared.
+    Variant(DefId, usize, Vec<Operand>),
 }
 
 #[derive(Debug)]
@@ -149,13 +220,31 @@ pub enum Operand {
     Move(Place),
     //FIXME: Move to Box<Constant>
     ConstantInt(i32),
+    ConstantFloat(f64),
+    ConstantBool(bool),
     ConstantString(String),
+    ConstantUnit,
 }
 
 #[derive(Debug)]
 pub enum BinOp {
     Add,
     Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug)]
+pub enum UnOp {
+    Neg,
+    Not,
 }
 
 #[derive(Debug)]
@@ -181,26 +270,16 @@ impl LocalDecl {
     }
 }
 
-pub mod builtin_type {
-    #[allow(unused)]
-    pub const UNKNOWN: usize = 0;
-    pub const VOID: usize = 1;
-    pub const I32: usize = 2;
-    pub const STRING: usize = 3;
-    pub const ERROR: usize = 100;
-}
-
-#[derive(Debug)]
-pub enum BuiltinFn {
-    StringInterpolate,
-}
-
 #[derive(Debug)]
 pub enum Definition {
-    Builtin,
+    /// Stands in for a definition that could not be produced; also
+    /// the type of expressions whose type is an error.
+    Error,
+    BuiltinType(BuiltinType),
     BuiltinFn(BuiltinFn),
     Fn(Function),
     Struct(Struct),
+    Enum(Enum),
 }
 
 pub struct Context {
@@ -208,14 +287,18 @@ pub struct Context {
 }
 
 impl Context {
+    /// Creates a context containing the error definition followed by
+    /// the prelude.
     pub fn new() -> Context {
-        let mut definitions = vec![];
+        let mut definitions = vec![Definition::Error];
 
-        for _ in 0..(builtin_type::ERROR + 1) {
-            definitions.push(Definition::Builtin); // UNKNOWN
+        for &builtin in BuiltinType::ALL {
+            definitions.push(Definition::BuiltinType(builtin));
         }
 
-        definitions.push(Definition::BuiltinFn(BuiltinFn::StringInterpolate));
+        for &builtin in BuiltinFn::ALL {
+            definitions.push(Definition::BuiltinFn(builtin));
+        }
 
         Context { definitions }
     }
@@ -232,4 +315,41 @@ impl Context {
     pub fn get_def_id_for_ty(&self, ty: Ty) -> Option<DefId> {
         Some(ty.def_id)
     }
+
+    /// The type of expressions whose type is an error.
+    pub fn error_ty(&self) -> Ty {
+        self.simple_type_for_def_id(0)
+    }
+
+    pub fn builtin_type(&self, builtin: BuiltinType) -> DefId {
+        self.definitions
+            .iter()
+            .position(|definition| match definition {
+                Definition::BuiltinType(b) => *b == builtin,
+                _ => false,
+            })
+            .unwrap()
+    }
+
+    pub fn builtin_ty(&self, builtin: BuiltinType) -> Ty {
+        self.simple_type_for_def_id(self.builtin_type(builtin))
+    }
+
+    pub fn builtin_fn(&self, builtin: BuiltinFn) -> DefId {
+        self.definitions
+            .iter()
+            .position(|definition| match definition {
+                Definition::BuiltinFn(b) => *b == builtin,
+                _ => false,
+            })
+            .unwrap()
+    }
+
+    /// If `ty` is a built-in type, which one.
+    pub fn builtin_type_of(&self, ty: Ty) -> Option<BuiltinType> {
+        match &self.definitions[ty.def_id] {
+            Definition::BuiltinType(builtin) => Some(*builtin),
+            _ => None,
+        }
+    }
 }
@@ -1,4 +1,5 @@
 mod debug;
+mod shift;
 
 #[cfg(test)]
 mod test_helpers;
@@ -19,18 +20,91 @@ pub type Identifier = Spanned<StringId>;
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum Item {
     Struct(Struct),
+    Enum(Enum),
     Def(Def),
+    Mod(Mod),
+    Use(Use),
 }
 
 impl Item {
+    /// The name the item is known by in its module; for a `use`, the
+    /// alias or else the last component of the path.
     pub fn name(&self) -> StringId {
         match self {
             Item::Struct(s) => s.name.node,
+            Item::Enum(e) => e.name.node,
             Item::Def(d) => d.name.node,
+            Item::Mod(m) => m.name.node,
+            Item::Use(u) => u.name().node,
+        }
+    }
+
+    pub fn docs(&self) -> &[Spanned<StringId>] {
+        match self {
+            Item::Struct(s) => &s.docs,
+            Item::Enum(e) => &e.docs,
+            Item::Def(d) => &d.docs,
+            Item::Mod(m) => &m.docs,
+            Item::Use(u) => &u.docs,
+        }
+    }
+
+    pub fn visibility(&self) -> Visibility {
+        match self {
+            Item::Struct(s) => s.visibility,
+            Item::Enum(e) => e.visibility,
+            Item::Def(d) => d.visibility,
+            Item::Mod(m) => m.visibility,
+            Item::Use(u) => u.visibility,
+        }
+    }
+}
+
+/// Where an item can be named from.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Visibility {
+    /// Within the module the item is in, and the modules inside that.
+    Private,
+
+    /// Everywhere, because the item is marked `pub`; the span is that
+    /// of the keyword.
+    Public(Span),
+}
+
+/// A module whose items are in another file, like `mod shapes`.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, new)]
+pub struct Mod {
+    pub docs: Docs,
+    pub visibility: Visibility,
+    pub name: Identifier,
+    pub span: Span,
+}
+
+/// An import of an item from another module, like
+/// `use shapes::Circle as Round`.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, new)]
+pub struct Use {
+    pub docs: Docs,
+    pub visibility: Visibility,
+    pub path: Path,
+    pub alias: Option<Identifier>,
+    pub span: Span,
+}
+
+impl Use {
+    /// The name the imported item is known by.
+    pub fn name(&self) -> Identifier {
+        match self.alias {
+            Some(alias) => alias,
+            None => *self.path.components.last().expect("empty path"),
         }
     }
 }
 
+/// The `///` comments before an item or field, one per line. Each is
+/// the text of its line, slashes included.
+pub type Docs = Vec<Spanned<StringId>>;
+
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum BlockItem {
     Item(Arc<Item>),
@@ -60,8 +134,16 @@ pub struct Module {
 
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, new)]
 pub struct Struct {
+    pub docs: Docs,
+    pub visibility: Visibility,
     pub name: Spanned<StringId>,
+
+    /// The generic type parameters, like the `T` in `struct Vec<T>`.
+    pub generics: Vec<Identifier>,
     pub fields: Vec<Field>,
+
+    /// The methods defined within the struct, after its fields.
+    pub methods: Vec<Def>,
     pub span: Span,
 }
 
@@ -73,11 +155,54 @@ impl HasSpan for Struct {
     }
 }
 
+/// An enum, like `enum Shape { Circle(f64), Rect { w: f64, h: f64 } }`.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, new)]
+pub struct Enum {
+    pub docs: Docs,
+    pub visibility: Visibility,
+    pub name: Identifier,
+    pub generics: Vec<Identifier>,
+    pub variants: Vec<Variant>,
+    pub span: Span,
+}
+
+impl HasSpan for Enum {
+    type Inner = Enum;
+
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, new)]
+pub struct Variant {
+    pub docs: Docs,
+    pub name: Identifier,
+    pub fields: VariantFields,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum VariantFields {
+    /// `Empty`, which has no fields.
+    Unit,
+
+    /// `Circle(f64)`, whose fields are named by their position: `0`,
+    /// `1` and so on.
+    Tuple(Vec<Spanned<Type>>),
+
+    /// `Rect { w: f64, h: f64 }`
+    Struct(Vec<Field>),
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, new)]
 pub struct Field {
-    name: Identifier,
-    ty: Spanned<Type>,
-    span: Span,
+    /// Always empty for parameters, which cannot be documented.
+    #[new(default)]
+    pub docs: Docs,
+    pub name: Identifier,
+    pub ty: Spanned<Type>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
@@ -90,6 +215,10 @@ pub enum ConstructField {
 pub struct Type {
     pub mode: Option<Spanned<Mode>>,
     pub name: Spanned<StringId>,
+
+    /// The type arguments, like the `own String` in
+    /// `Option<own String>`.
+    pub generics: Vec<Spanned<Type>>,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
@@ -124,18 +253,71 @@ impl From<Mode> for &'static str {
 pub enum Pattern {
     Underscore,
     Identifier(Identifier, Option<Spanned<Mode>>),
+
+    /// A literal, like `1` or `"a"`, which matches only that value.
+    Literal(Literal),
+
+    /// A unit variant, like `Shape::Empty`.
+    Path(Path),
+
+    /// A tuple variant, like `Shape::Circle(r)`.
+    Tuple(Path, Vec<Spanned<Pattern>>),
+
+    /// A struct or struct variant, like `Point { x, y: 0 }`.
+    Struct(Path, Vec<FieldPattern>),
+}
+
+/// A field in a struct pattern: either `x: pattern`, or just `x`,
+/// which binds the field to a variable of the same name.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, new)]
+pub struct FieldPattern {
+    pub name: Identifier,
+    pub pattern: Option<Spanned<Pattern>>,
 }
 
+/// A name, like `Point`, or a name within another, like
+/// `Shape::Circle`.
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, new)]
 pub struct Path {
     pub components: Vec<Identifier>,
 }
 
+impl Path {
+    /// The identifier, if the path is a single one, like `a` rather
+    /// than `a::b`.
+    pub fn single(&self) -> Option<Identifier> {
+        match &self.components[..] {
+            [id] => Some(*id),
+            _ => None,
+        }
+    }
+}
+
+impl HasSpan for Path {
+    type Inner = Path;
+
+    fn span(&self) -> Span {
+        let first = self.components.first().expect("empty path");
+        let last = self.components.last().expect("empty path");
+        first.span().to(last.span())
+    }
+}
+
 pub enum Statement {}
 
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, new)]
 pub struct Def {
+    pub docs: Docs,
+    pub visibility: Visibility,
     pub name: Identifier,
+
+    /// The generic type parameters, like the `T` in `def id<T>`.
+    pub generics: Vec<Identifier>,
+
+    /// The `self` parameter of a method, like `borrow self`; the span
+    /// covers both the mode and `self`.
+    pub receiver: Option<Spanned<Mode>>,
+
     pub parameters: Vec<Field>,
     pub ret: Option<Spanned<Type>>,
     pub body: Spanned<Block>,
@@ -147,10 +329,25 @@ pub enum Expression {
     Block(Spanned<Block>),
     ConstructStruct(ConstructStruct),
     Call(Spanned<Call>),
+    Field(Spanned<FieldAccess>),
+    SelfRef(Span),
+    If(Spanned<If>),
+    Loop(Spanned<Loop>),
+    Break(Spanned<Break>),
+    Continue(Spanned<Continue>),
     Ref(Identifier),
+
+    /// A unit variant, like `Shape::Empty`.
+    Path(Path),
+    Match(Spanned<Match>),
     Binary(Spanned<Op>, Box<Expression>, Box<Expression>),
+    Unary(Spanned<UnaryOp>, Box<Expression>),
     Interpolation(Vec<InterpolationElement>, Span),
     Literal(Literal),
+
+    /// Text that did not parse; the parser skipped it and recorded a
+    /// syntax error.
+    Error(Span),
 }
 
 impl Expression {
@@ -179,10 +376,20 @@ impl HasSpan for Expression {
             Block(block) => block.span(),
             ConstructStruct(construct) => construct.span(),
             Call(call) => call.span(),
+            Field(field) => field.span(),
+            SelfRef(span) => *span,
+            If(if_) => if_.span(),
+            Loop(loop_) => loop_.span(),
+            Break(break_) => break_.span(),
+            Continue(continue_) => continue_.span(),
             Ref(id) => id.span(),
+            Path(path) => path.span(),
+            Match(match_) => match_.span(),
             Binary(_, left, right) => left.span().to(right.span()),
+            Unary(op, value) => op.span().to(value.span()),
             Interpolation(_, span) => *span,
             Literal(lit) => lit.span(),
+            Error(span) => *span,
         }
     }
 }
@@ -193,6 +400,39 @@ pub enum Op {
     Sub,
     Mul,
     Div,
+    Rem,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    And,
+    Or,
+}
+
+impl Op {
+    /// How tightly the operator binds its operands; `a + b * c` is
+    /// `a + (b * c)` because `*` has a higher precedence than `+`.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            Op::Or => 1,
+            Op::And => 2,
+            Op::Equal
+            | Op::NotEqual
+            | Op::Less
+            | Op::LessEqual
+            | Op::Greater
+            | Op::GreaterEqual => 3,
+            Op::Add | Op::Sub => 4,
+            Op::Mul | Op::Div | Op::Rem => 5,
+        }
+    }
+
+    /// Comparisons cannot be chained, as in `a < b < c`.
+    pub fn is_comparison(&self) -> bool {
+        self.precedence() == 3
+    }
 }
 
 impl fmt::Display for Op {
@@ -205,13 +445,45 @@ impl fmt::Display for Op {
                 Op::Sub => "-",
                 Op::Mul => "*",
                 Op::Div => "/",
+                Op::Rem => "%",
+                Op::Equal => "==",
+                Op::NotEqual => "!=",
+                Op::Less => "<",
+                Op::LessEqual => "<=",
+                Op::Greater => ">",
+                Op::GreaterEqual => ">=",
+                Op::And => "&&",
+                Op::Or => "||",
             }
         )
     }
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+}
+
+impl fmt::Display for UnaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                UnaryOp::Neg => "-",
+                UnaryOp::Not => "!",
+            }
+        )
+    }
+}
+
+/// A part of an interpolated string like `"a {{b}} c"`.
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum InterpolationElement {
+    /// The text between two expressions. Like the text of a string
+    /// literal, it still includes its delimiters, as in `"a {{` or
+    /// `}} c"`.
     String(Spanned<StringId>),
     Expression(Expression),
 }
@@ -219,6 +491,14 @@ pub enum InterpolationElement {
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum Literal {
     String(Spanned<StringId>),
+
+    /// The text of an integer literal, like `1_000` or `0xff`.
+    Integer(Spanned<StringId>),
+
+    /// The text of a float literal, like `1.5` or `2e-3`.
+    Float(Spanned<StringId>),
+
+    Bool(Spanned<bool>),
 }
 
 impl HasSpan for Literal {
@@ -227,15 +507,19 @@ impl HasSpan for Literal {
     fn span(&self) -> Span {
         match self {
             Literal::String(string) => string.span(),
+            Literal::Integer(integer) => integer.span(),
+            Literal::Float(float) => float.span(),
+            Literal::Bool(boolean) => boolean.span(),
         }
     }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, new)]
 pub struct ConstructStruct {
-    name: Identifier,
-    fields: Vec<ConstructField>,
-    span: Span,
+    /// The struct, or the struct variant, like `Shape::Rect`.
+    pub path: Path,
+    pub fields: Vec<ConstructField>,
+    pub span: Span,
 }
 
 impl HasSpan for ConstructStruct {
@@ -246,15 +530,20 @@ impl HasSpan for ConstructStruct {
     }
 }
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, new)]
-
 pub struct Call {
-    callee: Callee,
-    arguments: Vec<Expression>,
+    pub callee: Callee,
+    pub arguments: Vec<Expression>,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, new)]
 pub enum Callee {
     Identifier(Identifier),
+
+    /// A tuple variant, like `Shape::Circle` in `Shape::Circle(1.0)`.
+    Path(Path),
+
+    /// The `a.m` in `a.m(b)`, which calls the method `m` on `a`.
+    Method(Box<Expression>, Identifier),
 }
 
 impl From<Identifier> for Callee {
@@ -263,26 +552,94 @@ impl From<Identifier> for Callee {
     }
 }
 
+/// `a.b`, which reads the field `b` of `a`.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, new)]
+pub struct FieldAccess {
+    pub owner: Box<Expression>,
+    pub name: Identifier,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, new)]
 pub struct Let {
-    pattern: Spanned<Pattern>,
-    ty: Option<Spanned<Type>>,
-    init: Option<Expression>,
+    pub pattern: Spanned<Pattern>,
+    pub ty: Option<Spanned<Type>>,
+    pub init: Option<Expression>,
 }
 
+/// A conditional, like `if a { .. } else { .. }`, or `if let a = b {
+/// .. }` which runs its block when `b` matches the pattern `a`.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum If {
-    If(Box<Expression>, Block, Option<ChainedElse>),
-    IfLet(Pattern, Box<Expression>, Block, Option<ChainedElse>),
+    If(Box<Expression>, Spanned<Block>, Option<ChainedElse>),
+    IfLet(
+        Spanned<Pattern>,
+        Box<Expression>,
+        Spanned<Block>,
+        Option<ChainedElse>,
+    ),
+}
+
+/// `match a { p => b, .. }`, which evaluates the body of the first
+/// arm whose pattern matches `a`.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, new)]
+pub struct Match {
+    pub scrutinee: Box<Expression>,
+    pub arms: Vec<MatchArm>,
 }
 
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, new)]
+pub struct MatchArm {
+    pub pattern: Spanned<Pattern>,
+    pub body: Expression,
+}
+
+/// What follows the `else` of an `if`: either a block or another
+/// `if`, as in `else if c { .. }`.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum ChainedElse {
-    Block(Block),
-    If(Box<If>),
+    Block(Spanned<Block>),
+    If(Box<Spanned<If>>),
+}
+
+/// A loop, optionally labeled, as in `'outer: loop { .. }`.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, new)]
+pub struct Loop {
+    pub label: Option<Identifier>,
+    pub kind: LoopKind,
+    pub body: Spanned<Block>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum LoopKind {
+    /// `loop { .. }`, which runs until it is broken out of.
+    Loop,
+
+    /// `while a { .. }`
+    While(Box<Expression>),
+
+    /// `for a in b..c { .. }`, which runs once for each integer from
+    /// `b` up to but not including `c`.
+    For(Spanned<Pattern>, Box<Expression>, Box<Expression>),
+}
+
+/// `break`, optionally naming the loop to break out of and the value
+/// that loop produces, as in `break 'outer 1`.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, new)]
+pub struct Break {
+    pub label: Option<Identifier>,
+    pub value: Option<Box<Expression>>,
+}
+
+/// `continue`, optionally naming the loop to continue, as in
+/// `continue 'outer`.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, new)]
+pub struct Continue {
+    pub label: Option<Identifier>,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, new)]
 pub struct Block {
-    expressions: Vec<BlockItem>,
+    pub expressions: Vec<BlockItem>,
 }
 
 impl Block {
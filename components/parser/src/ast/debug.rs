@@ -54,7 +54,16 @@ impl DebugModuleTable for Item {
     fn debug(&self, f: &mut fmt::Formatter<'_>, table: &'table ModuleTable) -> fmt::Result {
         match self {
             Item::Struct(s) => write!(f, "{:#?}", Debuggable::from(s, table)),
+            Item::Enum(e) => write!(f, "{:#?}", Debuggable::from(e, table)),
             Item::Def(d) => write!(f, "{:#?}", Debuggable::from(d, table)),
+            Item::Mod(m) => write!(f, "mod {}", table.lookup(m.name.node)),
+            Item::Use(u) => {
+                write!(f, "use {:?}", Debuggable::from(&u.path, table))?;
+                match u.alias {
+                    Some(alias) => write!(f, " as {}", table.lookup(alias.node)),
+                    None => Ok(()),
+                }
+            }
         }
     }
 }
@@ -76,10 +85,40 @@ impl DebugModuleTable for Struct {
         f.debug_struct("Struct")
             .field("name", &table.lookup(self.name.node))
             .field("fields", &DebuggableVec::from(&self.fields, table))
+            .field("methods", &DebuggableVec::from(&self.methods, table))
+            .finish()
+    }
+}
+
+impl DebugModuleTable for Enum {
+    fn debug(&self, f: &mut fmt::Formatter<'_>, table: &'table ModuleTable) -> fmt::Result {
+        f.debug_struct("Enum")
+            .field("name", &table.lookup(self.name.node))
+            .field("variants", &DebuggableVec::from(&self.variants, table))
             .finish()
     }
 }
 
+impl DebugModuleTable for Variant {
+    fn debug(&self, f: &mut fmt::Formatter<'_>, table: &'table ModuleTable) -> fmt::Result {
+        write!(f, "{}", table.lookup(self.name.node))?;
+
+        match &self.fields {
+            VariantFields::Unit => Ok(()),
+            VariantFields::Tuple(types) => {
+                let types: Vec<_> = types
+                    .iter()
+                    .map(|ty| format!("{:?}", Debuggable::from(&ty.node, table)))
+                    .collect();
+                write!(f, "({})", types.join(", "))
+            }
+            VariantFields::Struct(fields) => {
+                write!(f, " {:?}", DebuggableVec::from(fields, table))
+            }
+        }
+    }
+}
+
 impl DebugModuleTable for Field {
     fn debug(&self, f: &mut fmt::Formatter<'_>, table: &'table ModuleTable) -> fmt::Result {
         write!(
@@ -94,14 +133,25 @@ impl DebugModuleTable for Field {
 impl DebugModuleTable for Type {
     fn debug(&self, f: &mut fmt::Formatter<'_>, table: &'table ModuleTable) -> fmt::Result {
         match self.mode {
-            None => write!(f, "{:?}", &Debuggable::from(&self.name.node, table)),
+            None => write!(f, "{:?}", &Debuggable::from(&self.name.node, table))?,
             Some(mode) => write!(
                 f,
                 "{:?} {:?}",
                 &Debuggable::from(&self.mode, table),
                 &Debuggable::from(&self.name.node, table)
-            ),
+            )?,
+        }
+
+        if !self.generics.is_empty() {
+            let generics: Vec<_> = self
+                .generics
+                .iter()
+                .map(|ty| format!("{:?}", Debuggable::from(&ty.node, table)))
+                .collect();
+            write!(f, "<{}>", generics.join(", "))?;
         }
+
+        Ok(())
     }
 }
 
@@ -139,6 +189,7 @@ impl DebugModuleTable for Def {
     fn debug(&self, f: &mut fmt::Formatter<'_>, table: &'table ModuleTable) -> fmt::Result {
         f.debug_struct("Def")
             .field("name", &table.lookup(self.name.node))
+            .field("receiver", &Debuggable::from(&self.receiver, table))
             .field("parameters", &DebuggableVec::from(&self.parameters, table))
             .field("ret", &Debuggable::from(&self.ret, table))
             .field("body", &Debuggable::from(&self.body, table))
@@ -189,7 +240,10 @@ impl DebugModuleTable for Let {
 
         match &self.ty {
             None => {}
-            Some(ty) => unimplemented!("Debug output for annotated lets"),
+            Some(ty) => {
+                write!(f, ": ")?;
+                ty.debug(f, table)?
+            }
         };
 
         match &self.init {
@@ -214,10 +268,40 @@ impl DebugModuleTable for Pattern {
                 id.debug(f, table)
             }
             Pattern::Identifier(id, None) => id.debug(f, table),
+            Pattern::Literal(literal) => literal.debug(f, table),
+            Pattern::Path(path) => path.debug(f, table),
+            Pattern::Tuple(path, patterns) => {
+                path.debug(f, table)?;
+                write!(f, "{:?}", DebuggableVec::from(patterns, table))
+            }
+            Pattern::Struct(path, fields) => {
+                path.debug(f, table)?;
+                write!(f, " {{ ")?;
+                for field in fields {
+                    field.name.debug(f, table)?;
+                    if let Some(pattern) = &field.pattern {
+                        write!(f, ": ")?;
+                        pattern.debug(f, table)?;
+                    }
+                    write!(f, ", ")?;
+                }
+                write!(f, "}}")
+            }
         }
     }
 }
 
+impl DebugModuleTable for Path {
+    fn debug(&self, f: &mut fmt::Formatter<'_>, table: &'table ModuleTable) -> fmt::Result {
+        let components: Vec<_> = self
+            .components
+            .iter()
+            .map(|component| table.lookup(component.node).to_string())
+            .collect();
+        write!(f, "{}", components.join("::"))
+    }
+}
+
 impl DebugModuleTable for Mode {
     fn debug(&self, f: &mut fmt::Formatter<'_>, table: &'table ModuleTable) -> fmt::Result {
         let out: &str = (*self).into();
@@ -230,19 +314,87 @@ impl DebugModuleTable for Expression {
         use self::Expression::*;
 
         match self {
-            Block(block) => write!(f, "<block>"),
+            Block(block) => block.debug(f, table),
             ConstructStruct(construct) => {
-                construct.name.debug(f, table)?;
-                write!(f, " {{ ... }}")
+                construct.path.debug(f, table)?;
+                write!(f, " {{ ")?;
+                for field in &construct.fields {
+                    match field {
+                        ConstructField::Longhand(field) => field.debug(f, table)?,
+                        ConstructField::Shorthand(name) => name.debug(f, table)?,
+                    }
+                    write!(f, ", ")?;
+                }
+                write!(f, "}}")
             }
             Call(call) => call.debug(f, table),
+            Field(field) => {
+                field.node.owner.debug(f, table)?;
+                write!(f, ".")?;
+                field.node.name.debug(f, table)
+            }
+            SelfRef(_) => write!(f, "self"),
+            If(if_) => if_.debug(f, table),
+            Loop(loop_) => loop_.debug(f, table),
+            Break(break_) => {
+                write!(f, "break")?;
+                debug_label(&break_.node.label, f, table)?;
+                match &break_.node.value {
+                    Some(value) => {
+                        write!(f, " ")?;
+                        value.debug(f, table)
+                    }
+                    None => Ok(()),
+                }
+            }
+            Continue(continue_) => {
+                write!(f, "continue")?;
+                debug_label(&continue_.node.label, f, table)
+            }
             Ref(id) => id.debug(f, table),
+            Path(path) => path.debug(f, table),
+            Match(match_) => {
+                write!(f, "match ")?;
+                match_.node.scrutinee.debug(f, table)?;
+                write!(f, " {{ ")?;
+                for arm in &match_.node.arms {
+                    arm.pattern.debug(f, table)?;
+                    write!(f, " => ")?;
+                    arm.body.debug(f, table)?;
+                    write!(f, ", ")?;
+                }
+                write!(f, "}}")
+            }
+
+            // The parentheses show how the operands were grouped.
             Binary(op, box left, box right) => {
+                write!(f, "(")?;
                 left.debug(f, table)?;
                 write!(f, " {} ", op.node)?;
-                right.debug(f, table)
+                right.debug(f, table)?;
+                write!(f, ")")
+            }
+            Unary(op, box value) => {
+                write!(f, "({}", op.node)?;
+                value.debug(f, table)?;
+                write!(f, ")")
             }
-            Interpolation(elements, span) => write!(f, "<interpolation>"),
+            Interpolation(elements, span) => {
+                write!(f, "interpolation[")?;
+                for element in elements {
+                    match element {
+                        InterpolationElement::String(text) => text.debug(f, table)?,
+                        InterpolationElement::Expression(expression) => {
+                            write!(f, "{{")?;
+                            expression.debug(f, table)?;
+                            write!(f, "}}")?
+                        }
+                    }
+                    write!(f, ", ")?;
+                }
+                write!(f, "]")
+            }
+            Error(span) => write!(f, "<error>"),
             Literal(literal) => literal.debug(f, table),
         }
     }
@@ -251,17 +403,108 @@ impl DebugModuleTable for Expression {
 impl DebugModuleTable for Literal {
     fn debug(&self, f: &mut fmt::Formatter<'_>, table: &'table ModuleTable) -> fmt::Result {
         match self {
-            Literal::String(id) => id.debug(f, table),
+            Literal::String(id) | Literal::Integer(id) | Literal::Float(id) => id.debug(f, table),
+            Literal::Bool(value) => write!(f, "{}", value.node),
         }
     }
 }
 
 impl DebugModuleTable for Call {
     fn debug(&self, f: &mut fmt::Formatter<'_>, table: &'table ModuleTable) -> fmt::Result {
-        match self.callee {
+        match &self.callee {
             Callee::Identifier(id) => id.debug(f, table)?,
+            Callee::Path(path) => path.debug(f, table)?,
+            Callee::Method(owner, name) => {
+                owner.debug(f, table)?;
+                write!(f, ".")?;
+                name.debug(f, table)?
+            }
+        };
+
+        let arguments: Vec<_> = self
+            .arguments
+            .iter()
+            .map(|argument| format!("{:?}", Debuggable::from(argument, table)))
+            .collect();
+        write!(f, "({})", arguments.join(", "))
+    }
+}
+
+impl DebugModuleTable for If {
+    fn debug(&self, f: &mut fmt::Formatter<'_>, table: &'table ModuleTable) -> fmt::Result {
+        let els = match self {
+            If::If(condition, block, els) => {
+                write!(f, "if ")?;
+                condition.debug(f, table)?;
+                write!(f, " ")?;
+                block.debug(f, table)?;
+                els
+            }
+            If::IfLet(pattern, value, block, els) => {
+                write!(f, "if let ")?;
+                pattern.debug(f, table)?;
+                write!(f, " = ")?;
+                value.debug(f, table)?;
+                write!(f, " ")?;
+                block.debug(f, table)?;
+                els
+            }
         };
 
-        write!(f, "( ... )")
+        match els {
+            Some(ChainedElse::Block(block)) => {
+                write!(f, " else ")?;
+                block.debug(f, table)
+            }
+            Some(ChainedElse::If(if_)) => {
+                write!(f, " else ")?;
+                if_.debug(f, table)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl DebugModuleTable for Loop {
+    fn debug(&self, f: &mut fmt::Formatter<'_>, table: &'table ModuleTable) -> fmt::Result {
+        if let Some(label) = &self.label {
+            label.debug(f, table)?;
+            write!(f, ": ")?;
+        }
+
+        match &self.kind {
+            LoopKind::Loop => write!(f, "loop ")?,
+            LoopKind::While(condition) => {
+                write!(f, "while ")?;
+                condition.debug(f, table)?;
+                write!(f, " ")?
+            }
+            LoopKind::For(pattern, start, end) => {
+                write!(f, "for ")?;
+                pattern.debug(f, table)?;
+                write!(f, " in ")?;
+                start.debug(f, table)?;
+                write!(f, "..")?;
+                end.debug(f, table)?;
+                write!(f, " ")?
+            }
+        }
+
+        self.body.debug(f, table)
+    }
+}
+
+/// Writes the label of a `break` or `continue`, if it has one.
+fn debug_label(
+    label: &Option<Identifier>,
+    f: &mut fmt::Formatter<'_>,
+    table: &'table ModuleTable,
+) -> fmt::Result {
+    match label {
+        Some(label) => {
+            write!(f, " ")?;
+            label.debug(f, table)
+        }
+        None => Ok(()),
     }
 }
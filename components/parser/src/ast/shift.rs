@@ -0,0 +1,392 @@
+//! Moving the spans of an item that was parsed on its own, as if it
+//! began at the start of a file, to where it is in the file.
+
+use super::*;
+
+impl Item {
+    /// The item with each of its spans `by` bytes later in the file.
+    pub fn shifted(&self, by: u32) -> Item {
+        let mut item = self.clone();
+        if by != 0 {
+            item.shift(by);
+        }
+        item
+    }
+}
+
+trait Shift {
+    fn shift(&mut self, by: u32);
+}
+
+impl Shift for Span {
+    fn shift(&mut self, by: u32) {
+        *self = self.shifted(by);
+    }
+}
+
+impl<T: Shift> Shift for Spanned<T> {
+    fn shift(&mut self, by: u32) {
+        self.node.shift(by);
+        self.span.shift(by);
+    }
+}
+
+impl<T: Shift> Shift for Vec<T> {
+    fn shift(&mut self, by: u32) {
+        for element in self {
+            element.shift(by);
+        }
+    }
+}
+
+impl<T: Shift> Shift for Option<T> {
+    fn shift(&mut self, by: u32) {
+        if let Some(value) = self {
+            value.shift(by);
+        }
+    }
+}
+
+impl<T: Shift> Shift for Box<T> {
+    fn shift(&mut self, by: u32) {
+        (**self).shift(by);
+    }
+}
+
+impl<T: Shift + Clone> Shift for Arc<T> {
+    fn shift(&mut self, by: u32) {
+        Arc::make_mut(self).shift(by);
+    }
+}
+
+/// The nodes that have no spans of their own.
+macro_rules! no_spans {
+    ($($ty:ty),*) => {
+        $(
+            impl Shift for $ty {
+                fn shift(&mut self, _by: u32) {}
+            }
+        )*
+    };
+}
+
+no_spans!(StringId, bool, Mode, Op, UnaryOp);
+
+impl Shift for Item {
+    fn shift(&mut self, by: u32) {
+        match self {
+            Item::Struct(s) => s.shift(by),
+            Item::Enum(e) => e.shift(by),
+            Item::Def(d) => d.shift(by),
+            Item::Mod(m) => m.shift(by),
+            Item::Use(u) => u.shift(by),
+        }
+    }
+}
+
+impl Shift for Visibility {
+    fn shift(&mut self, by: u32) {
+        match self {
+            Visibility::Private => {}
+            Visibility::Public(span) => span.shift(by),
+        }
+    }
+}
+
+impl Shift for Mod {
+    fn shift(&mut self, by: u32) {
+        self.docs.shift(by);
+        self.visibility.shift(by);
+        self.name.shift(by);
+        self.span.shift(by);
+    }
+}
+
+impl Shift for Use {
+    fn shift(&mut self, by: u32) {
+        self.docs.shift(by);
+        self.visibility.shift(by);
+        self.path.shift(by);
+        self.alias.shift(by);
+        self.span.shift(by);
+    }
+}
+
+impl Shift for Struct {
+    fn shift(&mut self, by: u32) {
+        self.docs.shift(by);
+        self.visibility.shift(by);
+        self.name.shift(by);
+        self.generics.shift(by);
+        self.fields.shift(by);
+        self.methods.shift(by);
+        self.span.shift(by);
+    }
+}
+
+impl Shift for Enum {
+    fn shift(&mut self, by: u32) {
+        self.docs.shift(by);
+        self.visibility.shift(by);
+        self.name.shift(by);
+        self.generics.shift(by);
+        self.variants.shift(by);
+        self.span.shift(by);
+    }
+}
+
+impl Shift for Variant {
+    fn shift(&mut self, by: u32) {
+        self.docs.shift(by);
+        self.name.shift(by);
+        match &mut self.fields {
+            VariantFields::Unit => {}
+            VariantFields::Tuple(types) => types.shift(by),
+            VariantFields::Struct(fields) => fields.shift(by),
+        }
+        self.span.shift(by);
+    }
+}
+
+impl Shift for Field {
+    fn shift(&mut self, by: u32) {
+        self.docs.shift(by);
+        self.name.shift(by);
+        self.ty.shift(by);
+        self.span.shift(by);
+    }
+}
+
+impl Shift for Type {
+    fn shift(&mut self, by: u32) {
+        self.mode.shift(by);
+        self.name.shift(by);
+        self.generics.shift(by);
+    }
+}
+
+impl Shift for Def {
+    fn shift(&mut self, by: u32) {
+        self.docs.shift(by);
+        self.visibility.shift(by);
+        self.name.shift(by);
+        self.generics.shift(by);
+        self.receiver.shift(by);
+        self.parameters.shift(by);
+        self.ret.shift(by);
+        self.body.shift(by);
+        self.span.shift(by);
+    }
+}
+
+impl Shift for BlockItem {
+    fn shift(&mut self, by: u32) {
+        match self {
+            BlockItem::Item(item) => item.shift(by),
+            BlockItem::Decl(Declaration::Let(decl)) => decl.shift(by),
+            BlockItem::Expr(expr) => expr.shift(by),
+        }
+    }
+}
+
+impl Shift for Let {
+    fn shift(&mut self, by: u32) {
+        self.pattern.shift(by);
+        self.ty.shift(by);
+        self.init.shift(by);
+    }
+}
+
+impl Shift for Pattern {
+    fn shift(&mut self, by: u32) {
+        match self {
+            Pattern::Underscore => {}
+            Pattern::Identifier(name, mode) => {
+                name.shift(by);
+                mode.shift(by);
+            }
+            Pattern::Literal(literal) => literal.shift(by),
+            Pattern::Path(path) => path.shift(by),
+            Pattern::Tuple(path, patterns) => {
+                path.shift(by);
+                patterns.shift(by);
+            }
+            Pattern::Struct(path, fields) => {
+                path.shift(by);
+                fields.shift(by);
+            }
+        }
+    }
+}
+
+impl Shift for FieldPattern {
+    fn shift(&mut self, by: u32) {
+        self.name.shift(by);
+        self.pattern.shift(by);
+    }
+}
+
+impl Shift for Path {
+    fn shift(&mut self, by: u32) {
+        self.components.shift(by);
+    }
+}
+
+impl Shift for Literal {
+    fn shift(&mut self, by: u32) {
+        match self {
+            Literal::String(string) => string.shift(by),
+            Literal::Integer(integer) => integer.shift(by),
+            Literal::Float(float) => float.shift(by),
+            Literal::Bool(boolean) => boolean.shift(by),
+        }
+    }
+}
+
+impl Shift for Expression {
+    fn shift(&mut self, by: u32) {
+        match self {
+            Expression::Block(block) => block.shift(by),
+            Expression::ConstructStruct(construct) => construct.shift(by),
+            Expression::Call(call) => call.shift(by),
+            Expression::Field(field) => field.shift(by),
+            Expression::SelfRef(span) => span.shift(by),
+            Expression::If(if_) => if_.shift(by),
+            Expression::Loop(loop_) => loop_.shift(by),
+            Expression::Break(break_) => break_.shift(by),
+            Expression::Continue(continue_) => continue_.shift(by),
+            Expression::Ref(name) => name.shift(by),
+            Expression::Path(path) => path.shift(by),
+            Expression::Match(match_) => match_.shift(by),
+            Expression::Binary(op, left, right) => {
+                op.shift(by);
+                left.shift(by);
+                right.shift(by);
+            }
+            Expression::Unary(op, value) => {
+                op.shift(by);
+                value.shift(by);
+            }
+            Expression::Interpolation(elements, span) => {
+                elements.shift(by);
+                span.shift(by);
+            }
+            Expression::Literal(literal) => literal.shift(by),
+            Expression::Error(span) => span.shift(by),
+        }
+    }
+}
+
+impl Shift for InterpolationElement {
+    fn shift(&mut self, by: u32) {
+        match self {
+            InterpolationElement::String(string) => string.shift(by),
+            InterpolationElement::Expression(expr) => expr.shift(by),
+        }
+    }
+}
+
+impl Shift for ConstructStruct {
+    fn shift(&mut self, by: u32) {
+        self.path.shift(by);
+        self.fields.shift(by);
+        self.span.shift(by);
+    }
+}
+
+impl Shift for ConstructField {
+    fn shift(&mut self, by: u32) {
+        match self {
+            ConstructField::Longhand(field) => field.shift(by),
+            ConstructField::Shorthand(name) => name.shift(by),
+        }
+    }
+}
+
+impl Shift for Call {
+    fn shift(&mut self, by: u32) {
+        match &mut self.callee {
+            Callee::Identifier(name) => name.shift(by),
+            Callee::Path(path) => path.shift(by),
+            Callee::Method(owner, name) => {
+                owner.shift(by);
+                name.shift(by);
+            }
+        }
+        self.arguments.shift(by);
+    }
+}
+
+impl Shift for FieldAccess {
+    fn shift(&mut self, by: u32) {
+        self.owner.shift(by);
+        self.name.shift(by);
+    }
+}
+
+impl Shift for If {
+    fn shift(&mut self, by: u32) {
+        let (block, else_) = match self {
+            If::If(condition, block, else_) => {
+                condition.shift(by);
+                (block, else_)
+            }
+            If::IfLet(pattern, value, block, else_) => {
+                pattern.shift(by);
+                value.shift(by);
+                (block, else_)
+            }
+        };
+        block.shift(by);
+        match else_ {
+            None => {}
+            Some(ChainedElse::Block(block)) => block.shift(by),
+            Some(ChainedElse::If(if_)) => if_.shift(by),
+        }
+    }
+}
+
+impl Shift for Match {
+    fn shift(&mut self, by: u32) {
+        self.scrutinee.shift(by);
+        for arm in &mut self.arms {
+            arm.pattern.shift(by);
+            arm.body.shift(by);
+        }
+    }
+}
+
+impl Shift for Loop {
+    fn shift(&mut self, by: u32) {
+        self.label.shift(by);
+        match &mut self.kind {
+            LoopKind::Loop => {}
+            LoopKind::While(condition) => condition.shift(by),
+            LoopKind::For(pattern, start, end) => {
+                pattern.shift(by);
+                start.shift(by);
+                end.shift(by);
+            }
+        }
+        self.body.shift(by);
+    }
+}
+
+impl Shift for Break {
+    fn shift(&mut self, by: u32) {
+        self.label.shift(by);
+        self.value.shift(by);
+    }
+}
+
+impl Shift for Continue {
+    fn shift(&mut self, by: u32) {
+        self.label.shift(by);
+    }
+}
+
+impl Shift for Block {
+    fn shift(&mut self, by: u32) {
+        self.expressions.shift(by);
+    }
+}
@@ -0,0 +1,705 @@
+//! The formatter behind `lark fmt`. It prints the syntax tree of a file
+//! back out in a canonical layout: items, fields, variants, statements
+//! and match arms one to a line, indented two spaces per level of
+//! braces; argument, parameter and struct lists on one line if they fit
+//! within `MAX_WIDTH` and one element to a line, each with a trailing
+//! comma, if they do not. Comments are kept where they were, and blank
+//! lines between items or statements are kept, but never more than one
+//! in a row.
+//!
+//! The layout is worked out in two steps, as in Wadler's "prettier
+//! printer": the tree is first turned into a `Doc` that says where lines
+//! may be broken, and `print` then decides which breaks to take.
+
+use crate::lexer_helpers::ParseError;
+use crate::syntax::{
+    parse_syntax, AstNode, SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken, Trivia,
+    TriviaKind,
+};
+use crate::{ModuleTable, Token};
+use codespan::ByteIndex;
+use std::ops::Range;
+
+/// How much deeper each level of braces or of a broken list is
+/// indented.
+const INDENT: usize = 2;
+
+/// The width that lines are kept within where they can be broken.
+const MAX_WIDTH: usize = 80;
+
+/// Formats the file `source`. A file with syntax errors is not
+/// formatted, since its tree does not say what was meant; the errors
+/// are returned instead.
+pub fn format(source: &str) -> Result<String, Vec<ParseError>> {
+    let root = parse_checked(source)?;
+    let mut text = print(&node(&root));
+    if !text.is_empty() {
+        text.push('\n');
+    }
+    Ok(text)
+}
+
+/// Formats the items of the file `source` that overlap `range`, leaving
+/// the rest of the file alone. Each edit is the range of an item in
+/// `source`, from the start of its first line to the end of its last,
+/// and the text to replace it with; items that are already formatted
+/// are left out.
+pub fn format_range(
+    source: &str,
+    range: Range<usize>,
+) -> Result<Vec<(Range<usize>, String)>, Vec<ParseError>> {
+    let root = parse_checked(source)?;
+    Ok(root
+        .child_nodes()
+        .into_iter()
+        .map(|item| (item.full_range(), item))
+        .filter(|(item_range, _)| item_range.start <= range.end && range.start <= item_range.end)
+        .map(|(item_range, item)| (item_range, print(&node(&item))))
+        .filter(|(item_range, text)| source[item_range.clone()] != *text)
+        .collect())
+}
+
+/// The syntax tree of `source`, or its syntax errors.
+fn parse_checked(source: &str) -> Result<SyntaxNode, Vec<ParseError>> {
+    let mut table = ModuleTable::new();
+    let (_, errors) = crate::parse(source, &mut table, 0);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let root = parse_syntax(source, &mut table).syntax().clone();
+    let errors = error_nodes(&root);
+    if !errors.is_empty() {
+        return Err(errors
+            .iter()
+            .map(|error| {
+                let range = error.range();
+                ParseError::from(
+                    "Unexpected syntax",
+                    ByteIndex(range.start as u32),
+                    ByteIndex(range.end as u32),
+                )
+            })
+            .collect());
+    }
+
+    Ok(root)
+}
+
+fn error_nodes(node: &SyntaxNode) -> Vec<SyntaxNode> {
+    if node.kind() == SyntaxKind::Error {
+        return vec![node.clone()];
+    }
+    node.child_nodes()
+        .iter()
+        .flat_map(|child| error_nodes(child))
+        .collect()
+}
+
+/// A layout that has yet to be fitted to the page.
+#[derive(Clone, Debug)]
+enum Doc {
+    Text(String),
+
+    /// A space, or a line break if the enclosing group is broken.
+    Line,
+
+    /// Nothing, or a line break if the enclosing group is broken.
+    SoftLine,
+
+    HardLine,
+
+    /// Text that is only printed if the enclosing group is broken, like
+    /// the trailing comma of a list.
+    IfBreak(&'static str),
+
+    /// Text that is put off until the end of the line, like a `//`
+    /// comment after an element that still needs its comma.
+    LineSuffix(String),
+
+    /// Breaks the groups around it.
+    BreakParent,
+
+    /// Indents the lines that start within it.
+    Indent(Vec<Doc>),
+
+    /// Docs that are printed on one line if they fit, and with each of
+    /// their `Line`s broken if they do not.
+    Group(Vec<Doc>),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+/// Prints `docs`, breaking each group that does not fit on the rest of
+/// its line.
+fn print(docs: &[Doc]) -> String {
+    let mut out = String::new();
+    let mut suffixes: Vec<&str> = vec![];
+    let mut column = 0;
+
+    let mut stack: Vec<(usize, Mode, &Doc)> =
+        docs.iter().rev().map(|doc| (0, Mode::Break, doc)).collect();
+    while let Some((indent, mode, doc)) = stack.pop() {
+        match doc {
+            Doc::Text(text) => {
+                out.push_str(text);
+                column = match text.rfind('\n') {
+                    Some(newline) => text[newline + 1..].chars().count(),
+                    None => column + text.chars().count(),
+                };
+            }
+            Doc::Line if mode == Mode::Flat => {
+                out.push(' ');
+                column += 1;
+            }
+            Doc::SoftLine if mode == Mode::Flat => {}
+            Doc::Line | Doc::SoftLine | Doc::HardLine => {
+                end_line(&mut out, &mut suffixes);
+                out.push('\n');
+                out.extend(std::iter::repeat(' ').take(indent));
+                column = indent;
+            }
+            Doc::IfBreak(text) => {
+                if mode == Mode::Break {
+                    out.push_str(text);
+                    column += text.len();
+                }
+            }
+            Doc::LineSuffix(text) => suffixes.push(text),
+            Doc::BreakParent => {}
+            Doc::Indent(docs) => {
+                stack.extend(docs.iter().rev().map(|doc| (indent + INDENT, mode, doc)));
+            }
+            Doc::Group(docs) => {
+                let width = MAX_WIDTH as isize - column as isize;
+                let mode = if mode == Mode::Flat || fits(docs, &stack, width) {
+                    Mode::Flat
+                } else {
+                    Mode::Break
+                };
+                stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc)));
+            }
+        }
+    }
+
+    end_line(&mut out, &mut suffixes);
+    out
+}
+
+/// Finishes the line at the end of `out` with the `suffixes` put off
+/// until then, and drops any spaces it ends with.
+fn end_line(out: &mut String, suffixes: &mut Vec<&str>) {
+    for suffix in suffixes.drain(..) {
+        out.push_str(suffix);
+    }
+    let len = out.trim_end_matches(' ').len();
+    out.truncate(len);
+}
+
+/// True if `docs`, printed flat, fit within `width` columns together
+/// with whatever `rest` puts on the same line after them.
+fn fits(docs: &[Doc], rest: &[(usize, Mode, &Doc)], mut width: isize) -> bool {
+    let mut stack: Vec<(Mode, &Doc)> = docs.iter().rev().map(|doc| (Mode::Flat, doc)).collect();
+    let mut rest = rest.iter().rev();
+    loop {
+        let (mode, doc) = match stack.pop() {
+            Some(next) => next,
+            None => match rest.next() {
+                Some(&(_, mode, doc)) => (mode, doc),
+                None => return true,
+            },
+        };
+        match doc {
+            Doc::Text(text) => {
+                if text.contains('\n') {
+                    return mode == Mode::Break;
+                }
+                width -= text.chars().count() as isize;
+            }
+            Doc::Line | Doc::SoftLine | Doc::HardLine if mode == Mode::Break => return true,
+            Doc::Line => width -= 1,
+            Doc::SoftLine => {}
+            Doc::HardLine | Doc::BreakParent if mode == Mode::Flat => return false,
+            Doc::HardLine | Doc::BreakParent | Doc::LineSuffix(_) => {}
+            Doc::IfBreak(text) => {
+                if mode == Mode::Break {
+                    width -= text.len() as isize;
+                }
+            }
+            Doc::Indent(docs) | Doc::Group(docs) => {
+                stack.extend(docs.iter().rev().map(|doc| (mode, doc)));
+            }
+        }
+        if width < 0 {
+            return false;
+        }
+    }
+}
+
+/// How the elements between a pair of delimiters are laid out.
+enum Layout {
+    /// One to a line, like the statements of a block.
+    Lines,
+
+    /// On one line if they fit, and one to a line if not, like the
+    /// arguments of a call. The doc goes between the delimiters and
+    /// the elements.
+    Group(Doc),
+
+    /// Always on one line, like the type arguments of a type, which
+    /// cannot be broken. The text goes between the delimiters and the
+    /// elements.
+    Inline(&'static str),
+}
+
+fn node(node: &SyntaxNode) -> Vec<Doc> {
+    let children = node.children();
+    match node.kind() {
+        SyntaxKind::SourceFile => lines(&entries(&children)),
+        SyntaxKind::Struct | SyntaxKind::Enum | SyntaxKind::Block | SyntaxKind::MatchExpr => {
+            delimited(
+                &children,
+                Token::CurlyBraceOpen,
+                Token::CurlyBraceClose,
+                Layout::Lines,
+            )
+        }
+        SyntaxKind::ParamList | SyntaxKind::ArgList => delimited(
+            &children,
+            Token::ParenOpen,
+            Token::ParenClose,
+            Layout::Group(Doc::SoftLine),
+        ),
+        SyntaxKind::StructExpr | SyntaxKind::StructFields => delimited(
+            &children,
+            Token::CurlyBraceOpen,
+            Token::CurlyBraceClose,
+            Layout::Group(Doc::Line),
+        ),
+        SyntaxKind::StructPattern => delimited(
+            &children,
+            Token::CurlyBraceOpen,
+            Token::CurlyBraceClose,
+            Layout::Inline(" "),
+        ),
+        SyntaxKind::TupleFields | SyntaxKind::TuplePattern => delimited(
+            &children,
+            Token::ParenOpen,
+            Token::ParenClose,
+            Layout::Inline(""),
+        ),
+        SyntaxKind::GenericParams | SyntaxKind::TypeArgs => delimited(
+            &children,
+            Token::OpLess,
+            Token::OpGreater,
+            Layout::Inline(""),
+        ),
+        _ => inline(&children),
+    }
+}
+
+fn element(element: &SyntaxElement) -> Vec<Doc> {
+    match element {
+        SyntaxElement::Node(n) => node(n),
+        SyntaxElement::Token(t) => token(t),
+    }
+}
+
+/// A token, with the comments around it. A `//` comment after it is put
+/// off until the end of the line, and breaks the groups it is in so
+/// that it does not swallow what follows.
+fn token(token: &SyntaxToken) -> Vec<Doc> {
+    let mut docs = vec![];
+    for trivia in comments(token.leading()) {
+        docs.push(Doc::Text(comment_text(trivia)));
+        match trivia.kind {
+            TriviaKind::LineComment => docs.push(Doc::HardLine),
+            _ => docs.push(Doc::Text(" ".to_string())),
+        }
+    }
+    docs.push(Doc::Text(token.text().to_string()));
+    docs.extend(trailing_comments(token));
+    docs
+}
+
+/// The comments after `token` on its line.
+fn trailing_comments(token: &SyntaxToken) -> Vec<Doc> {
+    let mut docs = vec![];
+    for trivia in comments(token.trailing()) {
+        let text = format!(" {}", comment_text(trivia));
+        match trivia.kind {
+            TriviaKind::LineComment => {
+                docs.push(Doc::LineSuffix(text));
+                docs.push(Doc::BreakParent);
+            }
+            _ => docs.push(Doc::Text(text)),
+        }
+    }
+    docs
+}
+
+fn comments(trivia: &[Trivia]) -> impl Iterator<Item = &Trivia> {
+    trivia.iter().filter(|trivia| match trivia.kind {
+        TriviaKind::LineComment | TriviaKind::BlockComment => true,
+        TriviaKind::Whitespace | TriviaKind::Unknown => false,
+    })
+}
+
+fn comment_text(trivia: &Trivia) -> String {
+    trivia.text.trim_end().to_string()
+}
+
+/// `elements` on one line, with a space between two tokens wherever
+/// `space_between` says so. Doc comments, and comments on lines of
+/// their own, are each put on a line of their own.
+fn inline(elements: &[SyntaxElement]) -> Vec<Doc> {
+    let mut docs = vec![];
+    let mut previous: Option<SyntaxToken> = None;
+    for child in elements {
+        match child {
+            SyntaxElement::Token(t) if t.token() == Token::Newline => {
+                for trivia in comments(t.leading()) {
+                    docs.push(Doc::Text(comment_text(trivia)));
+                    docs.push(Doc::HardLine);
+                    previous = None;
+                }
+            }
+            SyntaxElement::Token(t) if is_doc_comment(t.token()) => {
+                docs.push(Doc::Text(t.text().trim_end().to_string()));
+                docs.push(Doc::HardLine);
+                previous = None;
+            }
+            _ => {
+                let tokens = match child {
+                    SyntaxElement::Node(n) => n.tokens(),
+                    SyntaxElement::Token(t) => vec![t.clone()],
+                };
+                if let (Some(before), Some(first)) = (&previous, tokens.first()) {
+                    if space_between(before, first) {
+                        docs.push(Doc::Text(" ".to_string()));
+                    }
+                }
+                docs.extend(element(child));
+                if let Some(last) = tokens.last() {
+                    previous = Some(last.clone());
+                }
+            }
+        }
+    }
+    docs
+}
+
+/// Whether a space goes between the adjacent tokens `before` and
+/// `after`.
+fn space_between(before: &SyntaxToken, after: &SyntaxToken) -> bool {
+    let in_angles = |token: &SyntaxToken| match token.parent().kind() {
+        SyntaxKind::GenericParams | SyntaxKind::TypeArgs => true,
+        _ => false,
+    };
+
+    match (before.token(), after.token()) {
+        // The expressions in a string hug the text around them.
+        (Token::StringFragment(_), _) | (_, Token::EndString(_)) => false,
+        (_, Token::StringFragment(_)) => after.parent().range().start == after.range().start,
+        (_, Token::Comma)
+        | (_, Token::Colon)
+        | (_, Token::Period)
+        | (_, Token::DotDot)
+        | (_, Token::DoubleColon)
+        | (_, Token::ParenClose)
+        | (Token::ParenOpen, _)
+        | (Token::Period, _)
+        | (Token::DotDot, _)
+        | (Token::DoubleColon, _) => false,
+        (Token::OpLess, _) if in_angles(before) => false,
+        (_, Token::OpLess) | (_, Token::OpGreater) if in_angles(after) => false,
+        (Token::OpSub, _) | (Token::OpNot, _) => before.parent().kind() != SyntaxKind::UnaryExpr,
+        (_, Token::ParenOpen) => match after.parent().kind() {
+            SyntaxKind::ParamList
+            | SyntaxKind::ArgList
+            | SyntaxKind::TupleFields
+            | SyntaxKind::TuplePattern => false,
+            _ => true,
+        },
+        _ => true,
+    }
+}
+
+/// The prefix of `children`, like the `struct Point` of a struct, then
+/// the elements between `open` and `close`, laid out as `layout` says.
+fn delimited(children: &[SyntaxElement], open: Token, close: Token, layout: Layout) -> Vec<Doc> {
+    let start = match children.iter().position(|child| is_token(child, open)) {
+        Some(start) => start,
+        None => return inline(children),
+    };
+    let end = match children.last() {
+        Some(last) if is_token(last, close) && children.len() - 1 > start => children.len() - 1,
+        _ => children.len(),
+    };
+
+    let mut docs = inline(&children[..start]);
+    let mut open = element(&children[start]);
+    if let (Some(before), SyntaxElement::Token(after)) =
+        (last_token(&children[..start]), &children[start])
+    {
+        if space_between(&before, after) {
+            open.insert(0, Doc::Text(" ".to_string()));
+        }
+    }
+    let entries = entries(&children[start + 1..end]);
+    let close = children[end..].iter().flat_map(element).collect::<Vec<_>>();
+
+    if entries.is_empty() {
+        // A comment after the opening delimiter, as in `{ /* nothing */ }`,
+        // gets a space on either side.
+        let comment = match open.last() {
+            Some(Doc::Text(text)) => text.ends_with("*/"),
+            _ => false,
+        };
+        docs.extend(open);
+        if comment {
+            docs.push(Doc::Text(" ".to_string()));
+        }
+        docs.extend(close);
+        return docs;
+    }
+
+    match layout {
+        Layout::Lines => {
+            docs.extend(open);
+            let mut body = vec![Doc::HardLine];
+            body.extend(lines(&entries));
+            docs.push(Doc::Indent(body));
+            docs.push(Doc::HardLine);
+            docs.extend(close);
+        }
+        Layout::Group(padding) => {
+            let count = entries.iter().filter(|entry| entry.is_element()).count();
+            let mut body = vec![padding.clone()];
+            let mut seen = 0;
+            for (index, entry) in entries.iter().enumerate() {
+                if index > 0 {
+                    body.push(Doc::Line);
+                }
+                match &entry.kind {
+                    EntryKind::Comment(text) => {
+                        body.push(Doc::Text(text.clone()));
+                        body.push(Doc::BreakParent);
+                    }
+                    EntryKind::Element(child, after) => {
+                        seen += 1;
+                        body.extend(element(child));
+                        if seen < count {
+                            body.push(Doc::Text(",".to_string()));
+                        } else {
+                            body.push(Doc::IfBreak(","));
+                        }
+                        body.extend(after.iter().cloned());
+                    }
+                }
+            }
+
+            let mut group = open;
+            group.push(Doc::Indent(body));
+            group.push(padding);
+            group.extend(close);
+            docs.push(Doc::Group(group));
+        }
+        Layout::Inline(padding) => {
+            docs.extend(open);
+            docs.push(Doc::Text(padding.to_string()));
+            let mut first = true;
+            for entry in &entries {
+                match &entry.kind {
+                    EntryKind::Comment(text) => {
+                        docs.push(Doc::LineSuffix(format!(" {}", text)));
+                        docs.push(Doc::BreakParent);
+                    }
+                    EntryKind::Element(child, after) => {
+                        if !first {
+                            docs.push(Doc::Text(", ".to_string()));
+                        }
+                        first = false;
+                        docs.extend(element(child));
+                        docs.extend(after.iter().cloned());
+                    }
+                }
+            }
+            docs.push(Doc::Text(padding.to_string()));
+            docs.extend(close);
+        }
+    }
+    docs
+}
+
+/// The last token of `elements`.
+fn last_token(elements: &[SyntaxElement]) -> Option<SyntaxToken> {
+    elements
+        .iter()
+        .rev()
+        .filter_map(|child| match child {
+            SyntaxElement::Node(n) => n.tokens().pop(),
+            SyntaxElement::Token(t) => Some(t.clone()),
+        })
+        .next()
+}
+
+fn is_token(element: &SyntaxElement, token: Token) -> bool {
+    match element {
+        SyntaxElement::Token(t) => t.token() == token,
+        SyntaxElement::Node(_) => false,
+    }
+}
+
+fn is_doc_comment(token: Token) -> bool {
+    match token {
+        Token::DocComment(_) => true,
+        _ => false,
+    }
+}
+
+/// Something that goes on a line of its own in a list.
+struct Entry {
+    kind: EntryKind,
+
+    /// Whether there was a blank line before it.
+    blank_before: bool,
+}
+
+enum EntryKind {
+    /// An element of the list, with the comments after the comma that
+    /// followed it.
+    Element(SyntaxElement, Vec<Doc>),
+
+    /// The comments on a line of their own.
+    Comment(String),
+}
+
+impl Entry {
+    fn is_element(&self) -> bool {
+        match self.kind {
+            EntryKind::Element(..) => true,
+            EntryKind::Comment(_) => false,
+        }
+    }
+
+    /// Whether the entry is an item with a body, which gets a blank
+    /// line before and after it.
+    fn is_block_item(&self) -> bool {
+        match &self.kind {
+            EntryKind::Element(SyntaxElement::Node(n), _) => match n.kind() {
+                SyntaxKind::Struct | SyntaxKind::Enum | SyntaxKind::Def => true,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+/// Splits the elements of a list into entries, dropping the commas and
+/// newlines between them.
+fn entries(elements: &[SyntaxElement]) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = vec![];
+    let mut blank = false;
+    let mut line_start = false;
+    for child in elements {
+        match child {
+            SyntaxElement::Token(t)
+                if t.token() == Token::Newline || t.token() == Token::EndOfFile =>
+            {
+                let comments: Vec<_> = comments(t.leading()).map(comment_text).collect();
+                if !comments.is_empty() {
+                    entries.push(Entry {
+                        kind: EntryKind::Comment(comments.join(" ")),
+                        blank_before: blank,
+                    });
+                    blank = false;
+                } else if line_start && t.token() == Token::Newline {
+                    blank = true;
+                }
+                line_start = true;
+            }
+            SyntaxElement::Token(t) if t.token() == Token::Comma => {
+                let mut docs: Vec<_> = comments(t.leading())
+                    .map(|trivia| Doc::Text(format!(" {}", comment_text(trivia))))
+                    .collect();
+                docs.extend(trailing_comments(t));
+                match entries.last_mut() {
+                    Some(Entry {
+                        kind: EntryKind::Element(_, after),
+                        ..
+                    }) => after.extend(docs),
+                    _ => {
+                        let text: Vec<_> = comments(t.leading())
+                            .chain(comments(t.trailing()))
+                            .map(comment_text)
+                            .collect();
+                        if !text.is_empty() {
+                            entries.push(Entry {
+                                kind: EntryKind::Comment(text.join(" ")),
+                                blank_before: blank,
+                            });
+                        }
+                    }
+                }
+                line_start = false;
+            }
+            _ => {
+                entries.push(Entry {
+                    kind: EntryKind::Element(child.clone(), vec![]),
+                    blank_before: blank,
+                });
+                blank = false;
+                line_start = false;
+            }
+        }
+    }
+    entries
+}
+
+/// `entries` one to a line. Fields, variants and match arms each get a
+/// comma, and a blank line goes around each item with a body.
+fn lines(entries: &[Entry]) -> Vec<Doc> {
+    let mut docs = vec![];
+    for (index, entry) in entries.iter().enumerate() {
+        if index > 0 {
+            docs.push(Doc::HardLine);
+
+            let previous = &entries[index - 1];
+            let next_element = entries[index..].iter().find(|entry| entry.is_element());
+            let around_block_item = previous.is_element()
+                && next_element.map_or(false, |next| {
+                    previous.is_block_item() || next.is_block_item()
+                });
+            if entry.blank_before || around_block_item {
+                docs.push(Doc::HardLine);
+            }
+        }
+
+        match &entry.kind {
+            EntryKind::Comment(text) => docs.push(Doc::Text(text.clone())),
+            EntryKind::Element(child, after) => {
+                docs.extend(element(child));
+                if needs_comma(child) {
+                    docs.push(Doc::Text(",".to_string()));
+                }
+                docs.extend(after.iter().cloned());
+            }
+        }
+    }
+    docs
+}
+
+fn needs_comma(element: &SyntaxElement) -> bool {
+    match element {
+        SyntaxElement::Node(n) => match n.kind() {
+            SyntaxKind::Field | SyntaxKind::Variant | SyntaxKind::MatchArm => true,
+            _ => false,
+        },
+        SyntaxElement::Token(_) => false,
+    }
+}
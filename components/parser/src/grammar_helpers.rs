@@ -1,3 +1,56 @@
+use crate::ast::{Expression, Op};
+use crate::pos::{HasSpan, Spanned};
+use crate::ParseError;
+use std::iter::Peekable;
+
 pub enum BlockOrIf {}
 
 pub enum Atom {}
+
+/// Groups `first op1 e1 op2 e2 ...` by precedence climbing, so that
+/// `a + b * c` becomes `a + (b * c)` and `a - b - c` becomes
+/// `(a - b) - c`.
+crate fn climb(
+    first: Expression,
+    rest: Vec<(Spanned<Op>, Expression)>,
+) -> Result<Expression, ParseError> {
+    climb_from(first, 0, &mut rest.into_iter().peekable())
+}
+
+/// Combines `left` with the operators in `rest` whose precedence is at
+/// least `min_precedence`.
+fn climb_from(
+    mut left: Expression,
+    min_precedence: u8,
+    rest: &mut Peekable<impl Iterator<Item = (Spanned<Op>, Expression)>>,
+) -> Result<Expression, ParseError> {
+    let mut previous: Option<Op> = None;
+
+    while let Some(precedence) = next_precedence(rest).filter(|&p| p >= min_precedence) {
+        let (op, mut right) = rest.next().unwrap();
+
+        while let Some(next) = next_precedence(rest).filter(|&p| p > precedence) {
+            right = climb_from(right, next, rest)?;
+        }
+
+        if let Some(previous) = &previous {
+            if previous.is_comparison() && op.node.is_comparison() {
+                return Err(ParseError {
+                    description: "comparison operators cannot be chained".to_string(),
+                    span: left.span().to(right.span()),
+                });
+            }
+        }
+
+        previous = Some(op.node.clone());
+        left = Expression::binary(op, left, right);
+    }
+
+    Ok(left)
+}
+
+fn next_precedence(
+    rest: &mut Peekable<impl Iterator<Item = (Spanned<Op>, Expression)>>,
+) -> Option<u8> {
+    rest.peek().map(|(op, _)| op.node.precedence())
+}
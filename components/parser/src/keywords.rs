@@ -11,22 +11,59 @@ tokens! {
 
     declare(pub) KEYWORDS {
         "struct" => KeywordStruct,
+        "enum"   => KeywordEnum,
+        "mod"    => KeywordMod,
+        "use"    => KeywordUse,
+        "pub"    => KeywordPub,
+        "as"     => KeywordAs,
         "own"    => KeywordOwn,
+        "borrow" => KeywordBorrow,
+        "share"  => KeywordShare,
+        "self"   => KeywordSelf,
         "def"    => KeywordDef,
-        "let"    => KeywordLet
+        "let"    => KeywordLet,
+        "if"     => KeywordIf,
+        "match"  => KeywordMatch,
+        "else"   => KeywordElse,
+        "while"  => KeywordWhile,
+        "loop"   => KeywordLoop,
+        "for"    => KeywordFor,
+        "in"     => KeywordIn,
+        "break"  => KeywordBreak,
+        "continue" => KeywordContinue,
+        "true"   => KeywordTrue,
+        "false"  => KeywordFalse,
+        "_"      => Underscore
     }
 
     declare(pub) SIGILS {
         "{"  => CurlyBraceOpen,
         "}"  => CurlyBraceClose,
         "("  => ParenOpen,
+        "::" => DoubleColon,
         ":"  => Colon,
         ")"  => ParenClose,
         ":"  => Colon,
         ","  => Comma,
         "->" => ThinArrow,
+        "=>" => FatArrow,
+        "==" => OpEqual,
+        "!=" => OpNotEqual,
+        "<=" => OpLessEqual,
+        ">=" => OpGreaterEqual,
+        "&&" => OpAnd,
+        "||" => OpOr,
         "="  => Equals,
         "+"  => OpAdd,
+        "-"  => OpSub,
+        "*"  => OpMul,
+        "/"  => OpDiv,
+        "%"  => OpRem,
+        "<"  => OpLess,
+        ">"  => OpGreater,
+        "!"  => OpNot,
+        ".." => DotDot,
+        "."  => Period,
         "\n" => Newline
     }
 }
@@ -18,7 +18,13 @@ pub enum LexerNext<Delegate: LexerDelegateTrait> {
     EOF,
     Remain(LexerAccumulate<Delegate>),
     Transition(LexerAccumulate<Delegate>, Delegate),
+
+    /// Enter a new state, remembering the current one so that a later
+    /// `PopState` can return to it.
     PushState(LexerAccumulate<Delegate>, Delegate),
+
+    /// Return to the state that was current at the matching
+    /// `PushState`.
     PopState(LexerAccumulate<Delegate>),
     Error(Option<char>),
 }
@@ -457,7 +463,7 @@ impl<Delegate: LexerDelegateTrait + Debug> Tokenizer<'table, Delegate> {
 
             LexerNext::PushState(action, state) => {
                 let ret = self.accumulate(action);
-                self.stack.push(state.clone());
+                self.stack.push(self.state);
                 self.transition(state);
 
                 ret
@@ -471,10 +477,28 @@ impl<Delegate: LexerDelegateTrait + Debug> Tokenizer<'table, Delegate> {
                 ret
             }
 
-            LexerNext::Error(c) => LoopCompletion::Return(Some(Err(self.error(c)))),
+            LexerNext::Error(c) => {
+                let error = self.error(c);
+                self.recover(c);
+                LoopCompletion::Return(Some(Err(error)))
+            }
         }
     }
 
+    /// Skips the partial token and the character `c` that caused an
+    /// error, and starts over in the top state, so that tokenizing can
+    /// continue after the error is reported.
+    fn recover(&mut self, c: Option<char>) {
+        if let Some(c) = c {
+            self.action(LexerAction::Consume(c.len_utf8() as u32));
+        }
+
+        self.start_pos += self.token_len;
+        self.token_len = 0;
+        self.stack.clear();
+        self.transition(Delegate::top());
+    }
+
     fn accumulate(
         &mut self,
         accum: LexerAccumulate<Delegate>,
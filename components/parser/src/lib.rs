@@ -10,22 +10,27 @@
 pub mod lexer;
 
 pub mod ast;
+pub mod format;
 pub mod grammar;
 pub mod grammar_helpers;
 pub mod keywords;
 pub mod lexer_helpers;
 pub mod pos;
 pub mod program;
+pub mod syntax;
 pub mod token;
 pub mod tokenizer;
 
 #[cfg(test)]
 pub mod test_helpers;
 
+#[cfg(test)]
+mod tests;
+
 #[cfg(test)]
 use self::test_helpers::LineTokenizer;
 
-pub use self::grammar::ProgramParser;
+pub use self::grammar::ItemParser;
 pub use self::lexer_helpers::ParseError;
 pub use self::program::{Environment, Module, ModuleTable, NameId, StringId};
 pub use self::token::Token;
@@ -44,19 +49,174 @@ use derive_new::new;
 use std::borrow::{Borrow, Cow};
 use std::error::Error;
 use std::fmt;
+use std::ops::Range;
 
+/// Parses a whole file. A syntax error does not stop the parse: the
+/// item containing it is skipped, or, within a function body, just the
+/// statement, and parsing resumes after it. The module holds everything
+/// that parsed, and the errors are returned alongside it.
 pub fn parse(
     source: impl Into<Cow<'source, str>>,
     table: &'source mut ModuleTable,
     start: u32,
-) -> Result<ast::Module, ParseError> {
+) -> (ast::Module, Vec<ParseError>) {
     let cow = source.into();
-    let tokenizer = Tokenizer::new(table, cow.borrow(), start);
-    let parser = ProgramParser::new();
-    let module = parser
-        .parse(tokenizer)
-        .map_err(|err| lalrpop_err(err, table));
-    Ok(module?)
+    let (tokens, mut errors) = tokenize(cow.borrow(), table, start);
+    let (module, parse_errors) = parse_tokens(&tokens, table);
+    errors.extend(parse_errors);
+    (module, errors)
+}
+
+/// Splits `source` into tokens, interning their text in `table`, and
+/// returns them along with an error for each bit of text that is not a
+/// token.
+pub fn tokenize(
+    source: &str,
+    table: &mut ModuleTable,
+    start: u32,
+) -> (Vec<(ByteIndex, Token, ByteIndex)>, Vec<ParseError>) {
+    let mut tokens = vec![];
+    let mut errors = vec![];
+    for token in Tokenizer::new(table, source, start) {
+        match token {
+            Ok(token) => tokens.push(token),
+            Err(err) => errors.push(err),
+        }
+    }
+    (tokens, errors)
+}
+
+/// Parses the `tokens` of a whole file, as `parse` does. Unlike
+/// tokenizing, this only reads `table`, to describe tokens in errors.
+pub fn parse_tokens(
+    tokens: &[(ByteIndex, Token, ByteIndex)],
+    table: &ModuleTable,
+) -> (ast::Module, Vec<ParseError>) {
+    let mut errors = vec![];
+    let parser = ItemParser::new();
+    let mut items = vec![];
+    for item_tokens in split_items(tokens) {
+        let mut recovered = vec![];
+        let result = parser.parse(&mut recovered, item_tokens.iter().cloned().map(Ok));
+        errors.extend(
+            recovered
+                .into_iter()
+                .map(|recovery| lalrpop_err(recovery.error, table)),
+        );
+        match result {
+            Ok(item) => items.push(item),
+            Err(err) => errors.push(lalrpop_err(err, table)),
+        }
+    }
+
+    (ast::Module::new(items), errors)
+}
+
+/// Splits `source` into the text of each of its items, as byte ranges
+/// that together cover the whole file: each runs from the start of an
+/// item to the start of the next. Parsing the ranges one at a time, each
+/// as if it were a file of its own, gives the same items and errors as
+/// parsing the whole file, so that a change to one item need only
+/// reparse that item.
+pub fn split_file(source: &str) -> Vec<Range<usize>> {
+    // Only the kinds and positions of the tokens matter here, so their
+    // text goes into a table of its own, rather than one that needs
+    // locking.
+    let mut table = ModuleTable::new();
+    let tokens: Vec<_> = Tokenizer::new(&mut table, source, 0)
+        .filter_map(|token| token.ok())
+        .collect();
+
+    let mut starts: Vec<usize> = split_items(&tokens)
+        .iter()
+        .map(|item| item[0].0.to_usize())
+        .collect();
+    match starts.first_mut() {
+        Some(start) => *start = 0,
+        None => starts.push(0),
+    }
+
+    let mut ranges = vec![];
+    for (i, &start) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).cloned().unwrap_or(source.len());
+        if start < end {
+            ranges.push(start..end);
+        }
+    }
+    ranges
+}
+
+/// Splits `tokens` into the tokens of each item, without the newlines
+/// around them. An item begins at each `struct`, `enum`, `mod` or
+/// `use`, and at each `def` other than a method directly within a
+/// struct's braces -- or at the `pub` before any of those. That does
+/// not rely on the braces before it being balanced, so a function the
+/// user has not closed yet does not swallow the items after it.
+fn split_items(tokens: &[(ByteIndex, Token, ByteIndex)]) -> Vec<&[(ByteIndex, Token, ByteIndex)]> {
+    let mut starts = vec![];
+    let mut in_struct = false;
+    let mut depth = 0;
+
+    for (index, (_, token, _)) in tokens.iter().enumerate() {
+        let after_pub = index > 0 && tokens[index - 1].1 == Token::KeywordPub;
+        let start = if after_pub { index - 1 } else { index };
+        match token {
+            Token::KeywordStruct | Token::KeywordEnum => {
+                starts.push(item_start(tokens, start));
+                in_struct = true;
+                depth = 0;
+            }
+            Token::KeywordMod | Token::KeywordUse => {
+                starts.push(item_start(tokens, start));
+                in_struct = false;
+                depth = 0;
+            }
+            Token::KeywordDef if !(in_struct && depth == 1) => {
+                starts.push(item_start(tokens, start));
+                in_struct = false;
+                depth = 0;
+            }
+            Token::CurlyBraceOpen => depth += 1,
+            Token::CurlyBraceClose => depth -= 1,
+            _ => {}
+        }
+    }
+
+    // Anything before the first item is an item of its own, so that
+    // it gets reported as an error.
+    if starts.first() != Some(&0) {
+        starts.insert(0, 0);
+    }
+
+    let mut items = vec![];
+    for (i, &start) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).cloned().unwrap_or(tokens.len());
+        let mut item = &tokens[start..end];
+        while let Some(((_, Token::Newline, _), rest)) = item.split_first() {
+            item = rest;
+        }
+        while let Some(((_, Token::Newline, _), rest)) = item.split_last() {
+            item = rest;
+        }
+        if !item.is_empty() {
+            items.push(item);
+        }
+    }
+    items
+}
+
+/// Where the item whose keyword is at `index` begins, which is at its
+/// first doc comment if it has any.
+fn item_start(tokens: &[(ByteIndex, Token, ByteIndex)], index: usize) -> usize {
+    let mut start = index;
+    for (i, (_, token, _)) in tokens[..index].iter().enumerate().rev() {
+        match token {
+            Token::Newline => {}
+            Token::DocComment(_) => start = i,
+            _ => break,
+        }
+    }
+    start
 }
 
 crate fn lalrpop_err(
@@ -68,7 +228,7 @@ crate fn lalrpop_err(
     match err {
         InvalidToken { location } => ParseError::from_pos("Invalid Token", location),
         UnrecognizedToken {
-            token: Some((left, token, right)),
+            token: (left, token, right),
             expected,
         } => ParseError::from(
             format!(
@@ -80,8 +240,8 @@ crate fn lalrpop_err(
             right,
         ),
 
-        UnrecognizedToken {
-            token: None,
+        UnrecognizedEOF {
+            location: _,
             expected,
         } => ParseError::from_eof(format!(
             "Unrecognized EOF, expected: {}",
@@ -179,19 +339,27 @@ mod test {
         let (actual, start) = parse_string(&source, &mut ann);
 
         let s = ast::Struct::new(
+            vec![],
+            ast::Visibility::Private,
             ann.ident(("open-struct", 1)),
+            vec![],
             vec![ann.field_mode("msg", 0), ann.field("level", 0)],
+            vec![],
             ann.span(("open-struct", 0), ("close-struct", 0)),
         );
 
         let def = ast::Def::new(
+            vec![],
+            ast::Visibility::Private,
             ann.ident(("new", 1)),
+            vec![],
+            None,
             vec![ann.field_mode("new", 2), ann.field("new", 5)],
             Some(ann.ty("new", 7)),
             ast::Block::spanned(
                 vec![ast::BlockItem::Expr(ast::Expression::ConstructStruct(
                     ast::ConstructStruct::new(
-                        ann.ident(("construct-diag", 0)),
+                        ast::Path::new(vec![ann.ident(("construct-diag", 0))]),
                         vec![
                             ann.shorthand(("construct-diag", 2)),
                             ann.shorthand(("construct-diag", 3)),
@@ -205,9 +373,13 @@ mod test {
         );
 
         let main = ast::Def::new(
+            vec![],
+            ast::Visibility::Private,
             ann.ident(("main", 1)),
             vec![],
             None,
+            vec![],
+            None,
             ast::Block::spanned(
                 vec![
                     ast::BlockItem::let_decl(
@@ -358,7 +530,7 @@ mod test {
 
         fn ty(&self, line: &str, start: u32) -> Spanned<ast::Type> {
             self.wrap_one(
-                ast::Type::new(None, self.ident((line, start))),
+                ast::Type::new(None, self.ident((line, start)), vec![]),
                 (line, start),
             )
         }
@@ -368,6 +540,7 @@ mod test {
                 ast::Type::new(
                     Some(self.mode((line, start))),
                     self.ident((line, start + 1)),
+                    vec![],
                 ),
                 (line, start),
                 (line, start + 1),
@@ -3,6 +3,18 @@ use std::fmt;
 use std::hash::Hash;
 use std::hash::Hasher;
 
+/// A range of bytes in a source file. What the offsets count from
+/// depends on where the span came from:
+///
+/// - In the AST of an item, as `AstDatabase::ast_of_item` gives it, and
+///   in everything computed from that (HIR, type check results), spans
+///   are relative to the start of the item's text, so that an edit to
+///   one item leaves the spans of the others unchanged.
+/// - In diagnostics, and in the AST of a whole file from `parse` or
+///   `ast_of_file`, spans are relative to the start of the file.
+///
+/// To turn the first kind into the second, move the span with `shifted`
+/// by the item's `item_offset` (or `def_offset`, for a `DefId`).
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
 pub enum Span {
     Real(ByteSpan),
@@ -35,13 +47,24 @@ impl Span {
         Span::Real(ByteSpan::new(ByteIndex(left), ByteIndex(right)))
     }
 
-    crate fn to(&self, to: Span) -> Span {
+    pub fn to(&self, to: Span) -> Span {
         match (self, to) {
             (Span::Real(left), Span::Real(right)) => Span::Real(left.to(right)),
             _ => Span::Synthetic,
         }
     }
 
+    /// The span `by` bytes later in the file; only real spans move.
+    pub fn shifted(&self, by: u32) -> Span {
+        match self {
+            Span::Real(span) => {
+                let by = ByteOffset(by as i64);
+                Span::Real(ByteSpan::new(span.start() + by, span.end() + by))
+            }
+            other => *other,
+        }
+    }
+
     crate fn to_codespan(&self) -> ByteSpan {
         match self {
             Span::Real(span) => *span,
@@ -131,7 +154,7 @@ impl<T> Spanned<T> {
     }
 }
 
-crate trait HasSpan {
+pub trait HasSpan {
     type Inner;
     fn span(&self) -> Span;
 }
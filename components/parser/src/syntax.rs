@@ -0,0 +1,30 @@
+//! A lossless syntax tree. Unlike the `ast`, which keeps only what the
+//! later passes need, this keeps every byte of the source: each token
+//! carries the whitespace and comments around it as trivia, so printing
+//! the tree gives back the file exactly. That makes it the starting
+//! point for tools that rewrite source, like a formatter.
+//!
+//! The tree has three layers: the green tree in `green`, which is
+//! immutable and position-independent; the red tree in `red`, which
+//! adds parents and offsets; and the typed wrappers in `typed`.
+
+mod green;
+mod parse;
+mod red;
+mod typed;
+
+pub use self::green::{GreenElement, GreenNode, GreenToken, SyntaxKind, Trivia, TriviaKind};
+pub use self::red::{SyntaxElement, SyntaxNode, SyntaxToken};
+pub use self::typed::*;
+
+use crate::ModuleTable;
+use std::sync::Arc;
+
+/// Parses `source` into a lossless syntax tree. This never fails: text
+/// that does not parse ends up in `Error` nodes, or as trivia if it
+/// does not even tokenize. The errors themselves come from `parse`.
+pub fn parse_syntax(source: &str, table: &mut ModuleTable) -> SourceFile {
+    let green = parse::parse_green(source, table);
+    let root = SyntaxNode::new_root(Arc::new(green));
+    SourceFile::cast(root).expect("root is not a source file")
+}
@@ -0,0 +1,207 @@
+//! The green tree: immutable nodes that know their kind, their children
+//! and how many bytes of source they cover, but not where they are.
+//! Since a green node does not know its position, an unchanged subtree
+//! can be shared between two versions of a file.
+
+use crate::Token;
+use std::sync::Arc;
+
+/// The kind of a syntax node. Tokens are identified by their `Token`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SyntaxKind {
+    SourceFile,
+
+    /// Tokens the parser could not make sense of, like a statement
+    /// starting with `)`.
+    Error,
+
+    Struct,
+    Enum,
+    Def,
+    Mod,
+    Use,
+
+    /// The `pub` before an item.
+    Visibility,
+
+    /// The generic parameters of an item, like `<K, V>`.
+    GenericParams,
+    Field,
+    Variant,
+
+    /// The fields of a variant like `Circle(i32)`.
+    TupleFields,
+
+    /// The fields of a variant like `Rect { w: i32, h: i32 }`.
+    StructFields,
+    ParamList,
+
+    /// The `self` parameter of a method, like `borrow self`.
+    SelfParam,
+    Param,
+    ReturnType,
+    Type,
+
+    /// The type arguments of a type, like `<own String>`.
+    TypeArgs,
+    Path,
+    Block,
+    Let,
+
+    WildcardPattern,
+
+    /// A pattern like `own x`.
+    BindingPattern,
+
+    /// A pattern that is just a path, like `x` or `Shape::Empty`.
+    PathPattern,
+    LiteralPattern,
+    TuplePattern,
+    StructPattern,
+
+    /// A field of a struct pattern, like the `w` or `h: 0` in
+    /// `Rect { w, h: 0 }`.
+    FieldPattern,
+
+    LiteralExpr,
+    PathExpr,
+    SelfExpr,
+    ParenExpr,
+    CallExpr,
+    MethodCallExpr,
+    FieldExpr,
+    BinaryExpr,
+    UnaryExpr,
+    IfExpr,
+    MatchExpr,
+    MatchArm,
+    LoopExpr,
+    WhileExpr,
+    ForExpr,
+    BreakExpr,
+    ContinueExpr,
+
+    /// Constructing a struct, like `Point { x, y }`.
+    StructExpr,
+    ConstructField,
+
+    /// A string with expressions embedded in it, like `"a {{b}} c"`.
+    InterpolationExpr,
+    ArgList,
+}
+
+/// What a piece of trivia is.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TriviaKind {
+    /// Spaces and tabs. Newlines are tokens of their own, except within
+    /// text that did not tokenize.
+    Whitespace,
+
+    /// A `//` comment, up to but not including the newline.
+    LineComment,
+
+    /// A `/* .. */` comment, which may span several lines.
+    BlockComment,
+
+    /// Text that the tokenizer rejected, like a stray `#`.
+    Unknown,
+}
+
+/// Source text that is not part of any token.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
+}
+
+/// A token, with the trivia around it. Trivia on the same line after a
+/// token belongs to it; the trivia at the start of a line belongs to the
+/// first token on it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GreenToken {
+    pub token: Token,
+    pub text: String,
+    pub leading: Vec<Trivia>,
+    pub trailing: Vec<Trivia>,
+}
+
+impl GreenToken {
+    /// The length of the token's text and its trivia, in bytes.
+    pub fn width(&self) -> usize {
+        self.leading_width() + self.text.len() + trivia_width(&self.trailing)
+    }
+
+    pub fn leading_width(&self) -> usize {
+        trivia_width(&self.leading)
+    }
+
+    /// Appends the token's text and its trivia to `out`.
+    pub fn write_to(&self, out: &mut String) {
+        for trivia in &self.leading {
+            out.push_str(&trivia.text);
+        }
+        out.push_str(&self.text);
+        for trivia in &self.trailing {
+            out.push_str(&trivia.text);
+        }
+    }
+}
+
+fn trivia_width(trivia: &[Trivia]) -> usize {
+    trivia.iter().map(|trivia| trivia.text.len()).sum()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GreenNode {
+    pub kind: SyntaxKind,
+    pub children: Vec<GreenElement>,
+    width: usize,
+}
+
+impl GreenNode {
+    pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> GreenNode {
+        let width = children.iter().map(|child| child.width()).sum();
+        GreenNode {
+            kind,
+            children,
+            width,
+        }
+    }
+
+    /// The length of the source the node covers, in bytes.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Appends the source the node covers to `out`.
+    pub fn write_to(&self, out: &mut String) {
+        for child in &self.children {
+            match child {
+                GreenElement::Node(node) => node.write_to(out),
+                GreenElement::Token(token) => token.write_to(out),
+            }
+        }
+    }
+
+    /// The source the node covers.
+    pub fn text(&self) -> String {
+        let mut out = String::with_capacity(self.width);
+        self.write_to(&mut out);
+        out
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum GreenElement {
+    Node(Arc<GreenNode>),
+    Token(Arc<GreenToken>),
+}
+
+impl GreenElement {
+    pub fn width(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.width(),
+            GreenElement::Token(token) => token.width(),
+        }
+    }
+}
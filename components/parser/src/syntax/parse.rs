@@ -0,0 +1,944 @@
+//! Builds the green tree for a file. This parser mirrors the grammar in
+//! `grammar.lalrpop`, but it never fails: tokens it does not expect end
+//! up in `Error` nodes, a part the grammar requires but that is missing
+//! becomes an empty `Error` node, and every byte of the source,
+//! including the trivia the tokenizer skips, ends up somewhere in the
+//! tree. The test `syntax_tree_agrees_with_grammar` checks that the two
+//! parsers accept the same files.
+
+use crate::ast::Op;
+use crate::syntax::green::{GreenElement, GreenNode, GreenToken, SyntaxKind, Trivia, TriviaKind};
+use crate::{ModuleTable, Token, Tokenizer};
+use std::sync::Arc;
+
+/// Parses `source` into the green tree of a `SourceFile`.
+crate fn parse_green(source: &str, table: &mut ModuleTable) -> GreenNode {
+    let (tokens, overlapping) = lex(source, table);
+    let mut parser = Parser {
+        tokens,
+        overlapping,
+        position: 0,
+        stack: vec![],
+    };
+    parser.source_file();
+    let (kind, children) = parser.stack.pop().expect("no source file");
+    GreenNode::new(kind, children)
+}
+
+/// Splits `source` into tokens, attaching the text between them to the
+/// tokens as trivia. The last token is always an `EndOfFile`. Also
+/// returns the indices of the tokens that overlap the one before them.
+fn lex(source: &str, table: &mut ModuleTable) -> (Vec<GreenToken>, Vec<usize>) {
+    let spans: Vec<_> = Tokenizer::new(table, source, 0)
+        .filter_map(|token| token.ok())
+        .map(|(start, token, end)| (start.to_usize(), token, end.to_usize()))
+        .collect();
+
+    let mut tokens: Vec<GreenToken> = vec![];
+    let mut overlapping = vec![];
+    let mut position = 0;
+    for (start, token, end) in spans.into_iter().chain(Some((
+        source.len(),
+        Token::EndOfFile,
+        source.len(),
+    ))) {
+        // A token that overlaps the one before it would write out the
+        // shared text twice, so it keeps only the text after the earlier
+        // token, and the parser puts it in an `Error` node.
+        let start = if start < position {
+            overlapping.push(tokens.len());
+            position
+        } else {
+            start
+        };
+        let end = end.max(position);
+
+        let gap = trivia(&source[position..start]);
+        let mut green = GreenToken {
+            token,
+            text: source[start..end].to_string(),
+            leading: vec![],
+            trailing: vec![],
+        };
+        match tokens.last_mut() {
+            Some(previous) if previous.token != Token::Newline => previous.trailing = gap,
+            _ => green.leading = gap,
+        }
+        tokens.push(green);
+        position = end;
+    }
+    (tokens, overlapping)
+}
+
+/// Splits `text`, which the tokenizer skipped, into trivia.
+fn trivia(text: &str) -> Vec<Trivia> {
+    let mut trivia = vec![];
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let (kind, len) = if rest.starts_with("//") {
+            (TriviaKind::LineComment, rest.find('\n').unwrap_or(rest.len()))
+        } else if rest.starts_with("/*") {
+            (TriviaKind::BlockComment, block_comment_len(rest))
+        } else if c.is_whitespace() {
+            let len = rest
+                .find(|c: char| !c.is_whitespace())
+                .unwrap_or(rest.len());
+            (TriviaKind::Whitespace, len)
+        } else {
+            let len = rest
+                .char_indices()
+                .skip(1)
+                .find(|&(i, c)| c.is_whitespace() || rest[i..].starts_with('/'))
+                .map_or(rest.len(), |(i, _)| i);
+            (TriviaKind::Unknown, len)
+        };
+        trivia.push(Trivia {
+            kind,
+            text: rest[..len].to_string(),
+        });
+        rest = &rest[len..];
+    }
+    trivia
+}
+
+/// The length of the (possibly nested) block comment at the start of
+/// `text`, or of all of `text` if the comment is not closed.
+fn block_comment_len(text: &str) -> usize {
+    let mut depth = 0;
+    let mut index = 0;
+    while index < text.len() {
+        if text[index..].starts_with("/*") {
+            depth += 1;
+            index += 2;
+        } else if text[index..].starts_with("*/") {
+            depth -= 1;
+            index += 2;
+            if depth == 0 {
+                return index;
+            }
+        } else {
+            index += text[index..].chars().next().unwrap().len_utf8();
+        }
+    }
+    text.len()
+}
+
+struct Parser {
+    tokens: Vec<GreenToken>,
+
+    /// The indices of the tokens that overlap the one before them, in
+    /// order.
+    overlapping: Vec<usize>,
+    position: usize,
+
+    /// The nodes we are in the middle of, each with the children it has
+    /// so far.
+    stack: Vec<(SyntaxKind, Vec<GreenElement>)>,
+}
+
+/// Where a node may later be started, so that it wraps the children
+/// added since, as for the left operand of a binary expression.
+#[derive(Copy, Clone)]
+struct Checkpoint(usize);
+
+impl Parser {
+    fn nth(&self, n: usize) -> Token {
+        self.tokens
+            .get(self.position + n)
+            .map_or(Token::EndOfFile, |token| token.token)
+    }
+
+    fn current(&self) -> Token {
+        self.nth(0)
+    }
+
+    fn at(&self, token: Token) -> bool {
+        self.current() == token
+    }
+
+    fn at_end(&self) -> bool {
+        self.at(Token::EndOfFile)
+    }
+
+    fn at_identifier(&self) -> bool {
+        is_identifier(self.current())
+    }
+
+    /// Moves the current token into the innermost node, or into an
+    /// `Error` node within it if the tokenizer gave it overlapping the
+    /// token before.
+    fn bump(&mut self) {
+        let token = GreenElement::Token(Arc::new(self.tokens[self.position].clone()));
+        if self.overlapping.binary_search(&self.position).is_ok() {
+            self.push(GreenElement::Node(Arc::new(GreenNode::new(
+                SyntaxKind::Error,
+                vec![token],
+            ))));
+        } else {
+            self.push(token);
+        }
+        self.position += 1;
+    }
+
+    fn push(&mut self, element: GreenElement) {
+        self.stack.last_mut().expect("no node").1.push(element);
+    }
+
+    fn eat(&mut self, token: Token) -> bool {
+        if self.at(token) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Moves the current token, which must be an identifier, into the
+    /// innermost node.
+    fn expect_identifier(&mut self) {
+        if self.at_identifier() {
+            self.bump();
+        } else {
+            self.missing();
+        }
+    }
+
+    fn eat_newlines(&mut self) {
+        while self.eat(Token::Newline) {}
+    }
+
+    fn start(&mut self, kind: SyntaxKind) {
+        self.stack.push((kind, vec![]));
+    }
+
+    fn finish(&mut self) {
+        let (kind, children) = self.stack.pop().expect("no node");
+        if self.stack.is_empty() {
+            self.stack.push((kind, children));
+        } else {
+            self.push(GreenElement::Node(Arc::new(GreenNode::new(kind, children))));
+        }
+    }
+
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.stack.last().expect("no node").1.len())
+    }
+
+    /// Starts a node of `kind` that begins with the children added since
+    /// `checkpoint`.
+    fn start_at(&mut self, checkpoint: Checkpoint, kind: SyntaxKind) {
+        let children = self.stack.last_mut().expect("no node").1.split_off(checkpoint.0);
+        self.stack.push((kind, children));
+    }
+
+    /// Wraps tokens in an `Error` node up to a newline, `stop`, an
+    /// unmatched closing brace or the end of the file, skipping over
+    /// anything in braces or parentheses. At least one token is taken
+    /// unless we are at a newline or the end of the file.
+    fn error(&mut self, stop: impl Fn(Token) -> bool) {
+        if self.at(Token::Newline) || self.at_end() {
+            return;
+        }
+        self.start(SyntaxKind::Error);
+        let mut depth = match self.current() {
+            Token::CurlyBraceOpen | Token::ParenOpen => 1,
+            _ => 0,
+        };
+        self.bump();
+        loop {
+            let token = self.current();
+            if token == Token::EndOfFile || (depth == 0 && (token == Token::Newline || stop(token)))
+            {
+                break;
+            }
+            match token {
+                Token::CurlyBraceOpen | Token::ParenOpen => depth += 1,
+                Token::CurlyBraceClose | Token::ParenClose if depth > 0 => depth -= 1,
+                Token::CurlyBraceClose | Token::ParenClose => break,
+                _ => {}
+            }
+            self.bump();
+        }
+        self.finish();
+    }
+
+    /// Adds an empty `Error` node where the grammar requires something
+    /// that is not there.
+    fn missing(&mut self) {
+        self.push(GreenElement::Node(Arc::new(GreenNode::new(
+            SyntaxKind::Error,
+            vec![],
+        ))));
+    }
+
+    fn source_file(&mut self) {
+        self.start(SyntaxKind::SourceFile);
+        loop {
+            self.eat_newlines();
+            if self.at_end() {
+                break;
+            }
+            match self.item_kind() {
+                Some(_) => self.item(),
+                None => self.error(|_| false),
+            }
+        }
+        self.bump();
+        self.finish();
+    }
+
+    /// The kind of the item that starts at the current token, looking
+    /// past its doc comments and visibility.
+    fn item_kind(&self) -> Option<SyntaxKind> {
+        let mut n = 0;
+        while let Token::DocComment(_) | Token::Newline | Token::KeywordPub = self.nth(n) {
+            n += 1;
+        }
+        match self.nth(n) {
+            Token::KeywordStruct => Some(SyntaxKind::Struct),
+            Token::KeywordEnum => Some(SyntaxKind::Enum),
+            Token::KeywordDef => Some(SyntaxKind::Def),
+            Token::KeywordMod => Some(SyntaxKind::Mod),
+            Token::KeywordUse => Some(SyntaxKind::Use),
+            _ => None,
+        }
+    }
+
+    /// True at a token that can only start an item, so that a block or
+    /// list that is missing its closing brace ends there.
+    fn at_item_start(&self) -> bool {
+        match self.current() {
+            Token::KeywordStruct
+            | Token::KeywordEnum
+            | Token::KeywordDef
+            | Token::KeywordMod
+            | Token::KeywordUse
+            | Token::KeywordPub
+            | Token::DocComment(_) => true,
+            _ => false,
+        }
+    }
+
+    fn item(&mut self) {
+        let kind = self.item_kind().expect("not at an item");
+        self.start(kind);
+        self.docs();
+        if self.at(Token::KeywordPub) {
+            self.start(SyntaxKind::Visibility);
+            self.bump();
+            self.finish();
+        }
+        self.bump();
+        match kind {
+            SyntaxKind::Struct => self.struct_rest(),
+            SyntaxKind::Enum => self.enum_rest(),
+            SyntaxKind::Def => self.def_rest(),
+            SyntaxKind::Mod => {
+                self.expect_identifier();
+            }
+            SyntaxKind::Use => {
+                self.path();
+                if self.eat(Token::KeywordAs) {
+                    self.expect_identifier();
+                }
+            }
+            _ => unreachable!(),
+        }
+        self.finish();
+    }
+
+    /// The `///` comments before an item, field or variant, each with
+    /// the newline after it.
+    fn docs(&mut self) {
+        while let Token::DocComment(_) = self.current() {
+            self.bump();
+            self.eat_newlines();
+        }
+    }
+
+    fn struct_rest(&mut self) {
+        self.expect_identifier();
+        self.generic_params();
+        if !self.eat(Token::CurlyBraceOpen) {
+            return;
+        }
+        loop {
+            self.eat_newlines();
+            if self.eat(Token::CurlyBraceClose) || self.at_end() {
+                break;
+            }
+            match self.item_kind() {
+                Some(SyntaxKind::Def) => self.item(),
+                Some(_) => break,
+                None if self.at_identifier() || self.at_doc_comment() => self.field(),
+                None => self.error(|token| token == Token::Comma),
+            }
+            self.eat(Token::Comma);
+        }
+    }
+
+    fn at_doc_comment(&self) -> bool {
+        match self.current() {
+            Token::DocComment(_) => true,
+            _ => false,
+        }
+    }
+
+    fn field(&mut self) {
+        self.start(SyntaxKind::Field);
+        self.docs();
+        self.expect_identifier();
+        if self.eat(Token::Colon) {
+            self.ty();
+        }
+        self.finish();
+    }
+
+    fn enum_rest(&mut self) {
+        self.expect_identifier();
+        self.generic_params();
+        if !self.eat(Token::CurlyBraceOpen) {
+            return;
+        }
+        loop {
+            self.eat_newlines();
+            if self.eat(Token::CurlyBraceClose) || self.at_end() || self.item_kind().is_some() {
+                break;
+            }
+            if self.at_identifier() || self.at_doc_comment() {
+                self.variant();
+            } else {
+                self.error(|token| token == Token::Comma);
+            }
+            self.eat(Token::Comma);
+        }
+    }
+
+    fn variant(&mut self) {
+        self.start(SyntaxKind::Variant);
+        self.docs();
+        self.expect_identifier();
+        if self.at(Token::ParenOpen) {
+            self.start(SyntaxKind::TupleFields);
+            self.bump();
+            self.list(Token::ParenClose, |parser| parser.at_type_start(), |parser| parser.ty());
+            self.finish();
+        } else if self.at(Token::CurlyBraceOpen) {
+            self.start(SyntaxKind::StructFields);
+            self.bump();
+            self.list(
+                Token::CurlyBraceClose,
+                |parser| parser.at_identifier(),
+                |parser| parser.field(),
+            );
+            self.finish();
+        }
+        self.finish();
+    }
+
+    /// The elements of a list after its opening delimiter, up to and
+    /// including `close`. Elements start where `at_element` is true and
+    /// are parsed by `element`; commas and newlines may come between
+    /// them.
+    fn list(
+        &mut self,
+        close: Token,
+        at_element: impl Fn(&Parser) -> bool,
+        element: impl Fn(&mut Parser),
+    ) {
+        loop {
+            if self.eat(close) || self.at_end() || self.at_item_start() {
+                break;
+            }
+            if self.eat(Token::Comma) || self.eat(Token::Newline) {
+                continue;
+            }
+            if at_element(self) {
+                element(self);
+            } else {
+                self.start(SyntaxKind::Error);
+                self.bump();
+                self.finish();
+            }
+        }
+    }
+
+    fn def_rest(&mut self) {
+        self.expect_identifier();
+        self.generic_params();
+        if self.at(Token::ParenOpen) {
+            self.param_list();
+        }
+        if self.at(Token::ThinArrow) {
+            self.start(SyntaxKind::ReturnType);
+            self.bump();
+            self.ty();
+            self.finish();
+        }
+        if self.at(Token::CurlyBraceOpen) {
+            self.block();
+        }
+    }
+
+    fn param_list(&mut self) {
+        self.start(SyntaxKind::ParamList);
+        self.bump();
+        self.list(
+            Token::ParenClose,
+            |parser| parser.at_identifier() || is_mode(parser.current()),
+            |parser| {
+                if is_mode(parser.current()) {
+                    parser.start(SyntaxKind::SelfParam);
+                    parser.bump();
+                    parser.eat(Token::KeywordSelf);
+                } else {
+                    parser.start(SyntaxKind::Param);
+                    parser.bump();
+                    if parser.eat(Token::Colon) {
+                        parser.ty();
+                    }
+                }
+                parser.finish();
+            },
+        );
+        self.finish();
+    }
+
+    fn generic_params(&mut self) {
+        if self.at(Token::OpLess) {
+            self.start(SyntaxKind::GenericParams);
+            self.bump();
+            self.list(
+                Token::OpGreater,
+                |parser| parser.at_identifier(),
+                |parser| parser.bump(),
+            );
+            self.finish();
+        }
+    }
+
+    fn at_type_start(&self) -> bool {
+        self.at_identifier() || is_mode(self.current())
+    }
+
+    fn ty(&mut self) {
+        if !self.at_type_start() {
+            self.missing();
+            return;
+        }
+        self.start(SyntaxKind::Type);
+        if is_mode(self.current()) {
+            self.bump();
+        }
+        self.expect_identifier();
+        if self.at(Token::OpLess) {
+            self.start(SyntaxKind::TypeArgs);
+            self.bump();
+            self.list(
+                Token::OpGreater,
+                |parser| parser.at_type_start(),
+                |parser| parser.ty(),
+            );
+            self.finish();
+        }
+        self.finish();
+    }
+
+    fn path(&mut self) {
+        self.start(SyntaxKind::Path);
+        self.expect_identifier();
+        while self.at(Token::DoubleColon) {
+            self.bump();
+            self.expect_identifier();
+        }
+        self.finish();
+    }
+
+    fn block(&mut self) {
+        self.start(SyntaxKind::Block);
+        self.bump();
+        loop {
+            self.eat_newlines();
+            if self.eat(Token::CurlyBraceClose) || self.at_end() || self.at_item_start() {
+                break;
+            }
+            self.statement();
+            if !self.at(Token::Newline) && !self.at(Token::CurlyBraceClose) && !self.at_end() {
+                self.error(|token| token == Token::CurlyBraceClose);
+            }
+        }
+        self.finish();
+    }
+
+    fn statement(&mut self) {
+        if self.at(Token::KeywordLet) {
+            self.start(SyntaxKind::Let);
+            self.bump();
+            self.pattern();
+            if self.eat(Token::Colon) {
+                self.ty();
+            }
+            if self.eat(Token::Equals) {
+                self.expr(false);
+            }
+            self.finish();
+        } else {
+            self.expr(false);
+        }
+    }
+
+    fn pattern(&mut self) {
+        let token = self.current();
+        if token == Token::Underscore {
+            self.start(SyntaxKind::WildcardPattern);
+            self.bump();
+            self.finish();
+        } else if is_mode(token) {
+            self.start(SyntaxKind::BindingPattern);
+            self.bump();
+            self.expect_identifier();
+            self.finish();
+        } else if is_literal(token) {
+            self.start(SyntaxKind::LiteralPattern);
+            self.bump();
+            self.finish();
+        } else if is_identifier(token) {
+            let checkpoint = self.checkpoint();
+            self.path();
+            if self.at(Token::ParenOpen) {
+                self.start_at(checkpoint, SyntaxKind::TuplePattern);
+                self.bump();
+                self.list(
+                    Token::ParenClose,
+                    |parser| parser.at_pattern_start(),
+                    |parser| parser.pattern(),
+                );
+            } else if self.at(Token::CurlyBraceOpen) {
+                self.start_at(checkpoint, SyntaxKind::StructPattern);
+                self.bump();
+                self.list(
+                    Token::CurlyBraceClose,
+                    |parser| parser.at_identifier(),
+                    |parser| {
+                        parser.start(SyntaxKind::FieldPattern);
+                        parser.bump();
+                        if parser.eat(Token::Colon) {
+                            parser.pattern();
+                        }
+                        parser.finish();
+                    },
+                );
+            } else {
+                self.start_at(checkpoint, SyntaxKind::PathPattern);
+            }
+            self.finish();
+        } else {
+            self.missing();
+        }
+    }
+
+    fn at_pattern_start(&self) -> bool {
+        let token = self.current();
+        token == Token::Underscore || is_mode(token) || is_literal(token) || is_identifier(token)
+    }
+
+    /// An expression. If `no_struct` is true, a path followed by `{` is
+    /// not a struct, so that the `{` can start the block in `if a { .. }`.
+    fn expr(&mut self, no_struct: bool) {
+        match self.current() {
+            Token::KeywordBreak => {
+                self.start(SyntaxKind::BreakExpr);
+                self.bump();
+                self.eat_label();
+                if self.at_expr_start() {
+                    self.binary(0, no_struct);
+                }
+                self.finish();
+            }
+            Token::KeywordContinue => {
+                self.start(SyntaxKind::ContinueExpr);
+                self.bump();
+                self.eat_label();
+                self.finish();
+            }
+            _ => self.binary(0, no_struct),
+        }
+    }
+
+    fn eat_label(&mut self) {
+        if let Token::Label(_) = self.current() {
+            self.bump();
+        }
+    }
+
+    fn at_expr_start(&self) -> bool {
+        match self.current() {
+            Token::OpSub
+            | Token::OpNot
+            | Token::ParenOpen
+            | Token::KeywordSelf
+            | Token::KeywordIf
+            | Token::KeywordMatch
+            | Token::KeywordLoop
+            | Token::KeywordWhile
+            | Token::KeywordFor
+            | Token::Label(_)
+            | Token::StringFragment(_) => true,
+            token => is_identifier(token) || is_literal(token),
+        }
+    }
+
+    /// Binary operators whose precedence is at least `min_precedence`,
+    /// grouped as `climb` groups them.
+    fn binary(&mut self, min_precedence: u8, no_struct: bool) {
+        let checkpoint = self.checkpoint();
+        self.unary(no_struct);
+        while let Some(op) = binary_op(self.current()) {
+            let precedence = op.precedence();
+            if precedence < min_precedence {
+                break;
+            }
+            self.start_at(checkpoint, SyntaxKind::BinaryExpr);
+            self.bump();
+            self.binary(precedence + 1, no_struct);
+            self.finish();
+        }
+    }
+
+    fn unary(&mut self, no_struct: bool) {
+        if self.at(Token::OpSub) || self.at(Token::OpNot) {
+            self.start(SyntaxKind::UnaryExpr);
+            self.bump();
+            self.unary(no_struct);
+            self.finish();
+        } else {
+            self.postfix(no_struct);
+        }
+    }
+
+    fn postfix(&mut self, no_struct: bool) {
+        let checkpoint = self.checkpoint();
+        self.primary(no_struct);
+        while self.at(Token::Period) {
+            if is_identifier(self.nth(1)) && self.nth(2) == Token::ParenOpen {
+                self.start_at(checkpoint, SyntaxKind::MethodCallExpr);
+                self.bump();
+                self.bump();
+                self.arg_list();
+            } else {
+                self.start_at(checkpoint, SyntaxKind::FieldExpr);
+                self.bump();
+                self.expect_identifier();
+            }
+            self.finish();
+        }
+    }
+
+    fn primary(&mut self, no_struct: bool) {
+        let token = self.current();
+        match token {
+            Token::CurlyBraceOpen => self.block(),
+            Token::KeywordIf => self.if_expr(),
+            Token::KeywordMatch => self.match_expr(),
+            Token::KeywordLoop | Token::KeywordWhile | Token::KeywordFor | Token::Label(_) => {
+                self.loop_expr()
+            }
+            Token::KeywordSelf => {
+                self.start(SyntaxKind::SelfExpr);
+                self.bump();
+                self.finish();
+            }
+            Token::ParenOpen => {
+                self.start(SyntaxKind::ParenExpr);
+                self.bump();
+                self.eat_newlines();
+                self.expr(false);
+                self.eat_newlines();
+                self.eat(Token::ParenClose);
+                self.finish();
+            }
+            Token::StringFragment(_) => self.interpolation(),
+            _ if is_literal(token) => {
+                self.start(SyntaxKind::LiteralExpr);
+                self.bump();
+                self.finish();
+            }
+            _ if is_identifier(token) => {
+                let checkpoint = self.checkpoint();
+                self.path();
+                if self.at(Token::ParenOpen) {
+                    self.start_at(checkpoint, SyntaxKind::CallExpr);
+                    self.arg_list();
+                } else if self.at(Token::CurlyBraceOpen) && !no_struct {
+                    self.start_at(checkpoint, SyntaxKind::StructExpr);
+                    self.bump();
+                    self.list(
+                        Token::CurlyBraceClose,
+                        |parser| parser.at_identifier(),
+                        |parser| {
+                            parser.start(SyntaxKind::ConstructField);
+                            parser.bump();
+                            if parser.eat(Token::Colon) {
+                                parser.ty();
+                            }
+                            parser.finish();
+                        },
+                    );
+                } else {
+                    self.start_at(checkpoint, SyntaxKind::PathExpr);
+                }
+                self.finish();
+            }
+            _ => self.missing(),
+        }
+    }
+
+    fn arg_list(&mut self) {
+        self.start(SyntaxKind::ArgList);
+        self.bump();
+        self.list(
+            Token::ParenClose,
+            |parser| parser.at_expr_start() || parser.at(Token::KeywordBreak),
+            |parser| parser.expr(false),
+        );
+        self.finish();
+    }
+
+    fn if_expr(&mut self) {
+        self.start(SyntaxKind::IfExpr);
+        self.bump();
+        if self.eat(Token::KeywordLet) {
+            self.pattern();
+            self.eat(Token::Equals);
+        }
+        self.expr(true);
+        if self.at(Token::CurlyBraceOpen) {
+            self.block();
+        }
+        if self.eat(Token::KeywordElse) {
+            if self.at(Token::KeywordIf) {
+                self.if_expr();
+            } else if self.at(Token::CurlyBraceOpen) {
+                self.block();
+            }
+        }
+        self.finish();
+    }
+
+    fn match_expr(&mut self) {
+        self.start(SyntaxKind::MatchExpr);
+        self.bump();
+        self.expr(true);
+        if self.eat(Token::CurlyBraceOpen) {
+            self.list(
+                Token::CurlyBraceClose,
+                |parser| parser.at_pattern_start(),
+                |parser| {
+                    parser.start(SyntaxKind::MatchArm);
+                    parser.pattern();
+                    if parser.eat(Token::FatArrow) {
+                        parser.expr(false);
+                    }
+                    parser.finish();
+                },
+            );
+        }
+        self.finish();
+    }
+
+    fn loop_expr(&mut self) {
+        let keyword = match self.current() {
+            Token::Label(_) => self.nth(2),
+            token => token,
+        };
+        let kind = match keyword {
+            Token::KeywordWhile => SyntaxKind::WhileExpr,
+            Token::KeywordFor => SyntaxKind::ForExpr,
+            _ => SyntaxKind::LoopExpr,
+        };
+        self.start(kind);
+        if let Token::Label(_) = self.current() {
+            self.bump();
+            self.eat(Token::Colon);
+        }
+        if self.at(keyword) {
+            self.bump();
+            match kind {
+                SyntaxKind::WhileExpr => self.expr(true),
+                SyntaxKind::ForExpr => {
+                    self.pattern();
+                    self.eat(Token::KeywordIn);
+                    self.expr(true);
+                    self.eat(Token::DotDot);
+                    self.expr(true);
+                }
+                _ => {}
+            }
+            if self.at(Token::CurlyBraceOpen) {
+                self.block();
+            }
+        }
+        self.finish();
+    }
+
+    fn interpolation(&mut self) {
+        self.start(SyntaxKind::InterpolationExpr);
+        self.bump();
+        loop {
+            self.binary(0, false);
+            match self.current() {
+                Token::StringFragment(_) => self.bump(),
+                Token::EndString(_) => {
+                    self.bump();
+                    break;
+                }
+                _ => break,
+            }
+        }
+        self.finish();
+    }
+}
+
+fn is_identifier(token: Token) -> bool {
+    match token {
+        Token::Identifier(_) => true,
+        _ => false,
+    }
+}
+
+fn is_mode(token: Token) -> bool {
+    match token {
+        Token::KeywordOwn | Token::KeywordBorrow | Token::KeywordShare => true,
+        _ => false,
+    }
+}
+
+fn is_literal(token: Token) -> bool {
+    match token {
+        Token::IntegerLiteral(_)
+        | Token::FloatLiteral(_)
+        | Token::StringLiteral(_)
+        | Token::KeywordTrue
+        | Token::KeywordFalse => true,
+        _ => false,
+    }
+}
+
+fn binary_op(token: Token) -> Option<Op> {
+    Some(match token {
+        Token::OpAdd => Op::Add,
+        Token::OpSub => Op::Sub,
+        Token::OpMul => Op::Mul,
+        Token::OpDiv => Op::Div,
+        Token::OpRem => Op::Rem,
+        Token::OpEqual => Op::Equal,
+        Token::OpNotEqual => Op::NotEqual,
+        Token::OpLess => Op::Less,
+        Token::OpLessEqual => Op::LessEqual,
+        Token::OpGreater => Op::Greater,
+        Token::OpGreaterEqual => Op::GreaterEqual,
+        Token::OpAnd => Op::And,
+        Token::OpOr => Op::Or,
+        _ => return None,
+    })
+}
@@ -0,0 +1,275 @@
+//! The red tree: a view of a green tree in which each node knows its
+//! parent and its offset within the file. Red nodes are created on
+//! demand as the tree is walked and are cheap to clone.
+
+use crate::syntax::green::{GreenElement, GreenNode, GreenToken, SyntaxKind, Trivia};
+use crate::Token;
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct SyntaxNode(Arc<NodeData>);
+
+struct NodeData {
+    green: Arc<GreenNode>,
+    parent: Option<SyntaxNode>,
+
+    /// Where the node starts, including any leading trivia.
+    offset: usize,
+}
+
+impl SyntaxNode {
+    /// The root of the tree for `green`, which starts at offset 0.
+    pub fn new_root(green: Arc<GreenNode>) -> SyntaxNode {
+        SyntaxNode(Arc::new(NodeData {
+            green,
+            parent: None,
+            offset: 0,
+        }))
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.green.kind
+    }
+
+    pub fn green(&self) -> &Arc<GreenNode> {
+        &self.0.green
+    }
+
+    pub fn parent(&self) -> Option<SyntaxNode> {
+        self.0.parent.clone()
+    }
+
+    /// The node's parent, its parent's parent, and so on up to the root.
+    pub fn ancestors(&self) -> Vec<SyntaxNode> {
+        let mut ancestors = vec![];
+        let mut node = self.parent();
+        while let Some(parent) = node {
+            node = parent.parent();
+            ancestors.push(parent);
+        }
+        ancestors
+    }
+
+    /// The bytes of the file the node covers, including the trivia of
+    /// its first and last tokens.
+    pub fn full_range(&self) -> Range<usize> {
+        self.0.offset..self.0.offset + self.0.green.width()
+    }
+
+    /// The bytes of the file the node covers, without the trivia before
+    /// its first token and after its last one.
+    pub fn range(&self) -> Range<usize> {
+        let tokens = self.tokens();
+        match (tokens.first(), tokens.last()) {
+            (Some(first), Some(last)) => first.range().start..last.range().end,
+            _ => self.full_range(),
+        }
+    }
+
+    pub fn children(&self) -> Vec<SyntaxElement> {
+        let mut offset = self.0.offset;
+        let mut children = Vec::with_capacity(self.0.green.children.len());
+        for child in &self.0.green.children {
+            children.push(match child {
+                GreenElement::Node(green) => SyntaxElement::Node(SyntaxNode(Arc::new(NodeData {
+                    green: green.clone(),
+                    parent: Some(self.clone()),
+                    offset,
+                }))),
+                GreenElement::Token(green) => SyntaxElement::Token(SyntaxToken {
+                    green: green.clone(),
+                    parent: self.clone(),
+                    offset,
+                }),
+            });
+            offset += child.width();
+        }
+        children
+    }
+
+    pub fn child_nodes(&self) -> Vec<SyntaxNode> {
+        self.children()
+            .into_iter()
+            .filter_map(|child| match child {
+                SyntaxElement::Node(node) => Some(node),
+                SyntaxElement::Token(_) => None,
+            })
+            .collect()
+    }
+
+    pub fn child_tokens(&self) -> Vec<SyntaxToken> {
+        self.children()
+            .into_iter()
+            .filter_map(|child| match child {
+                SyntaxElement::Node(_) => None,
+                SyntaxElement::Token(token) => Some(token),
+            })
+            .collect()
+    }
+
+    /// The first child token for which `test` is true.
+    pub fn child_token(&self, test: impl Fn(Token) -> bool) -> Option<SyntaxToken> {
+        self.child_tokens()
+            .into_iter()
+            .find(|token| test(token.token()))
+    }
+
+    /// All the tokens within the node, in order.
+    pub fn tokens(&self) -> Vec<SyntaxToken> {
+        let mut tokens = vec![];
+        for child in self.children() {
+            match child {
+                SyntaxElement::Node(node) => tokens.extend(node.tokens()),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+        tokens
+    }
+
+    /// The innermost token whose text or trivia contains `offset`.
+    pub fn token_at(&self, offset: usize) -> Option<SyntaxToken> {
+        for child in self.children() {
+            match child {
+                SyntaxElement::Node(node) => {
+                    if node.full_range().contains(&offset) {
+                        return node.token_at(offset);
+                    }
+                }
+                SyntaxElement::Token(token) => {
+                    if token.full_range().contains(&offset) {
+                        return Some(token);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// The source the node covers, including trivia.
+    pub fn text(&self) -> String {
+        self.0.green.text()
+    }
+
+    fn debug_tree(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        writeln!(f, "{:indent$}{:?}@{:?}", "", self.kind(), self.range(), indent = depth * 2)?;
+        for child in self.children() {
+            match child {
+                SyntaxElement::Node(node) => node.debug_tree(f, depth + 1)?,
+                SyntaxElement::Token(token) => writeln!(
+                    f,
+                    "{:indent$}{:?}",
+                    "",
+                    token,
+                    indent = (depth + 1) * 2
+                )?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PartialEq for SyntaxNode {
+    fn eq(&self, other: &SyntaxNode) -> bool {
+        Arc::ptr_eq(&self.0.green, &other.0.green) && self.0.offset == other.0.offset
+    }
+}
+
+impl Eq for SyntaxNode {}
+
+impl fmt::Display for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+/// Prints the tree, one node or token per line, indented by depth.
+impl fmt::Debug for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.debug_tree(f, 0)
+    }
+}
+
+#[derive(Clone)]
+pub struct SyntaxToken {
+    green: Arc<GreenToken>,
+    parent: SyntaxNode,
+
+    /// Where the token's leading trivia starts.
+    offset: usize,
+}
+
+impl SyntaxToken {
+    pub fn token(&self) -> Token {
+        self.green.token
+    }
+
+    pub fn green(&self) -> &Arc<GreenToken> {
+        &self.green
+    }
+
+    pub fn parent(&self) -> SyntaxNode {
+        self.parent.clone()
+    }
+
+    /// The token's text, without its trivia.
+    pub fn text(&self) -> &str {
+        &self.green.text
+    }
+
+    pub fn leading(&self) -> &[Trivia] {
+        &self.green.leading
+    }
+
+    pub fn trailing(&self) -> &[Trivia] {
+        &self.green.trailing
+    }
+
+    /// The bytes of the file the token's text covers.
+    pub fn range(&self) -> Range<usize> {
+        let start = self.offset + self.green.leading_width();
+        start..start + self.green.text.len()
+    }
+
+    /// The bytes of the file the token and its trivia cover.
+    pub fn full_range(&self) -> Range<usize> {
+        self.offset..self.offset + self.green.width()
+    }
+}
+
+impl PartialEq for SyntaxToken {
+    fn eq(&self, other: &SyntaxToken) -> bool {
+        Arc::ptr_eq(&self.green, &other.green) && self.offset == other.offset
+    }
+}
+
+impl Eq for SyntaxToken {}
+
+impl fmt::Debug for SyntaxToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}@{:?} {:?}", self.token(), self.range(), self.text())?;
+        for trivia in self.leading() {
+            write!(f, " leading {:?}", trivia.text)?;
+        }
+        for trivia in self.trailing() {
+            write!(f, " trailing {:?}", trivia.text)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl SyntaxElement {
+    pub fn full_range(&self) -> Range<usize> {
+        match self {
+            SyntaxElement::Node(node) => node.full_range(),
+            SyntaxElement::Token(token) => token.full_range(),
+        }
+    }
+}
@@ -0,0 +1,740 @@
+//! A typed view of the syntax tree: a wrapper type for each kind of
+//! node, with accessors for its parts. A part that is missing because
+//! of a syntax error is `None` (or left out of a list) rather than an
+//! error, so the view works on any file.
+
+use crate::syntax::green::SyntaxKind;
+use crate::syntax::red::{SyntaxElement, SyntaxNode, SyntaxToken};
+use crate::Token;
+
+pub trait AstNode: Sized {
+    /// Wraps `node`, if it is of the right kind.
+    fn cast(node: SyntaxNode) -> Option<Self>;
+
+    fn syntax(&self) -> &SyntaxNode;
+}
+
+macro_rules! ast_nodes {
+    ($($name:ident,)*) => {
+        $(
+            #[derive(Clone, Debug, PartialEq, Eq)]
+            pub struct $name(SyntaxNode);
+
+            impl AstNode for $name {
+                fn cast(node: SyntaxNode) -> Option<$name> {
+                    if node.kind() == SyntaxKind::$name {
+                        Some($name(node))
+                    } else {
+                        None
+                    }
+                }
+
+                fn syntax(&self) -> &SyntaxNode {
+                    &self.0
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! ast_enum {
+    ($(#[$attr:meta])* $name:ident { $($variant:ident,)* }) => {
+        $(#[$attr])*
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        pub enum $name {
+            $($variant($variant),)*
+        }
+
+        impl AstNode for $name {
+            fn cast(node: SyntaxNode) -> Option<$name> {
+                match node.kind() {
+                    $(SyntaxKind::$variant => Some($name::$variant($variant(node))),)*
+                    _ => None,
+                }
+            }
+
+            fn syntax(&self) -> &SyntaxNode {
+                match self {
+                    $($name::$variant(node) => node.syntax(),)*
+                }
+            }
+        }
+    };
+}
+
+ast_nodes! {
+    SourceFile,
+    Struct,
+    Enum,
+    Def,
+    Mod,
+    Use,
+    Visibility,
+    GenericParams,
+    Field,
+    Variant,
+    TupleFields,
+    StructFields,
+    ParamList,
+    SelfParam,
+    Param,
+    ReturnType,
+    Type,
+    TypeArgs,
+    Path,
+    Block,
+    Let,
+    WildcardPattern,
+    BindingPattern,
+    PathPattern,
+    LiteralPattern,
+    TuplePattern,
+    StructPattern,
+    FieldPattern,
+    LiteralExpr,
+    PathExpr,
+    SelfExpr,
+    ParenExpr,
+    CallExpr,
+    MethodCallExpr,
+    FieldExpr,
+    BinaryExpr,
+    UnaryExpr,
+    IfExpr,
+    MatchExpr,
+    MatchArm,
+    LoopExpr,
+    WhileExpr,
+    ForExpr,
+    BreakExpr,
+    ContinueExpr,
+    StructExpr,
+    ConstructField,
+    InterpolationExpr,
+    ArgList,
+}
+
+ast_enum! {
+    Item {
+        Struct,
+        Enum,
+        Def,
+        Mod,
+        Use,
+    }
+}
+
+ast_enum! {
+    Pattern {
+        WildcardPattern,
+        BindingPattern,
+        PathPattern,
+        LiteralPattern,
+        TuplePattern,
+        StructPattern,
+    }
+}
+
+ast_enum! {
+    Expr {
+        Block,
+        LiteralExpr,
+        PathExpr,
+        SelfExpr,
+        ParenExpr,
+        CallExpr,
+        MethodCallExpr,
+        FieldExpr,
+        BinaryExpr,
+        UnaryExpr,
+        IfExpr,
+        MatchExpr,
+        LoopExpr,
+        WhileExpr,
+        ForExpr,
+        BreakExpr,
+        ContinueExpr,
+        StructExpr,
+        InterpolationExpr,
+    }
+}
+
+/// A statement in a block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Stmt {
+    Let(Let),
+    Expr(Expr),
+}
+
+fn child<N: AstNode>(node: &SyntaxNode) -> Option<N> {
+    node.child_nodes().into_iter().filter_map(N::cast).next()
+}
+
+fn children<N: AstNode>(node: &SyntaxNode) -> Vec<N> {
+    node.child_nodes().into_iter().filter_map(N::cast).collect()
+}
+
+/// The first identifier directly within `node`.
+fn identifier(node: &SyntaxNode) -> Option<SyntaxToken> {
+    node.child_token(|token| match token {
+        Token::Identifier(_) => true,
+        _ => false,
+    })
+}
+
+/// The `own`, `borrow` or `share` directly within `node`.
+fn mode(node: &SyntaxNode) -> Option<SyntaxToken> {
+    node.child_token(|token| match token {
+        Token::KeywordOwn | Token::KeywordBorrow | Token::KeywordShare => true,
+        _ => false,
+    })
+}
+
+/// The label directly within `node`, like the `'outer` in `break 'outer`.
+fn label(node: &SyntaxNode) -> Option<SyntaxToken> {
+    node.child_token(|token| match token {
+        Token::Label(_) => true,
+        _ => false,
+    })
+}
+
+/// The `///` comments directly within `node`.
+fn docs(node: &SyntaxNode) -> Vec<SyntaxToken> {
+    node.child_tokens()
+        .into_iter()
+        .filter(|token| match token.token() {
+            Token::DocComment(_) => true,
+            _ => false,
+        })
+        .collect()
+}
+
+/// The first child node of type `N` after the token `after`.
+fn child_after<N: AstNode>(node: &SyntaxNode, after: Token) -> Option<N> {
+    node.children()
+        .into_iter()
+        .skip_while(|child| match child {
+            SyntaxElement::Token(token) => token.token() != after,
+            SyntaxElement::Node(_) => true,
+        })
+        .filter_map(|child| match child {
+            SyntaxElement::Node(node) => N::cast(node),
+            SyntaxElement::Token(_) => None,
+        })
+        .next()
+}
+
+impl SourceFile {
+    pub fn items(&self) -> Vec<Item> {
+        children(&self.0)
+    }
+}
+
+impl Item {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        match self {
+            Item::Use(u) => u.alias().or_else(|| u.path()?.segments().pop()),
+            _ => identifier(self.syntax()),
+        }
+    }
+
+    pub fn docs(&self) -> Vec<SyntaxToken> {
+        docs(self.syntax())
+    }
+
+    pub fn visibility(&self) -> Option<Visibility> {
+        child(self.syntax())
+    }
+}
+
+impl Struct {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        identifier(&self.0)
+    }
+
+    pub fn generic_params(&self) -> Option<GenericParams> {
+        child(&self.0)
+    }
+
+    pub fn fields(&self) -> Vec<Field> {
+        children(&self.0)
+    }
+
+    pub fn methods(&self) -> Vec<Def> {
+        children(&self.0)
+    }
+}
+
+impl Enum {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        identifier(&self.0)
+    }
+
+    pub fn generic_params(&self) -> Option<GenericParams> {
+        child(&self.0)
+    }
+
+    pub fn variants(&self) -> Vec<Variant> {
+        children(&self.0)
+    }
+}
+
+impl Variant {
+    pub fn docs(&self) -> Vec<SyntaxToken> {
+        docs(&self.0)
+    }
+
+    pub fn name(&self) -> Option<SyntaxToken> {
+        identifier(&self.0)
+    }
+
+    pub fn tuple_fields(&self) -> Option<TupleFields> {
+        child(&self.0)
+    }
+
+    pub fn struct_fields(&self) -> Option<StructFields> {
+        child(&self.0)
+    }
+}
+
+impl TupleFields {
+    pub fn types(&self) -> Vec<Type> {
+        children(&self.0)
+    }
+}
+
+impl StructFields {
+    pub fn fields(&self) -> Vec<Field> {
+        children(&self.0)
+    }
+}
+
+impl Field {
+    pub fn docs(&self) -> Vec<SyntaxToken> {
+        docs(&self.0)
+    }
+
+    pub fn name(&self) -> Option<SyntaxToken> {
+        identifier(&self.0)
+    }
+
+    pub fn ty(&self) -> Option<Type> {
+        child(&self.0)
+    }
+}
+
+impl Def {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        identifier(&self.0)
+    }
+
+    pub fn generic_params(&self) -> Option<GenericParams> {
+        child(&self.0)
+    }
+
+    pub fn param_list(&self) -> Option<ParamList> {
+        child(&self.0)
+    }
+
+    pub fn return_type(&self) -> Option<ReturnType> {
+        child(&self.0)
+    }
+
+    pub fn body(&self) -> Option<Block> {
+        child(&self.0)
+    }
+}
+
+impl ParamList {
+    pub fn self_param(&self) -> Option<SelfParam> {
+        child(&self.0)
+    }
+
+    pub fn params(&self) -> Vec<Param> {
+        children(&self.0)
+    }
+}
+
+impl SelfParam {
+    pub fn mode(&self) -> Option<SyntaxToken> {
+        mode(&self.0)
+    }
+}
+
+impl Param {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        identifier(&self.0)
+    }
+
+    pub fn ty(&self) -> Option<Type> {
+        child(&self.0)
+    }
+}
+
+impl ReturnType {
+    pub fn ty(&self) -> Option<Type> {
+        child(&self.0)
+    }
+}
+
+impl GenericParams {
+    pub fn names(&self) -> Vec<SyntaxToken> {
+        self.0
+            .child_tokens()
+            .into_iter()
+            .filter(|token| match token.token() {
+                Token::Identifier(_) => true,
+                _ => false,
+            })
+            .collect()
+    }
+}
+
+impl Type {
+    pub fn mode(&self) -> Option<SyntaxToken> {
+        mode(&self.0)
+    }
+
+    pub fn name(&self) -> Option<SyntaxToken> {
+        identifier(&self.0)
+    }
+
+    pub fn args(&self) -> Vec<Type> {
+        child::<TypeArgs>(&self.0).map_or(vec![], |args| children(&args.0))
+    }
+}
+
+impl Mod {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        identifier(&self.0)
+    }
+}
+
+impl Use {
+    pub fn path(&self) -> Option<Path> {
+        child(&self.0)
+    }
+
+    /// The name after `as`, if there is one.
+    pub fn alias(&self) -> Option<SyntaxToken> {
+        identifier(&self.0)
+    }
+}
+
+impl Path {
+    /// The identifiers of the path, like `Shape` and `Circle` in
+    /// `Shape::Circle`.
+    pub fn segments(&self) -> Vec<SyntaxToken> {
+        self.0
+            .child_tokens()
+            .into_iter()
+            .filter(|token| match token.token() {
+                Token::Identifier(_) => true,
+                _ => false,
+            })
+            .collect()
+    }
+}
+
+impl Block {
+    pub fn statements(&self) -> Vec<Stmt> {
+        self.0
+            .child_nodes()
+            .into_iter()
+            .filter_map(|node| match Let::cast(node.clone()) {
+                Some(l) => Some(Stmt::Let(l)),
+                None => Expr::cast(node).map(Stmt::Expr),
+            })
+            .collect()
+    }
+}
+
+impl Let {
+    pub fn pattern(&self) -> Option<Pattern> {
+        child(&self.0)
+    }
+
+    pub fn ty(&self) -> Option<Type> {
+        child(&self.0)
+    }
+
+    pub fn initializer(&self) -> Option<Expr> {
+        child_after(&self.0, Token::Equals)
+    }
+}
+
+impl BindingPattern {
+    pub fn mode(&self) -> Option<SyntaxToken> {
+        mode(&self.0)
+    }
+
+    pub fn name(&self) -> Option<SyntaxToken> {
+        identifier(&self.0)
+    }
+}
+
+impl PathPattern {
+    pub fn path(&self) -> Option<Path> {
+        child(&self.0)
+    }
+}
+
+impl LiteralPattern {
+    pub fn literal(&self) -> Option<SyntaxToken> {
+        self.0.child_tokens().into_iter().next()
+    }
+}
+
+impl TuplePattern {
+    pub fn path(&self) -> Option<Path> {
+        child(&self.0)
+    }
+
+    pub fn patterns(&self) -> Vec<Pattern> {
+        children(&self.0)
+    }
+}
+
+impl StructPattern {
+    pub fn path(&self) -> Option<Path> {
+        child(&self.0)
+    }
+
+    pub fn fields(&self) -> Vec<FieldPattern> {
+        children(&self.0)
+    }
+}
+
+impl FieldPattern {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        identifier(&self.0)
+    }
+
+    /// The pattern after the `:`, which is left out in shorthand like
+    /// the `w` in `Rect { w, h: 0 }`.
+    pub fn pattern(&self) -> Option<Pattern> {
+        child(&self.0)
+    }
+}
+
+impl LiteralExpr {
+    pub fn literal(&self) -> Option<SyntaxToken> {
+        self.0.child_tokens().into_iter().next()
+    }
+}
+
+impl PathExpr {
+    pub fn path(&self) -> Option<Path> {
+        child(&self.0)
+    }
+}
+
+impl ParenExpr {
+    pub fn expr(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl CallExpr {
+    pub fn path(&self) -> Option<Path> {
+        child(&self.0)
+    }
+
+    pub fn args(&self) -> Vec<Expr> {
+        child::<ArgList>(&self.0).map_or(vec![], |args| args.args())
+    }
+}
+
+impl ArgList {
+    pub fn args(&self) -> Vec<Expr> {
+        children(&self.0)
+    }
+}
+
+impl MethodCallExpr {
+    pub fn receiver(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+
+    pub fn name(&self) -> Option<SyntaxToken> {
+        identifier(&self.0)
+    }
+
+    pub fn args(&self) -> Vec<Expr> {
+        child::<ArgList>(&self.0).map_or(vec![], |args| args.args())
+    }
+}
+
+impl FieldExpr {
+    pub fn receiver(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+
+    pub fn name(&self) -> Option<SyntaxToken> {
+        identifier(&self.0)
+    }
+}
+
+impl BinaryExpr {
+    pub fn lhs(&self) -> Option<Expr> {
+        children(&self.0).into_iter().next()
+    }
+
+    pub fn rhs(&self) -> Option<Expr> {
+        children(&self.0).into_iter().nth(1)
+    }
+
+    /// The operator, which is the only token directly within the node.
+    pub fn op(&self) -> Option<SyntaxToken> {
+        self.0.child_tokens().into_iter().next()
+    }
+}
+
+impl UnaryExpr {
+    pub fn op(&self) -> Option<SyntaxToken> {
+        self.0.child_tokens().into_iter().next()
+    }
+
+    pub fn operand(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl IfExpr {
+    /// The pattern of an `if let`.
+    pub fn pattern(&self) -> Option<Pattern> {
+        child(&self.0)
+    }
+
+    pub fn condition(&self) -> Option<Expr> {
+        children(&self.0).into_iter().next()
+    }
+
+    pub fn then_branch(&self) -> Option<Block> {
+        children::<Expr>(&self.0)
+            .into_iter()
+            .nth(1)
+            .and_then(|expr| Block::cast(expr.syntax().clone()))
+    }
+
+    /// What comes after `else`: a block or another `if`.
+    pub fn else_branch(&self) -> Option<Expr> {
+        child_after(&self.0, Token::KeywordElse)
+    }
+}
+
+impl MatchExpr {
+    pub fn scrutinee(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+
+    pub fn arms(&self) -> Vec<MatchArm> {
+        children(&self.0)
+    }
+}
+
+impl MatchArm {
+    pub fn pattern(&self) -> Option<Pattern> {
+        child(&self.0)
+    }
+
+    pub fn body(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl LoopExpr {
+    pub fn label(&self) -> Option<SyntaxToken> {
+        label(&self.0)
+    }
+
+    pub fn body(&self) -> Option<Block> {
+        child(&self.0)
+    }
+}
+
+impl WhileExpr {
+    pub fn label(&self) -> Option<SyntaxToken> {
+        label(&self.0)
+    }
+
+    pub fn condition(&self) -> Option<Expr> {
+        child_after(&self.0, Token::KeywordWhile)
+    }
+
+    pub fn body(&self) -> Option<Block> {
+        self.0.child_nodes().into_iter().filter_map(Block::cast).last()
+    }
+}
+
+impl ForExpr {
+    pub fn label(&self) -> Option<SyntaxToken> {
+        label(&self.0)
+    }
+
+    pub fn pattern(&self) -> Option<Pattern> {
+        child(&self.0)
+    }
+
+    /// The first integer of the range, the `b` in `for a in b..c`.
+    pub fn start(&self) -> Option<Expr> {
+        child_after(&self.0, Token::KeywordIn)
+    }
+
+    /// The end of the range, which it stops short of.
+    pub fn end(&self) -> Option<Expr> {
+        child_after(&self.0, Token::DotDot)
+    }
+
+    pub fn body(&self) -> Option<Block> {
+        self.0.child_nodes().into_iter().filter_map(Block::cast).last()
+    }
+}
+
+impl BreakExpr {
+    pub fn label(&self) -> Option<SyntaxToken> {
+        label(&self.0)
+    }
+
+    pub fn value(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl ContinueExpr {
+    pub fn label(&self) -> Option<SyntaxToken> {
+        label(&self.0)
+    }
+}
+
+impl StructExpr {
+    pub fn path(&self) -> Option<Path> {
+        child(&self.0)
+    }
+
+    pub fn fields(&self) -> Vec<ConstructField> {
+        children(&self.0)
+    }
+}
+
+impl ConstructField {
+    pub fn name(&self) -> Option<SyntaxToken> {
+        identifier(&self.0)
+    }
+
+    pub fn ty(&self) -> Option<Type> {
+        child(&self.0)
+    }
+}
+
+impl InterpolationExpr {
+    /// The pieces of text between the expressions, each with its
+    /// delimiters, like `"a {{` and `}} c"`.
+    pub fn fragments(&self) -> Vec<SyntaxToken> {
+        self.0.child_tokens()
+    }
+
+    pub fn exprs(&self) -> Vec<Expr> {
+        children(&self.0)
+    }
+}
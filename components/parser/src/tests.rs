@@ -0,0 +1,425 @@
+use crate::ast::{self, Debuggable};
+use crate::{parse, ModuleTable};
+
+/// Parses `source`, which must have no syntax errors, and shows the
+/// module it parses to without any spans.
+fn parse_debug(source: &str) -> String {
+    let mut table = ModuleTable::new();
+    let (module, errors) = parse(source, &mut table, 0);
+    assert!(errors.is_empty(), "unexpected errors: {:?}", errors);
+    format!("{:?}", Debuggable::from(&module, &table))
+}
+
+#[test]
+fn lists_across_lines() {
+    let one_line = parse_debug(
+        "
+def add(a: Int, b: Int) -> Point {
+  sum(a, b)
+  Point { x: a, y: b }
+}
+",
+    );
+    let broken = parse_debug(
+        "
+def add(
+  a: Int,
+  b: Int,
+) -> Point {
+  sum(
+    a,
+    b,
+  )
+  Point {
+    x: a,
+    y: b,
+  }
+}
+",
+    );
+    assert_eq!(broken, one_line);
+}
+
+#[test]
+fn method_parameters_across_lines() {
+    let one_line = parse_debug(
+        "
+struct S {
+  def m(borrow self, a: Int) {
+    a
+  }
+}
+",
+    );
+    let broken = parse_debug(
+        "
+struct S {
+  def m(
+    borrow self,
+    a: Int,
+  ) {
+    a
+  }
+}
+",
+    );
+    assert_eq!(broken, one_line);
+}
+
+#[test]
+fn trailing_commas() {
+    let without = parse_debug("def f(a: Int) {\n  g(a)\n  Point { x: a }\n}\n");
+    let with = parse_debug("def f(a: Int,) {\n  g(a,)\n  Point { x: a, }\n}\n");
+    assert_eq!(with, without);
+}
+
+#[test]
+fn block_expressions() {
+    let mut table = ModuleTable::new();
+    let source = "
+def f() {
+  let x = {
+    1
+  }
+  {
+    x
+  }
+}
+";
+    let (module, errors) = parse(source, &mut table, 0);
+    assert!(errors.is_empty(), "unexpected errors: {:?}", errors);
+
+    let def = match &*module.items[0] {
+        ast::Item::Def(def) => def,
+        item => panic!("expected a def, found {:?}", item),
+    };
+    match &def.body.node.expressions[..] {
+        [ast::BlockItem::Decl(ast::Declaration::Let(ast::Let {
+            init: Some(ast::Expression::Block(_)),
+            ..
+        })), ast::BlockItem::Expr(ast::Expression::Block(_))] => {}
+        items => panic!("expected a let of a block and a block, found {:?}", items),
+    }
+}
+
+#[test]
+fn debug_output_shows_expressions() {
+    let debug = parse_debug(
+        "
+def f() {
+  'outer: while x { break 'outer g(1 - (2 - 3)) }
+  if let Some(y) = x { y } else { Point { y, } }
+}
+",
+    );
+    assert!(debug.contains("'outer: while x"), "{}", debug);
+    assert!(debug.contains("break 'outer g((1 - (2 - 3)))"), "{}", debug);
+    assert!(debug.contains("if let Some[y] = x"), "{}", debug);
+    assert!(debug.contains("Point { y, }"), "{}", debug);
+}
+
+#[test]
+fn syntax_tree_round_trips() {
+    use crate::syntax::{parse_syntax, AstNode};
+
+    let sources = [
+        include_str!("../../../samples/challenge1.lark"),
+        "",
+        "\n\n  // only a comment",
+        "/// Docs.\r\n/// More docs.\r\npub struct Point<T> {\r\n  x: own T, // the x\r\n  y: T,\r\n}\r\n",
+        "def main() {\n\t/* a /* nested */ block\n comment */ let x = -1 + 2 * 3\n  print(\"{{x}} and {{ x + 1 }}!\")\n  'outer: loop { break 'outer }\n}",
+        "enum E { A(i32,), B { x: i32 }, C }\ndef f(e: E,) -> i32 {\n  match e {\n    E::A(n) => n\n    E::B { x: 0 } => 1,\n    _ => 2,\n  }\n}\n",
+        "def broken( {\n  let = )\n  # ? \"unterminated\n}\n}}\nstruct S { , }\n/* unterminated",
+        "mod shapes\nuse shapes::Shape as S\ndef f() { if a { b } else if c { d } else { while x { } } }",
+    ];
+
+    for source in sources.iter() {
+        let mut table = ModuleTable::new();
+        let file = parse_syntax(source, &mut table);
+        assert_eq!(file.syntax().to_string(), *source);
+
+        let mut offset = 0;
+        for token in file.syntax().tokens() {
+            assert_eq!(token.full_range().start, offset, "{:?}", file.syntax());
+            assert_eq!(&source[token.range()], token.text());
+            offset = token.full_range().end;
+        }
+        assert_eq!(offset, source.len());
+    }
+}
+
+#[test]
+fn syntax_tree_typed_view() {
+    use crate::syntax::{parse_syntax, AstNode, Expr, Item, Stmt, TriviaKind};
+    use crate::Token;
+
+    let source = "/// A point.
+pub struct Point {
+  x: i32, // across
+  y: i32,
+
+  def sum(borrow self) -> i32 {
+    self.x + self.y * 2
+  }
+}
+
+// Unused.
+def main() {
+  let p = Point { x, y }
+  print(\"{{p.sum()}}\")
+}
+";
+    let mut table = ModuleTable::new();
+    let file = parse_syntax(source, &mut table);
+    let items = file.items();
+    assert_eq!(items.len(), 2);
+
+    let point = match &items[0] {
+        Item::Struct(s) => s,
+        item => panic!("unexpected item: {:?}", item),
+    };
+    assert_eq!(items[0].name().unwrap().text(), "Point");
+    assert_eq!(items[0].docs()[0].text(), "/// A point.");
+    assert!(items[0].visibility().is_some());
+    let fields: Vec<_> = point
+        .fields()
+        .iter()
+        .map(|field| field.name().unwrap().text().to_string())
+        .collect();
+    assert_eq!(fields, vec!["x", "y"]);
+
+    let comma = point
+        .syntax()
+        .child_token(|token| token == Token::Comma)
+        .unwrap();
+    assert_eq!(comma.trailing()[1].kind, TriviaKind::LineComment);
+    assert_eq!(comma.trailing()[1].text, "// across");
+
+    let sum = &point.methods()[0];
+    assert_eq!(sum.name().unwrap().text(), "sum");
+    assert!(sum.param_list().unwrap().self_param().is_some());
+    assert_eq!(
+        sum.return_type()
+            .unwrap()
+            .ty()
+            .unwrap()
+            .name()
+            .unwrap()
+            .text(),
+        "i32"
+    );
+    match &sum.body().unwrap().statements()[..] {
+        [Stmt::Expr(Expr::BinaryExpr(add))] => {
+            assert_eq!(add.op().unwrap().text(), "+");
+            assert_eq!(add.lhs().unwrap().syntax().to_string(), "    self.x ");
+            assert_eq!(&source[add.rhs().unwrap().syntax().range()], "self.y * 2");
+        }
+        statements => panic!("unexpected statements: {:?}", statements),
+    }
+
+    let main = &items[1];
+    assert_eq!(main.name().unwrap().text(), "main");
+    let line = file
+        .syntax()
+        .token_at(source.find("// Unused.").unwrap())
+        .unwrap();
+    assert_eq!(line.token(), Token::Newline);
+    assert_eq!(line.leading()[0].text, "// Unused.");
+    assert_eq!(
+        main.syntax().range(),
+        source.find("def main").unwrap()..source.len() - 1
+    );
+}
+
+/// The items of `module`, each as its kind and name followed by the
+/// names of its fields and methods, variants or parameters.
+fn ast_outline(module: &ast::Module, table: &ModuleTable) -> Vec<String> {
+    let name = |id| table.lookup(id).to_string();
+    let names = |fields: &[ast::Field]| {
+        fields
+            .iter()
+            .map(|field| name(field.name.node))
+            .collect::<Vec<_>>()
+    };
+    module
+        .items
+        .iter()
+        .map(|item| {
+            let (kind, members) = match &**item {
+                ast::Item::Struct(s) => {
+                    let mut members = names(&s.fields);
+                    members.extend(s.methods.iter().map(|method| name(method.name.node)));
+                    ("struct", members)
+                }
+                ast::Item::Enum(e) => (
+                    "enum",
+                    e.variants
+                        .iter()
+                        .map(|variant| name(variant.name.node))
+                        .collect(),
+                ),
+                ast::Item::Def(d) => ("def", names(&d.parameters)),
+                ast::Item::Mod(_) => ("mod", vec![]),
+                ast::Item::Use(_) => ("use", vec![]),
+            };
+            format!("{} {} {:?}", kind, name(item.name()), members)
+        })
+        .collect()
+}
+
+/// The same as `ast_outline`, but read from a syntax tree.
+fn syntax_outline(file: &crate::syntax::SourceFile) -> Vec<String> {
+    use crate::syntax::{Item, SyntaxToken};
+
+    fn names(tokens: impl Iterator<Item = Option<SyntaxToken>>) -> Vec<String> {
+        tokens
+            .map(|token| token.map_or(String::new(), |token| token.text().to_string()))
+            .collect()
+    }
+
+    file.items()
+        .iter()
+        .map(|item| {
+            let (kind, members) = match item {
+                Item::Struct(s) => {
+                    let fields = s.fields().into_iter().map(|field| field.name());
+                    let methods = s.methods().into_iter().map(|method| method.name());
+                    ("struct", names(fields.chain(methods)))
+                }
+                Item::Enum(e) => (
+                    "enum",
+                    names(e.variants().into_iter().map(|variant| variant.name())),
+                ),
+                Item::Def(d) => (
+                    "def",
+                    names(
+                        d.param_list()
+                            .map_or(vec![], |list| list.params())
+                            .into_iter()
+                            .map(|param| param.name()),
+                    ),
+                ),
+                Item::Mod(_) => ("mod", vec![]),
+                Item::Use(_) => ("use", vec![]),
+            };
+            let name = item.name().map_or(String::new(), |name| name.text().to_string());
+            format!("{} {} {:?}", kind, name, members)
+        })
+        .collect()
+}
+
+/// `syntax/parse.rs` mirrors `grammar.lalrpop` by hand, so check that
+/// the two agree on every sample: both accept it, with the same items,
+/// and both reject the same broken files.
+#[test]
+fn syntax_tree_agrees_with_grammar() {
+    use crate::syntax::{parse_syntax, AstNode, SyntaxKind, SyntaxNode};
+    use std::fs;
+
+    fn has_error(node: &SyntaxNode) -> bool {
+        node.kind() == SyntaxKind::Error || node.child_nodes().iter().any(has_error)
+    }
+
+    let samples = concat!(env!("CARGO_MANIFEST_DIR"), "/../../samples");
+    let mut paths = vec![];
+    for dir in [samples.to_string(), format!("{}/fmt", samples)].iter() {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().map_or(false, |extension| extension == "lark") {
+                paths.push(path);
+            }
+        }
+    }
+    assert!(paths.len() >= 9);
+
+    for path in paths {
+        let source = fs::read_to_string(&path).unwrap();
+        let mut table = ModuleTable::new();
+        let (module, errors) = parse(&source[..], &mut table, 0);
+        assert!(errors.is_empty(), "{}: {:?}", path.display(), errors);
+        let file = parse_syntax(&source, &mut table);
+        assert!(!has_error(file.syntax()), "{}", path.display());
+        assert_eq!(
+            syntax_outline(&file),
+            ast_outline(&module, &table),
+            "{}",
+            path.display()
+        );
+    }
+
+    let broken = [
+        "def f( {\n}\n",
+        "def f() {\n  )\n}\n",
+        "struct S { , }\n",
+        "enum E { A(, }\n",
+        "def f() {\n  let = 1\n}\n",
+        "def f() {\n  1 +\n}\n",
+        "use a::\n",
+        "def f() -> {\n}\n",
+        "def f() {\n  let x: = 1\n}\n",
+        "def f() {\n  a.\n}\n",
+        "struct S {\n  x:\n}\n",
+        "mod\n",
+        "def f() {\n  match x {\n    => 1\n  }\n}\n",
+    ];
+    for source in broken.iter() {
+        let mut table = ModuleTable::new();
+        let (_, errors) = parse(*source, &mut table, 0);
+        assert!(!errors.is_empty(), "{:?}", source);
+        let file = parse_syntax(source, &mut table);
+        assert!(has_error(file.syntax()), "{:?}", source);
+    }
+}
+
+#[test]
+fn format_golden_files() {
+    use std::fs;
+
+    let mut checked = 0;
+    for entry in fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/../../samples/fmt")).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_str().unwrap().to_string();
+        if !name.ends_with(".in.lark") {
+            continue;
+        }
+
+        let input = fs::read_to_string(&path).unwrap();
+        let expected = path.with_file_name(name.replace(".in.", ".out."));
+        let expected = fs::read_to_string(expected).unwrap();
+        let formatted = crate::format::format(&input).unwrap();
+        assert_eq!(formatted, expected, "formatting {}", name);
+        assert_eq!(
+            crate::format::format(&formatted).unwrap(),
+            formatted,
+            "reformatting {}",
+            name
+        );
+        assert_eq!(
+            parse_debug(&formatted),
+            parse_debug(&input),
+            "parsing {}",
+            name
+        );
+        checked += 1;
+    }
+    assert!(checked >= 4);
+}
+
+#[test]
+fn format_ranges_and_errors() {
+    use crate::format::{format, format_range};
+
+    assert!(format("def f( {\n}\n").is_err());
+    assert!(format("def f() {\n  )\n}\n").is_err());
+    assert_eq!(format("").unwrap(), "");
+
+    let source = "def f(){\n    1\n}\n\ndef g(){\n    2\n}\n";
+    let edits = format_range(source, 21..22).unwrap();
+    assert_eq!(edits.len(), 1);
+    let (range, text) = &edits[0];
+    assert_eq!(&source[range.clone()], "def g(){\n    2\n}");
+    assert_eq!(text, "def g() {\n  2\n}");
+
+    let edits = format_range("def f() {\n  1\n}\n", 0..5).unwrap();
+    assert_eq!(edits, vec![]);
+}
@@ -18,29 +18,63 @@ pub enum Token {
     Comma,
     Equals,
     ThinArrow,
+    FatArrow,
     DoubleColon,
     Period,
+    DotDot,
     OpAdd,
     OpSub,
     OpMul,
     OpDiv,
+    OpRem,
+    OpEqual,
+    OpNotEqual,
+    OpLess,
+    OpLessEqual,
+    OpGreater,
+    OpGreaterEqual,
+    OpAnd,
+    OpOr,
+    OpNot,
     KeywordFor,
     KeywordLoop,
     KeywordWhile,
+    KeywordIn,
+    KeywordBreak,
+    KeywordContinue,
     KeywordDef,
     KeywordLet,
     KeywordStruct,
+    KeywordEnum,
+    KeywordMod,
+    KeywordUse,
+    KeywordPub,
+    KeywordAs,
+    KeywordMatch,
     KeywordIf,
     KeywordElse,
     KeywordOwn,
     KeywordBorrow,
+    KeywordShare,
     KeywordSelf,
+    KeywordTrue,
+    KeywordFalse,
     Identifier(StringId),
+    Label(StringId),
     StringLiteral(StringId),
+    IntegerLiteral(StringId),
+    FloatLiteral(StringId),
     StringFragment(StringId),
     EndString(StringId),
+
+    /// A `///` comment, up to but not including the newline.
+    DocComment(StringId),
     Newline,
     Unimplemented,
+
+    /// The end of the input. The parser never sees this; it is the last
+    /// token of a syntax tree, holding the trivia at the end of the file.
+    EndOfFile,
 }
 
 impl fmt::Display for Token {
@@ -50,6 +84,24 @@ impl fmt::Display for Token {
 }
 
 impl Token {
+    /// The same token, with `f` applied to its text if it has any.
+    /// Moves a token from the table it was interned in to another.
+    pub fn map_string_id(self, f: impl FnOnce(StringId) -> StringId) -> Token {
+        use self::Token::*;
+
+        match self {
+            Identifier(id) => Identifier(f(id)),
+            Label(id) => Label(f(id)),
+            StringLiteral(id) => StringLiteral(f(id)),
+            IntegerLiteral(id) => IntegerLiteral(f(id)),
+            FloatLiteral(id) => FloatLiteral(f(id)),
+            StringFragment(id) => StringFragment(f(id)),
+            EndString(id) => EndString(f(id)),
+            DocComment(id) => DocComment(f(id)),
+            token => token,
+        }
+    }
+
     crate fn source(&self, table: &'table ModuleTable) -> Cow<'table, str> {
         use self::Token::*;
 
@@ -64,31 +116,60 @@ impl Token {
             Comma => ",",
             Equals => "=",
             ThinArrow => "->",
+            FatArrow => "=>",
             DoubleColon => "::",
             Period => ".",
+            DotDot => "..",
             OpAdd => "+",
             OpSub => "-",
             OpMul => "*",
             OpDiv => "/",
+            OpRem => "%",
+            OpEqual => "==",
+            OpNotEqual => "!=",
+            OpLess => "<",
+            OpLessEqual => "<=",
+            OpGreater => ">",
+            OpGreaterEqual => ">=",
+            OpAnd => "&&",
+            OpOr => "||",
+            OpNot => "!",
             KeywordFor => "for",
             KeywordLoop => "loop",
             KeywordWhile => "while",
+            KeywordIn => "in",
+            KeywordBreak => "break",
+            KeywordContinue => "continue",
             KeywordDef => "def",
             KeywordLet => "let",
             KeywordStruct => "struct",
+            KeywordEnum => "enum",
+            KeywordMod => "mod",
+            KeywordUse => "use",
+            KeywordPub => "pub",
+            KeywordAs => "as",
+            KeywordMatch => "match",
             KeywordIf => "if",
             KeywordElse => "else",
             KeywordOwn => "own",
             KeywordBorrow => "borrow",
+            KeywordShare => "share",
             KeywordSelf => "self",
+            KeywordTrue => "true",
+            KeywordFalse => "false",
             Identifier(id) => table.lookup(*id),
+            Label(id) => table.lookup(*id),
             StringLiteral(id) => return Cow::Owned(format!("String({})", table.lookup(*id))),
+            IntegerLiteral(id) => return Cow::Owned(format!("Integer({})", table.lookup(*id))),
+            FloatLiteral(id) => return Cow::Owned(format!("Float({})", table.lookup(*id))),
             StringFragment(id) => {
                 return Cow::Owned(format!("StringFragment({})", table.lookup(*id)))
             }
             EndString(id) => "closequote",
+            DocComment(id) => "doc comment",
             Newline => "newline",
             Unimplemented => "unimplemented",
+            EndOfFile => "end of file",
         };
 
         Cow::Borrowed(result)
@@ -18,12 +18,36 @@ pub type Tokenizer<'table> = GenericTokenizer<'table, LexerState>;
 pub enum LexerState {
     Top,
     Integer,
+    HexInteger,
+    BinaryInteger,
+    Fraction,
+    Exponent,
     StartStringLiteral,
+
+    /// Within a string literal, before any `{{`.
     StringLiteral,
+
+    /// Within a string literal, after the end of an interpolated
+    /// expression.
+    StringTemplate,
+
+    /// Within the `{{ .. }}` of a string literal; like `Top`, except
+    /// that `}}` returns to the string.
+    Interpolation,
     OpenCurly,
     Whitespace,
     StartIdent,
     ContinueIdent,
+    Label,
+
+    /// Within a `//` comment, which is skipped.
+    LineComment,
+
+    /// Within a `///` comment, which becomes a `DocComment` token.
+    DocComment,
+
+    /// Within a `/* .. */` comment, nested this many levels deep.
+    BlockComment(u32),
 }
 
 impl LexerDelegateTrait for LexerState {
@@ -39,10 +63,26 @@ impl LexerDelegateTrait for LexerState {
         rest: &'input str,
     ) -> Result<LexerNext<Self>, ParseError> {
         let out = match self {
-            LexerState::Top => match c {
+            LexerState::Interpolation if rest.starts_with("}}") => {
+                LexerNext::PopState(LexerAccumulate::Continue(LexerAction::Consume(2)))
+            }
+
+            LexerState::Top | LexerState::Interpolation => match c {
                 None => LexerNext::EOF,
                 Some(c) => {
-                    if let Some((tok, size)) = KEYWORDS.match_token(rest) {
+                    if rest.starts_with("///") && !rest.starts_with("////") {
+                        LexerNext::PushState(LexerAccumulate::Begin, LexerState::DocComment)
+                    } else if rest.starts_with("//") {
+                        LexerNext::PushState(LexerAccumulate::Begin, LexerState::LineComment)
+                    } else if rest.starts_with("/*") {
+                        LexerNext::PushState(
+                            LexerAccumulate::Continue(LexerAction::Consume(2)),
+                            LexerState::BlockComment(1),
+                        )
+                    } else if let Some((tok, size)) = KEYWORDS
+                        .match_token(rest)
+                        .filter(|&(_, size)| !continues_ident(&rest[size as usize..]))
+                    {
                         LexerNext::Remain(LexerAccumulate::Emit {
                             before: Some(LexerAction::Consume(size)),
                             after: None,
@@ -55,16 +95,24 @@ impl LexerDelegateTrait for LexerState {
                             token: LexerToken::Fixed(tok),
                         })
                     } else if c.is_digit(10) {
-                        LexerNext::Transition(LexerAccumulate::Nothing, LexerState::Integer)
+                        if rest.starts_with("0x") && starts_with_digit(&rest[2..], 16) {
+                            LexerState::HexInteger.consume_prefix()
+                        } else if rest.starts_with("0b") && starts_with_digit(&rest[2..], 2) {
+                            LexerState::BinaryInteger.consume_prefix()
+                        } else {
+                            LexerNext::PushState(LexerAccumulate::Begin, LexerState::Integer)
+                        }
                     } else if c == '"' {
-                        LexerNext::Transition(
-                            LexerAccumulate::Begin,
-                            LexerState::StartStringLiteral,
+                        LexerNext::PushState(LexerAccumulate::Begin, LexerState::StartStringLiteral)
+                    } else if c == '\'' && starts_with_ident(&rest[1..]) {
+                        LexerNext::PushState(
+                            LexerAccumulate::Continue(LexerAction::Consume(1)),
+                            LexerState::Label,
                         )
                     } else if c.is_whitespace() {
-                        LexerNext::Transition(LexerAccumulate::Begin, LexerState::Whitespace)
+                        LexerNext::PushState(LexerAccumulate::Begin, LexerState::Whitespace)
                     } else if UnicodeXID::is_xid_start(c) {
-                        LexerNext::Transition(LexerAccumulate::Begin, LexerState::StartIdent)
+                        LexerNext::PushState(LexerAccumulate::Begin, LexerState::StartIdent)
                     } else {
                         LexerNext::Error(Some(c))
                     }
@@ -75,36 +123,61 @@ impl LexerDelegateTrait for LexerState {
                 None => LexerNext::EOF,
                 Some(c) => {
                     if c == '\n' {
-                        LexerNext::Transition(
-                            LexerAccumulate::Skip(LexerAction::Reconsume),
-                            LexerState::Top,
-                        )
+                        LexerNext::PopState(LexerAccumulate::Skip(LexerAction::Reconsume))
                     } else if c.is_whitespace() {
                         LexerNext::Remain(LexerAccumulate::Continue(LexerAction::Consume(1)))
                     } else {
-                        LexerNext::Transition(
-                            LexerAccumulate::Skip(LexerAction::Reconsume),
-                            LexerState::Top,
-                        )
+                        LexerNext::PopState(LexerAccumulate::Skip(LexerAction::Reconsume))
                     }
                 }
             },
 
-            LexerState::StringLiteral => match c {
+            // A string literal is a single `StringLiteral` token,
+            // unless it contains interpolated expressions, like
+            // `"a {{b}} c"`. Those are split into a `StringFragment`
+            // for the text up to each `{{`, the tokens of each
+            // expression, and an `EndString` for the rest.
+            LexerState::StringLiteral | LexerState::StringTemplate => match c {
                 None => LexerNext::EOF,
 
-                Some(c) => match c {
-                    '"' => LexerNext::Transition(
+                Some('"') => {
+                    let token: fn(StringId) -> Token = match self {
+                        LexerState::StringLiteral => Token::StringLiteral,
+                        _ => Token::EndString,
+                    };
+                    LexerNext::PopState(LexerAccumulate::Emit {
+                        before: Some(LexerAction::Consume(1)),
+                        after: None,
+                        token: LexerToken::Dynamic(token),
+                    })
+                }
+
+                Some('{') if rest.starts_with("{{") => match self {
+                    LexerState::StringLiteral => {
+                        LexerNext::Transition(LexerAccumulate::Nothing, LexerState::StringTemplate)
+                    }
+                    _ => LexerNext::PushState(
                         LexerAccumulate::Emit {
-                            before: Some(LexerAction::Consume(1)),
+                            before: Some(LexerAction::Consume(2)),
                             after: None,
-                            token: LexerToken::Dynamic(Token::StringLiteral),
+                            token: LexerToken::Dynamic(Token::StringFragment),
                         },
-                        LexerState::Top,
+                        LexerState::Interpolation,
                     ),
-
-                    other => LexerNext::Remain(LexerAccumulate::Continue(LexerAction::Consume(1))),
                 },
+
+                // An escape, like `\{`, is never the end of the string
+                // or the start of an expression.
+                Some('\\') => {
+                    let escaped = rest[1..].chars().next().map_or(0, |c| c.len_utf8());
+                    LexerNext::Remain(LexerAccumulate::Continue(LexerAction::Consume(
+                        1 + escaped as u32,
+                    )))
+                }
+
+                Some(c) => LexerNext::Remain(LexerAccumulate::Continue(LexerAction::Consume(
+                    c.len_utf8() as u32,
+                ))),
             },
 
             LexerState::StartStringLiteral => LexerNext::Transition(
@@ -114,7 +187,7 @@ impl LexerDelegateTrait for LexerState {
 
             LexerState::StartIdent => match c {
                 None => {
-                    LexerNext::Transition(LexerAccumulate::emit_dynamic(tk_id), LexerState::Top)
+                    LexerNext::PopState(LexerAccumulate::emit_dynamic(tk_id))
                 }
                 Some(c) => {
                     if UnicodeXID::is_xid_continue(c) {
@@ -123,47 +196,120 @@ impl LexerDelegateTrait for LexerState {
                             LexerState::ContinueIdent,
                         )
                     } else {
-                        LexerNext::Transition(LexerAccumulate::emit_dynamic(tk_id), LexerState::Top)
+                        LexerNext::PopState(LexerAccumulate::emit_dynamic(tk_id))
                     }
                 }
             },
 
             LexerState::ContinueIdent => match c {
                 None => {
-                    LexerNext::Transition(LexerAccumulate::emit_dynamic(tk_id), LexerState::Top)
+                    LexerNext::PopState(LexerAccumulate::emit_dynamic(tk_id))
                 }
                 Some(c) => {
                     if UnicodeXID::is_xid_continue(c) {
                         LexerNext::Remain(LexerAccumulate::Continue(LexerAction::Consume(1)))
                     } else {
-                        LexerNext::Transition(LexerAccumulate::emit_dynamic(tk_id), LexerState::Top)
+                        LexerNext::PopState(LexerAccumulate::emit_dynamic(tk_id))
                     }
                 }
             },
 
-            // LexerState::Integer => match c {
-            //     None => LexerNext::emit_current(0, tk_int, LexerState::Top),
-            //     Some(c) => {
-            //         if c.is_digit(10) {
-            //             LexerNext::consume()
-            //         } else if c == '.' {
-            //             LexerNext::transition_to(LexerState::Decimal)
-            //         } else {
-            //             LexerNext::emit(tk_int, LexerState::Top).reconsume()
-            //         }
-            //     }
-            // },
-
-            // LexerState::Decimal => match c {
-            //     None => LexerNext::emit_current(0, tk_float, LexerState::Top),
-            //     Some(c) => {
-            //         if c.is_digit(10) {
-            //             LexerNext::consume()
-            //         } else {
-            //             LexerNext::emit(tk_float, LexerState::Top).reconsume()
-            //         }
-            //     }
-            // },
+            // Comments run up to the end of the line; the newline
+            // itself is still a token.
+            LexerState::LineComment => match c {
+                None | Some('\n') => {
+                    LexerNext::PopState(LexerAccumulate::Skip(LexerAction::Reconsume))
+                }
+                Some(c) => LexerNext::Remain(LexerAccumulate::Continue(LexerAction::Consume(
+                    c.len_utf8() as u32,
+                ))),
+            },
+
+            LexerState::DocComment => match c {
+                None | Some('\n') => LexerNext::PopState(LexerAccumulate::emit_dynamic(tk_doc)),
+                Some(c) => LexerNext::Remain(LexerAccumulate::Continue(LexerAction::Consume(
+                    c.len_utf8() as u32,
+                ))),
+            },
+
+            LexerState::BlockComment(depth) => match c {
+                None => LexerNext::Error(None),
+                Some('/') if rest.starts_with("/*") => LexerNext::Transition(
+                    LexerAccumulate::Continue(LexerAction::Consume(2)),
+                    LexerState::BlockComment(depth + 1),
+                ),
+                Some('*') if rest.starts_with("*/") => match depth {
+                    1 => LexerNext::PopState(LexerAccumulate::Skip(LexerAction::Consume(2))),
+                    _ => LexerNext::Transition(
+                        LexerAccumulate::Continue(LexerAction::Consume(2)),
+                        LexerState::BlockComment(depth - 1),
+                    ),
+                },
+                Some(c) => LexerNext::Remain(LexerAccumulate::Continue(LexerAction::Consume(
+                    c.len_utf8() as u32,
+                ))),
+            },
+
+            LexerState::Label => match c {
+                Some(c) if UnicodeXID::is_xid_continue(c) => LexerNext::Remain(
+                    LexerAccumulate::Continue(LexerAction::Consume(c.len_utf8() as u32)),
+                ),
+                _ => LexerNext::PopState(LexerAccumulate::emit_dynamic(tk_label)),
+            },
+
+            LexerState::Integer => match c {
+                Some(c) if c.is_digit(10) || c == '_' => {
+                    LexerNext::Remain(LexerAccumulate::Continue(LexerAction::Consume(1)))
+                }
+                Some('.') if starts_with_digit(&rest[1..], 10) => LexerNext::Transition(
+                    LexerAccumulate::Continue(LexerAction::Consume(1)),
+                    LexerState::Fraction,
+                ),
+                Some('e') | Some('E') => match exponent_prefix(rest) {
+                    Some(size) => LexerNext::Transition(
+                        LexerAccumulate::Continue(LexerAction::Consume(size)),
+                        LexerState::Exponent,
+                    ),
+                    None => LexerNext::Error(c),
+                },
+                c => LexerState::Integer.end_number(c, tk_integer),
+            },
+
+            LexerState::HexInteger => match c {
+                Some(c) if c.is_digit(16) || c == '_' => {
+                    LexerNext::Remain(LexerAccumulate::Continue(LexerAction::Consume(1)))
+                }
+                c => LexerState::HexInteger.end_number(c, tk_integer),
+            },
+
+            LexerState::BinaryInteger => match c {
+                Some(c) if c.is_digit(2) || c == '_' => {
+                    LexerNext::Remain(LexerAccumulate::Continue(LexerAction::Consume(1)))
+                }
+                c => LexerState::BinaryInteger.end_number(c, tk_integer),
+            },
+
+            LexerState::Fraction => match c {
+                Some(c) if c.is_digit(10) || c == '_' => {
+                    LexerNext::Remain(LexerAccumulate::Continue(LexerAction::Consume(1)))
+                }
+                Some('e') | Some('E') => match exponent_prefix(rest) {
+                    Some(size) => LexerNext::Transition(
+                        LexerAccumulate::Continue(LexerAction::Consume(size)),
+                        LexerState::Exponent,
+                    ),
+                    None => LexerNext::Error(c),
+                },
+                c => LexerState::Fraction.end_number(c, tk_float),
+            },
+
+            LexerState::Exponent => match c {
+                Some(c) if c.is_digit(10) || c == '_' => {
+                    LexerNext::Remain(LexerAccumulate::Continue(LexerAction::Consume(1)))
+                }
+                c => LexerState::Exponent.end_number(c, tk_float),
+            },
+
             other => unimplemented!("{:?}", other),
         };
 
@@ -171,6 +317,80 @@ impl LexerDelegateTrait for LexerState {
     }
 }
 
+impl LexerState {
+    /// Begins a number whose two-character radix prefix, like `0x`, is
+    /// at the start of the input.
+    fn consume_prefix(self) -> LexerNext<LexerState> {
+        LexerNext::PushState(LexerAccumulate::Continue(LexerAction::Consume(2)), self)
+    }
+
+    /// Ends the number being lexed in this state, now that `c` does not
+    /// continue it. A letter or digit directly after a number, like the
+    /// `z` in `12z`, is an error rather than the start of a new token.
+    fn end_number(
+        self,
+        c: Option<char>,
+        token: fn(StringId) -> Token,
+    ) -> LexerNext<LexerState> {
+        match c {
+            Some(c) if UnicodeXID::is_xid_continue(c) => LexerNext::Error(Some(c)),
+            _ => LexerNext::PopState(LexerAccumulate::emit_dynamic(token)),
+        }
+    }
+}
+
+/// True if `rest` starts with a character that could continue an
+/// identifier, so that a keyword just before it is really part of a
+/// longer identifier (like the `let` in `letter`).
+fn continues_ident(rest: &str) -> bool {
+    rest.chars()
+        .next()
+        .map_or(false, |c| UnicodeXID::is_xid_continue(c))
+}
+
+/// True if `rest` starts with a character that can begin an
+/// identifier, like the `o` in `'outer`.
+fn starts_with_ident(rest: &str) -> bool {
+    rest.chars()
+        .next()
+        .map_or(false, |c| UnicodeXID::is_xid_start(c))
+}
+
+fn starts_with_digit(rest: &str, radix: u32) -> bool {
+    rest.chars().next().map_or(false, |c| c.is_digit(radix))
+}
+
+/// If `rest` starts with an exponent marker like `e`, `e+` or `E-`
+/// followed by a digit, the length of the marker.
+fn exponent_prefix(rest: &str) -> Option<u32> {
+    let after_e = &rest[1..];
+    if starts_with_digit(after_e, 10) {
+        Some(1)
+    } else if (after_e.starts_with('+') || after_e.starts_with('-'))
+        && starts_with_digit(&after_e[1..], 10)
+    {
+        Some(2)
+    } else {
+        None
+    }
+}
+
 fn tk_id(token: StringId) -> Token {
     Token::Identifier(token)
 }
+
+fn tk_label(token: StringId) -> Token {
+    Token::Label(token)
+}
+
+fn tk_integer(token: StringId) -> Token {
+    Token::IntegerLiteral(token)
+}
+
+fn tk_float(token: StringId) -> Token {
+    Token::FloatLiteral(token)
+}
+
+fn tk_doc(token: StringId) -> Token {
+    Token::DocComment(token)
+}
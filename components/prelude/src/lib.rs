@@ -0,0 +1,90 @@
+//! The built-in types and functions that are in scope in every Lark
+//! program. Each layer of the compiler refers to these entries by
+//! name, so that they all agree on what the prelude contains.
+
+/// A type that is built into the language.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BuiltinType {
+    Unit,
+    Bool,
+    I32,
+    U32,
+    F64,
+    String,
+}
+
+impl BuiltinType {
+    /// All the built-in types, in a fixed order.
+    pub const ALL: &'static [BuiltinType] = &[
+        BuiltinType::Unit,
+        BuiltinType::Bool,
+        BuiltinType::I32,
+        BuiltinType::U32,
+        BuiltinType::F64,
+        BuiltinType::String,
+    ];
+
+    /// The name by which Lark code refers to this type.
+    pub fn name(self) -> &'static str {
+        match self {
+            BuiltinType::Unit => "()",
+            BuiltinType::Bool => "bool",
+            BuiltinType::I32 => "i32",
+            BuiltinType::U32 => "u32",
+            BuiltinType::F64 => "f64",
+            BuiltinType::String => "String",
+        }
+    }
+
+    /// The built-in type that Lark code calls `name`, if any.
+    pub fn from_name(name: &str) -> Option<BuiltinType> {
+        BuiltinType::ALL
+            .iter()
+            .cloned()
+            .find(|builtin| builtin.name() == name)
+    }
+}
+
+/// A function that is built into the language.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BuiltinFn {
+    /// `print(s)`: writes `s` and a newline to stdout.
+    Print,
+
+    /// Produces the string for an interpolated string literal. Its
+    /// first argument is the format string, with `{}` standing for each
+    /// of the remaining arguments and `{{` and `}}` for literal braces.
+    /// It cannot be called by name.
+    StringInterpolate,
+}
+
+impl BuiltinFn {
+    /// All the built-in functions, in a fixed order.
+    pub const ALL: &'static [BuiltinFn] = &[BuiltinFn::Print, BuiltinFn::StringInterpolate];
+
+    /// The name by which Lark code calls this function, if it can.
+    pub fn name(self) -> Option<&'static str> {
+        match self {
+            BuiltinFn::Print => Some("print"),
+            BuiltinFn::StringInterpolate => None,
+        }
+    }
+
+    /// The built-in function that Lark code calls `name`, if any.
+    /// Functions that cannot be called by name are never found.
+    pub fn from_name(name: &str) -> Option<BuiltinFn> {
+        BuiltinFn::ALL
+            .iter()
+            .cloned()
+            .find(|builtin| builtin.name() == Some(name))
+    }
+
+    /// The parameter types and the return type, for the functions
+    /// that can be called by name.
+    pub fn signature(self) -> Option<(&'static [BuiltinType], BuiltinType)> {
+        match self {
+            BuiltinFn::Print => Some((&[BuiltinType::String], BuiltinType::Unit)),
+            BuiltinFn::StringInterpolate => None,
+        }
+    }
+}
@@ -0,0 +1,504 @@
+//! The query system answers the IDE's questions about the open
+//! documents. It owns the compiler database, keeps it in sync with
+//! the edits the editor sends, and reports the diagnostics of each
+//! document whenever it changes.
+
+use ast::def_id::{DefId, DefIdData};
+use ast::{AstDatabase, HasParserState};
+use database::check::CheckErrorKind;
+use database::LarkDatabase;
+use hir::{HirDatabase, MemberKind};
+use ide::diagnostics::{lsp_diagnostic, lsp_error, offset_position, position_offset};
+use intern::{Intern, Untern};
+use languageserver_types::{
+    Diagnostic, Position, Range, TextDocumentContentChangeEvent, TextEdit, Url,
+};
+use parser::pos::{Span, Spanned};
+use parser::StringId;
+use std::collections::HashMap;
+use task_manager::{Actor, Completion, TypeMessage, TypeResponse};
+use ty::BaseKind;
+use type_check::pretty::Pretty;
+use type_check::TypeCheckDatabase;
+
+pub struct QuerySystem {
+    db: LarkDatabase,
+    send_channel: Option<Box<dyn Fn(TypeResponse) -> () + Send>>,
+
+    /// The nodes that we have handed out ids for since the last edit;
+    /// the id `first_node_id + i` stands for `nodes[i]`. An edit can
+    /// change what a node refers to, so it forgets them all, and ids
+    /// from before it find nothing.
+    nodes: Vec<Node>,
+    node_ids: HashMap<Node, task_manager::DefId>,
+    first_node_id: task_manager::DefId,
+
+    /// The documents that have been opened, in order, each with the
+    /// diagnostics we last published for it.
+    open_files: Vec<(Url, Vec<Diagnostic>)>,
+}
+
+/// A part of the program that the IDE can ask about.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+enum Node {
+    /// There is nothing of interest at the position that was asked
+    /// about.
+    Nothing,
+
+    /// An item, or a field of a struct.
+    Item(DefId),
+
+    /// A part of the body of the function `DefId`.
+    Body(DefId, hir::MetaIndex),
+}
+
+impl QuerySystem {
+    pub fn new() -> QuerySystem {
+        QuerySystem {
+            db: LarkDatabase::default(),
+            send_channel: None,
+            nodes: vec![],
+            node_ids: HashMap::new(),
+            first_node_id: 0,
+            open_files: vec![],
+        }
+    }
+
+    fn send(&self, response: TypeResponse) {
+        if let Some(ref c) = self.send_channel {
+            c(response);
+        }
+    }
+
+    fn node_id(&mut self, node: Node) -> task_manager::DefId {
+        let nodes = &mut self.nodes;
+        let first_node_id = self.first_node_id;
+        *self.node_ids.entry(node).or_insert_with(|| {
+            nodes.push(node);
+            first_node_id + nodes.len() - 1
+        })
+    }
+
+    /// The node that `node_id` was handed out for, or `Node::Nothing`
+    /// if there has been an edit since.
+    fn node(&self, node_id: task_manager::DefId) -> Node {
+        match node_id.checked_sub(self.first_node_id) {
+            Some(index) => self.nodes.get(index).cloned().unwrap_or(Node::Nothing),
+            None => Node::Nothing,
+        }
+    }
+
+    /// Forgets the nodes handed out so far, after an edit.
+    fn forget_nodes(&mut self) {
+        self.first_node_id += self.nodes.len();
+        self.nodes.clear();
+        self.node_ids.clear();
+    }
+
+    /// The innermost node at `position` in the document `url`.
+    fn node_at(&self, url: &Url, position: Position) -> Node {
+        let path = self.db.intern_string(url.as_str());
+        let text = match self.db.input_text(path) {
+            Some(text) => self.db.untern_string(text),
+            None => return Node::Nothing,
+        };
+
+        // When completing after a `.`, what we want is the thing
+        // before it.
+        let mut offset = position_offset(position, &text);
+        if text[..offset].ends_with('.') {
+            offset -= 1;
+        }
+
+        for &item_id in self.db.items_in_file(path).iter() {
+            // The spans of an item are relative to where it starts.
+            let start = self.db.item_offset(item_id) as usize;
+            if offset < start {
+                continue;
+            }
+            let offset = offset - start;
+
+            let item = self.db.ast_of_item(item_id);
+            let def_id = DefIdData::Item(item_id).intern(&self.db);
+
+            match &*item {
+                ast::ast::Item::Struct(s) if contains(s.span, offset) => {
+                    if let Some(method) = s.methods.iter().find(|m| contains(m.span, offset)) {
+                        let members = self.db.members(def_id);
+                        let member = members.iter().find(|member| {
+                            member.kind == MemberKind::Method && member.name == method.name.node
+                        });
+                        if let Some(member) = member {
+                            return self.body_node(member.def_id, offset);
+                        }
+                    }
+
+                    return match s.fields.iter().find(|f| contains(f.span, offset)) {
+                        Some(field) => Node::Item(
+                            DefIdData::Field {
+                                owner: def_id,
+                                name: field.name.node,
+                            }
+                            .intern(&self.db),
+                        ),
+                        None => Node::Item(def_id),
+                    };
+                }
+
+                ast::ast::Item::Enum(e) if contains(e.span, offset) => {
+                    return match e.variants.iter().find(|v| contains(v.span, offset)) {
+                        Some(variant) => Node::Item(
+                            DefIdData::Variant {
+                                owner: def_id,
+                                name: variant.name.node,
+                            }
+                            .intern(&self.db),
+                        ),
+                        None => Node::Item(def_id),
+                    };
+                }
+
+                ast::ast::Item::Def(d) if contains(d.span, offset) => {
+                    return self.body_node(def_id, offset);
+                }
+
+                _ => {}
+            }
+        }
+
+        Node::Nothing
+    }
+
+    /// The innermost part of the body of the function `def_id` at
+    /// `offset`, or the function itself.
+    fn body_node(&self, def_id: DefId, offset: usize) -> Node {
+        let fn_body = self.db.fn_body(def_id);
+        let innermost = self
+            .db
+            .base_type_check(def_id)
+            .types()
+            .map(|(index, _)| (index, fn_body.span(index)))
+            .filter(|&(_, span)| contains(span, offset))
+            .min_by_key(|&(_, span)| len(span));
+        match innermost {
+            Some((index, _)) => Node::Body(def_id, index),
+            None => Node::Item(def_id),
+        }
+    }
+
+    /// The type of `node`, like `String`, or of a function, like
+    /// `(i32, i32) -> ()`.
+    fn type_of(&self, node: Node) -> String {
+        let db = &self.db;
+        match node {
+            Node::Nothing => String::new(),
+            Node::Body(def_id, index) => match db
+                .base_type_check(def_id)
+                .types()
+                .find(|&(i, _)| i == index)
+            {
+                Some((_, ty)) => ty.pretty(db),
+                None => String::new(),
+            },
+            Node::Item(def_id) => match def_id.untern(db) {
+                DefIdData::Item(item_id) => match &*db.ast_of_item(item_id) {
+                    ast::ast::Item::Def(_) => db.signature(def_id).pretty(db),
+                    ast::ast::Item::Struct(_) | ast::ast::Item::Enum(_) => {
+                        db.ty(def_id).pretty(db)
+                    }
+                    ast::ast::Item::Mod(_) | ast::ast::Item::Use(_) => String::new(),
+                },
+                DefIdData::Variant { .. } => db.signature(def_id).pretty(db),
+                DefIdData::Field { .. }
+                | DefIdData::TypeParameter { .. }
+                | DefIdData::Builtin(_) => db.ty(def_id).pretty(db),
+            },
+        }
+    }
+
+    /// What to show when hovering over `node`: its type, followed by
+    /// its documentation if it has any.
+    fn hover(&self, node: Node) -> String {
+        let ty = self.type_of(node);
+        let docs = match node {
+            Node::Item(def_id) => self.docs(def_id),
+            Node::Nothing | Node::Body(..) => None,
+        };
+        match docs {
+            Some(docs) => format!("{}\n\n{}", ty, docs),
+            None => ty,
+        }
+    }
+
+    /// The text of the `///` comments on the item, variant or field
+    /// `def_id`, without the slashes.
+    fn docs(&self, def_id: DefId) -> Option<String> {
+        let db = &self.db;
+        let item = match def_id.untern(db) {
+            DefIdData::Item(item_id) => db.ast_of_item(item_id),
+            DefIdData::Variant { owner, name } => {
+                let variant = self.variant(owner, name)?;
+                return doc_text(db, &variant.docs);
+            }
+            DefIdData::Field { owner, name } => {
+                let fields = match owner.untern(db) {
+                    DefIdData::Item(item_id) => match &*db.ast_of_item(item_id) {
+                        ast::ast::Item::Struct(s) => s.fields.clone(),
+                        ast::ast::Item::Enum(_)
+                        | ast::ast::Item::Def(_)
+                        | ast::ast::Item::Mod(_)
+                        | ast::ast::Item::Use(_) => return None,
+                    },
+                    DefIdData::Variant {
+                        owner: enum_def_id,
+                        name: variant_name,
+                    } => match self.variant(enum_def_id, variant_name)?.fields {
+                        ast::ast::VariantFields::Struct(fields) => fields,
+                        ast::ast::VariantFields::Unit | ast::ast::VariantFields::Tuple(_) => {
+                            return None
+                        }
+                    },
+                    _ => return None,
+                };
+                let field = fields.iter().find(|field| field.name.node == name)?;
+                return doc_text(db, &field.docs);
+            }
+            DefIdData::Builtin(_) | DefIdData::TypeParameter { .. } => return None,
+        };
+        doc_text(db, item.docs())
+    }
+
+    /// The variant `name` of the enum `owner`.
+    fn variant(&self, owner: DefId, name: StringId) -> Option<ast::ast::Variant> {
+        let item_id = match owner.untern(&self.db) {
+            DefIdData::Item(item_id) => item_id,
+            _ => return None,
+        };
+        match &*self.db.ast_of_item(item_id) {
+            ast::ast::Item::Enum(e) => e.variants.iter().find(|v| v.name.node == name).cloned(),
+            ast::ast::Item::Struct(_)
+            | ast::ast::Item::Def(_)
+            | ast::ast::Item::Mod(_)
+            | ast::ast::Item::Use(_) => None,
+        }
+    }
+
+    /// The members of the type of `node`, each with its type and
+    /// documentation.
+    fn completions(&self, node: Node) -> Vec<Completion> {
+        let db = &self.db;
+        let ty_def_id = match node {
+            Node::Nothing => None,
+            Node::Body(def_id, index) => db
+                .base_type_check(def_id)
+                .types()
+                .find(|&(i, _)| i == index)
+                .and_then(|(_, ty)| match ty.base.untern(db).kind {
+                    BaseKind::Named(def_id) => Some(def_id),
+                    BaseKind::Placeholder(_) | BaseKind::Error => None,
+                }),
+            Node::Item(def_id) => match def_id.untern(db) {
+                DefIdData::Item(_) => Some(def_id),
+                DefIdData::Field { .. }
+                | DefIdData::Variant { .. }
+                | DefIdData::TypeParameter { .. }
+                | DefIdData::Builtin(_) => match db.ty(def_id).base.untern(db) {
+                    ty::BoundVarOr::Known(data) => match data.kind {
+                        BaseKind::Named(def_id) => Some(def_id),
+                        BaseKind::Placeholder(never) => never,
+                        BaseKind::Error => None,
+                    },
+                    ty::BoundVarOr::BoundVar(_) => None,
+                },
+            },
+        };
+
+        let ty_def_id = match ty_def_id {
+            Some(def_id) => def_id,
+            None => return vec![],
+        };
+
+        db.members(ty_def_id)
+            .iter()
+            .map(|member| {
+                let detail = match member.kind {
+                    MemberKind::Field => db.ty(member.def_id).pretty(db),
+                    MemberKind::Method | MemberKind::Variant => {
+                        db.signature(member.def_id).pretty(db)
+                    }
+                };
+                let docs = self.docs(member.def_id);
+                (db.untern_string(member.name).to_string(), detail, docs)
+            })
+            .collect()
+    }
+
+    /// The current text of the document `url`, or the empty string if
+    /// it has not been opened.
+    fn text(&self, url: &Url) -> String {
+        let path = self.db.intern_string(url.as_str());
+        match self.db.input_text(path) {
+            Some(text) => self.db.untern_string(text).to_string(),
+            None => String::new(),
+        }
+    }
+
+    /// The edits that format the document `url`, or with a `range`,
+    /// just the items that overlap it. A document with syntax errors
+    /// is left as it is.
+    fn formatting_edits(&self, url: &Url, range: Option<Range>) -> Vec<TextEdit> {
+        let text = self.text(url);
+        let edit = |start, end, new_text| {
+            TextEdit::new(
+                Range::new(offset_position(start, &text), offset_position(end, &text)),
+                new_text,
+            )
+        };
+
+        match range {
+            None => match parser::format::format(&text) {
+                Ok(formatted) if formatted != text => vec![edit(0, text.len(), formatted)],
+                Ok(_) | Err(_) => vec![],
+            },
+            Some(range) => {
+                let start = position_offset(range.start, &text);
+                let end = position_offset(range.end, &text);
+                match parser::format::format_range(&text, start..end) {
+                    Ok(edits) => edits
+                        .into_iter()
+                        .map(|(range, formatted)| edit(range.start, range.end, formatted))
+                        .collect(),
+                    Err(_) => vec![],
+                }
+            }
+        }
+    }
+
+    /// Publishes the diagnostics of the document `url`, which has just
+    /// been opened or edited, and of each other open document whose
+    /// diagnostics the change affected.
+    fn publish_diagnostics(&mut self, url: Url) {
+        if !self.open_files.iter().any(|(open, _)| *open == url) {
+            self.open_files.push((url.clone(), vec![]));
+        }
+
+        for index in 0..self.open_files.len() {
+            let open = self.open_files[index].0.clone();
+            let diagnostics = self.diagnostics(&open);
+            if open != url && diagnostics == self.open_files[index].1 {
+                continue;
+            }
+
+            self.open_files[index].1 = diagnostics.clone();
+            self.send(TypeResponse::Diagnostics(open, diagnostics));
+        }
+    }
+
+    /// The errors in the document `url`, found by the same checks as
+    /// `lark build` runs.
+    fn diagnostics(&self, url: &Url) -> Vec<Diagnostic> {
+        let path = self.db.intern_string(url.as_str());
+        let text = self.text(url);
+        database::check::check(&self.db, &[path])
+            .into_iter()
+            .map(|err| match err.kind {
+                CheckErrorKind::Message { message, span } => lsp_error(message, span, &text),
+                CheckErrorKind::TypeCheck(diagnostic) => lsp_diagnostic(&diagnostic, url, &text),
+            })
+            .collect()
+    }
+}
+
+impl Actor for QuerySystem {
+    type InMessage = TypeMessage;
+    type OutMessage = TypeResponse;
+
+    fn startup(&mut self, send_channel: Box<dyn Fn(Self::OutMessage) -> () + Send>) {
+        self.send_channel = Some(send_channel);
+    }
+
+    fn shutdown(&mut self) {}
+
+    fn receive_message(&mut self, message: Self::InMessage) {
+        match message {
+            TypeMessage::OpenFile(url, contents) => {
+                self.db.add_file(url.as_str(), &contents);
+                self.forget_nodes();
+                self.publish_diagnostics(url);
+            }
+            TypeMessage::EditFile(url, changes) => {
+                let mut text = self.text(&url);
+                for change in changes {
+                    apply_change(&mut text, change);
+                }
+
+                self.db.add_file(url.as_str(), &text);
+                self.forget_nodes();
+                self.publish_diagnostics(url);
+            }
+            TypeMessage::DefIdForPos(task_id, url, position) => {
+                let node = self.node_at(&url, position);
+                let node_id = self.node_id(node);
+                self.send(TypeResponse::DefId(task_id, node_id));
+            }
+            TypeMessage::TypeForDefId(task_id, node_id) => {
+                let hover = self.hover(self.node(node_id));
+                self.send(TypeResponse::Type(task_id, hover));
+            }
+            TypeMessage::CompletionsForDefId(task_id, node_id) => {
+                let completions = self.completions(self.node(node_id));
+                self.send(TypeResponse::Completions(task_id, completions));
+            }
+            TypeMessage::EditsForFormatting(task_id, url, range) => {
+                let edits = self.formatting_edits(&url, range);
+                self.send(TypeResponse::Edits(task_id, edits));
+            }
+        }
+    }
+}
+
+/// Applies the edit `change` to `text`. A change without a range
+/// replaces the whole text.
+fn apply_change(text: &mut String, change: TextDocumentContentChangeEvent) {
+    match change.range {
+        Some(range) => {
+            let start = position_offset(range.start, text);
+            let end = position_offset(range.end, text);
+            text.replace_range(start..end, &change.text);
+        }
+        None => *text = change.text,
+    }
+}
+
+/// Joins the lines of the doc comment `docs`, dropping each line's
+/// `///` and the space after it.
+fn doc_text(db: &LarkDatabase, docs: &[Spanned<StringId>]) -> Option<String> {
+    if docs.is_empty() {
+        return None;
+    }
+
+    let lines: Vec<String> = docs
+        .iter()
+        .map(|line| {
+            let text = db.untern_string(line.node);
+            let text = text.trim_end().trim_start_matches("///");
+            let text = if text.starts_with(' ') { &text[1..] } else { text };
+            text.to_string()
+        })
+        .collect();
+    Some(lines.join("\n"))
+}
+
+fn contains(span: Span, offset: usize) -> bool {
+    match span {
+        Span::Real(span) => span.start().to_usize() <= offset && offset <= span.end().to_usize(),
+        Span::EOF | Span::Synthetic => false,
+    }
+}
+
+fn len(span: Span) -> usize {
+    match span {
+        Span::Real(span) => span.end().to_usize() - span.start().to_usize(),
+        Span::EOF | Span::Synthetic => 0,
+    }
+}
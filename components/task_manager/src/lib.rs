@@ -1,13 +1,18 @@
-use languageserver_types::Position;
+use languageserver_types::{
+    Diagnostic, Position, Range, TextDocumentContentChangeEvent, TextEdit, Url,
+};
 
 use std::collections::HashMap;
 use std::sync::mpsc::{channel, Receiver, Sender};
 use std::thread;
 
-use mir::DefId;
-
 type TaskId = usize;
 
+/// Identifies a part of the program, like an item or an expression,
+/// that a task is asking about. The type checker hands these out and
+/// is the only one that knows what they refer to.
+pub type DefId = usize;
+
 enum MsgFromManager<T> {
     Shutdown,
     Message(T),
@@ -15,27 +20,44 @@ enum MsgFromManager<T> {
 
 #[derive(Debug)]
 pub enum TypeMessage {
-    DefIdForPos(TaskId, Position),
+    DefIdForPos(TaskId, Url, Position),
     TypeForDefId(TaskId, DefId),
     CompletionsForDefId(TaskId, DefId),
+
+    /// The edits that format the document, or with a range, just the
+    /// items that overlap it.
+    EditsForFormatting(TaskId, Url, Option<Range>),
+    OpenFile(Url, String),
+    EditFile(Url, Vec<TextDocumentContentChangeEvent>),
 }
 
 pub enum TypeResponse {
     DefId(TaskId, DefId),
     Type(TaskId, String),
-    Completions(TaskId, Vec<(String, String)>),
+    Completions(TaskId, Vec<Completion>),
+    Edits(TaskId, Vec<TextEdit>),
+    Diagnostics(Url, Vec<Diagnostic>),
 }
 
+/// A name that can be completed: the name, its type, and its
+/// documentation, if it has any.
+pub type Completion = (String, String, Option<String>);
+
 pub enum LspRequest {
-    TypeForPos(TaskId, Position),
-    Completion(TaskId, Position),
+    TypeForPos(TaskId, Url, Position),
+    Completion(TaskId, Url, Position),
+    Formatting(TaskId, Url, Option<Range>),
     Initialize(TaskId),
+    OpenFile(Url, String),
+    EditFile(Url, Vec<TextDocumentContentChangeEvent>),
 }
 
 pub enum LspResponse {
     Type(TaskId, String),
-    Completions(TaskId, Vec<(String, String)>),
+    Completions(TaskId, Vec<Completion>),
+    Edits(TaskId, Vec<TextEdit>),
     Initialized(TaskId),
+    Diagnostics(Url, Vec<Diagnostic>),
 }
 
 pub enum MsgToManager {
@@ -49,9 +71,11 @@ enum RecipeStep {
 
     GetTypeForDefId,
     GetCompletionsForDefId,
+    GetEditsForFormatting,
 
     RespondWithType,
     RespondWithCompletions,
+    RespondWithEdits,
     RespondWithInitialized,
 }
 
@@ -69,50 +93,6 @@ pub struct ActorControl<MessageType: Send + Sync + 'static> {
     pub join_handle: std::thread::JoinHandle<()>,
 }
 
-pub struct FakeTypeChecker {
-    send_channel: Option<Box<dyn Fn(TypeResponse) -> () + Send>>,
-}
-
-impl Actor for FakeTypeChecker {
-    type InMessage = TypeMessage;
-    type OutMessage = TypeResponse;
-
-    fn startup(&mut self, send_channel: Box<dyn Fn(Self::OutMessage) -> () + Send>) {
-        self.send_channel = Some(send_channel);
-    }
-
-    fn shutdown(&mut self) {}
-
-    fn receive_message(&mut self, message: Self::InMessage) {
-        match message {
-            TypeMessage::DefIdForPos(task_id, pos) => match self.send_channel {
-                Some(ref c) => c(TypeResponse::DefId(task_id, pos.line as usize * 100)),
-                None => {}
-            },
-            TypeMessage::TypeForDefId(task_id, def_id) => match self.send_channel {
-                Some(ref c) => c(TypeResponse::Type(task_id, format!("<type:{}>", def_id))),
-                None => {}
-            },
-            TypeMessage::CompletionsForDefId(task_id, def_id) => match self.send_channel {
-                Some(ref c) => c(TypeResponse::Completions(
-                    task_id,
-                    vec![
-                        ("bar".into(), format!("First option for {}", def_id)),
-                        ("foo".into(), format!("Second option for {}", def_id)),
-                    ],
-                )),
-                None => {}
-            },
-        }
-    }
-}
-
-impl FakeTypeChecker {
-    pub fn new() -> FakeTypeChecker {
-        FakeTypeChecker { send_channel: None }
-    }
-}
-
 pub struct TaskManager {
     live_recipes: HashMap<TaskId, Vec<RecipeStep>>,
     receive_channel: Receiver<MsgToManager>,
@@ -174,11 +154,12 @@ impl TaskManager {
 
                     match next_step {
                         RecipeStep::GetDefIdForPosition => {
-                            if let Ok(position) = argument.downcast::<Position>() {
+                            if let Ok(argument) = argument.downcast::<(Url, Position)>() {
+                                let (url, position) = *argument;
                                 self.type_checker
                                     .channel
                                     .send(MsgFromManager::Message(TypeMessage::DefIdForPos(
-                                        task_id, *position,
+                                        task_id, url, position,
                                     )))
                                     .unwrap();
                             } else {
@@ -209,6 +190,19 @@ impl TaskManager {
                                 panic!("Internal error: malformed GetCompletionsForDefId");
                             }
                         }
+                        RecipeStep::GetEditsForFormatting => {
+                            if let Ok(argument) = argument.downcast::<(Url, Option<Range>)>() {
+                                let (url, range) = *argument;
+                                self.type_checker
+                                    .channel
+                                    .send(MsgFromManager::Message(
+                                        TypeMessage::EditsForFormatting(task_id, url, range),
+                                    ))
+                                    .unwrap();
+                            } else {
+                                panic!("Internal error: malformed GetEditsForFormatting");
+                            }
+                        }
                         RecipeStep::RespondWithType => {
                             if let Ok(ty) = argument.downcast::<String>() {
                                 self.lsp_responder
@@ -220,7 +214,7 @@ impl TaskManager {
                             }
                         }
                         RecipeStep::RespondWithCompletions => {
-                            if let Ok(completions) = argument.downcast::<Vec<(String, String)>>() {
+                            if let Ok(completions) = argument.downcast::<Vec<Completion>>() {
                                 self.lsp_responder
                                     .channel
                                     .send(MsgFromManager::Message(LspResponse::Completions(
@@ -232,6 +226,18 @@ impl TaskManager {
                                 panic!("Internal error: malformed RespondWithCompletion");
                             }
                         }
+                        RecipeStep::RespondWithEdits => {
+                            if let Ok(edits) = argument.downcast::<Vec<TextEdit>>() {
+                                self.lsp_responder
+                                    .channel
+                                    .send(MsgFromManager::Message(LspResponse::Edits(
+                                        task_id, *edits,
+                                    )))
+                                    .unwrap();
+                            } else {
+                                panic!("Internal error: malformed RespondWithEdits");
+                            }
+                        }
                         RecipeStep::RespondWithInitialized => {
                             self.lsp_responder
                                 .channel
@@ -249,7 +255,7 @@ impl TaskManager {
 
     fn do_recipe_for_lsp_request(&mut self, lsp_request: LspRequest) {
         match lsp_request {
-            LspRequest::TypeForPos(task_id, position) => {
+            LspRequest::TypeForPos(task_id, url, position) => {
                 let recipe = vec![
                     RecipeStep::GetDefIdForPosition,
                     RecipeStep::GetTypeForDefId,
@@ -257,10 +263,10 @@ impl TaskManager {
                 ];
 
                 self.live_recipes.insert(task_id, recipe);
-                self.send_next_step(task_id, Box::new(position));
+                self.send_next_step(task_id, Box::new((url, position)));
             }
 
-            LspRequest::Completion(task_id, position) => {
+            LspRequest::Completion(task_id, url, position) => {
                 let recipe = vec![
                     RecipeStep::GetDefIdForPosition,
                     RecipeStep::GetCompletionsForDefId,
@@ -268,7 +274,17 @@ impl TaskManager {
                 ];
 
                 self.live_recipes.insert(task_id, recipe);
-                self.send_next_step(task_id, Box::new(position));
+                self.send_next_step(task_id, Box::new((url, position)));
+            }
+
+            LspRequest::Formatting(task_id, url, range) => {
+                let recipe = vec![
+                    RecipeStep::GetEditsForFormatting,
+                    RecipeStep::RespondWithEdits,
+                ];
+
+                self.live_recipes.insert(task_id, recipe);
+                self.send_next_step(task_id, Box::new((url, range)));
             }
 
             LspRequest::Initialize(task_id) => {
@@ -277,6 +293,22 @@ impl TaskManager {
                 self.live_recipes.insert(task_id, recipe);
                 self.send_next_step(task_id, Box::new(()));
             }
+
+            // Document changes are notifications: there is no task to
+            // track, so they go straight to the type checker.
+            LspRequest::OpenFile(url, contents) => {
+                self.type_checker
+                    .channel
+                    .send(MsgFromManager::Message(TypeMessage::OpenFile(url, contents)))
+                    .unwrap();
+            }
+
+            LspRequest::EditFile(url, changes) => {
+                self.type_checker
+                    .channel
+                    .send(MsgFromManager::Message(TypeMessage::EditFile(url, changes)))
+                    .unwrap();
+            }
         }
     }
 
@@ -292,6 +324,18 @@ impl TaskManager {
                 Ok(MsgToManager::TypeResponse(TypeResponse::Completions(task_id, completions))) => {
                     self.send_next_step(task_id, Box::new(completions));
                 }
+                Ok(MsgToManager::TypeResponse(TypeResponse::Edits(task_id, edits))) => {
+                    self.send_next_step(task_id, Box::new(edits));
+                }
+                Ok(MsgToManager::TypeResponse(TypeResponse::Diagnostics(url, diagnostics))) => {
+                    self.lsp_responder
+                        .channel
+                        .send(MsgFromManager::Message(LspResponse::Diagnostics(
+                            url,
+                            diagnostics,
+                        )))
+                        .unwrap();
+                }
                 Ok(MsgToManager::LspRequest(lsp_request)) => {
                     self.do_recipe_for_lsp_request(lsp_request);
                 }
@@ -8,7 +8,7 @@
 use crate::interners::TyInternTables;
 use indices::IndexVec;
 use intern::Has;
-use mir::DefId;
+use ast::def_id::DefId;
 use parser::program::StringId;
 use std::fmt::Debug;
 use std::hash::Hash;
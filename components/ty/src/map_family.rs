@@ -8,7 +8,7 @@ use crate::Signature;
 use crate::Ty;
 use crate::TypeFamily;
 use intern::Has;
-use mir::DefId;
+use ast::def_id::DefId;
 use std::sync::Arc;
 
 pub trait Map<S: TypeFamily, T: TypeFamily>: Clone {
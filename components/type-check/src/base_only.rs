@@ -1,9 +1,11 @@
 use crate::substitute::Substitution;
 use crate::Error;
+use crate::ErrorKind;
 use crate::TypeCheckFamily;
 use crate::TypeCheckerFields;
 use hir;
 use hir::HirDatabase;
+use ty::base_inferred::BaseInferred;
 use ty::base_only::{Base, BaseOnly, BaseTy};
 use ty::declaration::Declaration;
 use ty::identity::Identity;
@@ -56,7 +58,15 @@ impl TypeCheckFamily for BaseOnly {
                 }
 
                 if data1.kind != data2.kind {
-                    this.results().errors.push(Error { location: cause });
+                    let expected_from = this.unify().value_cause(base2);
+                    this.results().errors.push(Error {
+                        location: cause,
+                        kind: ErrorKind::Mismatch {
+                            expected: inferred_kind(data2.kind),
+                            found: inferred_kind(data1.kind),
+                            expected_from,
+                        },
+                    });
                     return;
                 }
 
@@ -170,3 +180,13 @@ fn propagate_error<F: TypeCheckFamily>(
         }
     }
 }
+
+/// Converts a `BaseKind` from the type check into the form used in
+/// its results.
+fn inferred_kind(kind: BaseKind<BaseOnly>) -> BaseKind<BaseInferred> {
+    match kind {
+        BaseKind::Named(def_id) => BaseKind::Named(def_id),
+        BaseKind::Placeholder(placeholder) => BaseKind::Placeholder(placeholder),
+        BaseKind::Error => BaseKind::Error,
+    }
+}
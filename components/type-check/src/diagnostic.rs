@@ -0,0 +1,215 @@
+//! Explains type-check errors to the user. A `Diagnostic` says what
+//! to show but not how to show it; the CLI and the IDE each render it
+//! in their own way.
+
+use crate::Error;
+use crate::ErrorKind;
+use crate::TypeCheckDatabase;
+use ast::def_id::{DefId, DefIdData};
+use hir::HirDatabase;
+use intern::Untern;
+use parser::pos::Span;
+use ty::base_inferred::BaseInferred;
+use ty::BaseKind;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+    /// Identifies the kind of error, like `E0001`.
+    pub code: &'static str,
+
+    /// A summary of what went wrong.
+    pub message: String,
+
+    /// The parts of the source that the diagnostic refers to.
+    pub labels: Vec<Label>,
+
+    /// Further explanation, shown after the labels.
+    pub notes: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+
+    /// The primary label is where the error was detected; secondary
+    /// labels give context.
+    pub primary: bool,
+}
+
+impl Label {
+    fn primary(span: Span, message: String) -> Label {
+        Label {
+            span,
+            message,
+            primary: true,
+        }
+    }
+
+    fn secondary(span: Span, message: String) -> Label {
+        Label {
+            span,
+            message,
+            primary: false,
+        }
+    }
+}
+
+impl Error {
+    /// Explains this error, which was found in `fn_body`.
+    pub fn diagnostic(&self, db: &impl TypeCheckDatabase, fn_body: &hir::FnBody) -> Diagnostic {
+        let span = fn_body.span(self.location);
+        match self.kind {
+            ErrorKind::Mismatch {
+                expected,
+                found,
+                expected_from,
+            } => {
+                let expected = kind_name(db, expected);
+                let found = kind_name(db, found);
+
+                let mut labels = vec![Label::primary(
+                    span,
+                    format!("expected `{}`, found `{}`", expected, found),
+                )];
+                if let Some(expected_from) = expected_from {
+                    if expected_from != self.location {
+                        labels.push(Label::secondary(
+                            fn_body.span(expected_from),
+                            format!("expected `{}` because of this", expected),
+                        ));
+                    }
+                }
+
+                Diagnostic {
+                    code: "E0001",
+                    message: "mismatched types".to_string(),
+                    labels,
+                    notes: vec![
+                        format!("expected type `{}`", expected),
+                        format!("   found type `{}`", found),
+                    ],
+                }
+            }
+
+            ErrorKind::WrongNumberOfArguments { expected, found } => Diagnostic {
+                code: "E0002",
+                message: format!(
+                    "expected {} argument{}, found {}",
+                    expected,
+                    plural(expected),
+                    found
+                ),
+                labels: vec![Label::primary(
+                    span,
+                    format!("expected {} argument{}", expected, plural(expected)),
+                )],
+                notes: vec![],
+            },
+
+            ErrorKind::WrongNumberOfFields { expected, found } => Diagnostic {
+                code: "E0003",
+                message: format!("expected {} field{}, found {}", expected, plural(expected), found),
+                labels: vec![Label::primary(
+                    span,
+                    format!("expected {} field{}", expected, plural(expected)),
+                )],
+                notes: vec![],
+            },
+
+            ErrorKind::UnknownMember { name } => Diagnostic {
+                code: "E0004",
+                message: format!("no field or method named `{}`", db.untern_string(name)),
+                labels: vec![Label::primary(span, "unknown member".to_string())],
+                notes: vec![],
+            },
+
+            ErrorKind::CannotInfer => Diagnostic {
+                code: "E0005",
+                message: "cannot infer type".to_string(),
+                labels: vec![Label::primary(
+                    span,
+                    "cannot infer the type of this".to_string(),
+                )],
+                notes: vec![],
+            },
+
+            ErrorKind::NonExhaustive { missing } => {
+                let missing = db.untern_string(missing);
+                Diagnostic {
+                    code: "E0006",
+                    message: format!("non-exhaustive patterns: `{}` not covered", missing),
+                    labels: vec![Label::primary(
+                        span,
+                        format!("pattern `{}` not covered", missing),
+                    )],
+                    notes: vec![],
+                }
+            }
+
+            ErrorKind::UnreachableArm => Diagnostic {
+                code: "E0007",
+                message: "unreachable pattern".to_string(),
+                labels: vec![Label::primary(
+                    span,
+                    "the arms above already match everything this does".to_string(),
+                )],
+                notes: vec![],
+            },
+
+            ErrorKind::CannotInterpolate { found } => {
+                let found = kind_name(db, found);
+                Diagnostic {
+                    code: "E0008",
+                    message: format!("`{}` cannot be embedded in a string", found),
+                    labels: vec![Label::primary(span, format!("found `{}`", found))],
+                    notes: vec![
+                        "only `bool`, `i32`, `u32`, `f64` and `String` can be embedded".to_string(),
+                    ],
+                }
+            }
+
+            ErrorKind::UnsupportedOperator { operator, found } => {
+                let found = kind_name(db, found);
+                Diagnostic {
+                    code: "E0009",
+                    message: format!("cannot apply `{}` to `{}`", operator, found),
+                    labels: vec![Label::primary(span, format!("found `{}`", found))],
+                    notes: vec![],
+                }
+            }
+        }
+    }
+}
+
+/// The name of the type or item `def_id`, as the user would write it.
+pub fn def_id_name(db: &impl HirDatabase, def_id: DefId) -> String {
+    match def_id.untern(db) {
+        DefIdData::Builtin(name) => db.untern_string(name).to_string(),
+        DefIdData::Item(item_id) => {
+            let path = item_id.untern(db).path;
+            db.untern_string(*path.last().unwrap()).to_string()
+        }
+        DefIdData::Field { name, .. }
+        | DefIdData::Variant { name, .. }
+        | DefIdData::TypeParameter { name, .. } => {
+            db.untern_string(name).to_string()
+        }
+    }
+}
+
+fn kind_name(db: &impl HirDatabase, kind: BaseKind<BaseInferred>) -> String {
+    match kind {
+        BaseKind::Named(def_id) => def_id_name(db, def_id),
+        BaseKind::Placeholder(_) => "<generic>".to_string(),
+        BaseKind::Error => "<error>".to_string(),
+    }
+}
+
+fn plural(count: usize) -> &'static str {
+    if count == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
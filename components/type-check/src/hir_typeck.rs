@@ -1,11 +1,16 @@
+use crate::ErrorKind;
 use crate::TypeCheckDatabase;
 use crate::TypeCheckFamily;
 use crate::TypeChecker;
 use crate::TypeCheckerFields;
+use ast::def_id::{DefId, DefIdData};
 use hir;
+use intern::Untern;
+use prelude::BuiltinType;
 use std::sync::Arc;
+use ty::base_inferred::BaseInferred;
 use ty::Ty;
-use ty::{BaseData, BaseKind};
+use ty::{BaseData, BaseKind, GenericKind, Generics};
 
 impl<DB, F> TypeChecker<'_, DB, F>
 where
@@ -16,6 +21,7 @@ where
         let signature = self.db.signature(self.fn_def_id);
         let placeholders = self.placeholders_for(self.fn_def_id);
         let signature = self.substitute(self.hir.root_expression, &placeholders, signature);
+        self.fn_generics = placeholders;
         assert_eq!(signature.inputs.len(), self.hir.arguments.len());
         for (&argument, &input) in self.hir.arguments.iter().zip(signature.inputs.iter()) {
             self.results.record_ty(argument, input);
@@ -42,11 +48,20 @@ where
         match expression_data {
             hir::ExpressionData::Let {
                 var,
+                ty,
                 initializer,
                 body,
             } => {
-                let initializer_ty = self.check_expression(initializer);
-                self.results.record_ty(var, initializer_ty);
+                let var_ty = match ty {
+                    Some(declared_ty) => {
+                        let generics = self.fn_generics.clone();
+                        let var_ty = self.substitute(var, &generics, declared_ty);
+                        self.check_expression_has_type(var_ty, initializer);
+                        var_ty
+                    }
+                    None => self.check_expression(initializer),
+                };
+                self.results.record_ty(var, var_ty);
                 self.check_expression(body)
             }
 
@@ -88,10 +103,313 @@ where
                 self.least_upper_bound(expression, true_ty, false_ty)
             }
 
-            hir::ExpressionData::Unit {} => unimplemented!(),
+            // Each arm must produce the same type; a `match` without
+            // arms, which is only allowed on an enum without variants,
+            // produces `()`.
+            hir::ExpressionData::Match { scrutinee, arms } => {
+                let scrutinee_ty = self.check_place(scrutinee);
+                let mut match_ty = None;
+                for arm in arms.iter() {
+                    self.check_pattern(arm.pattern, scrutinee_ty);
+                    let body_ty = self.check_expression(arm.body);
+                    match_ty = Some(match match_ty {
+                        Some(match_ty) => self.least_upper_bound(arm.body, body_ty, match_ty),
+                        None => body_ty,
+                    });
+                }
+                match match_ty {
+                    Some(match_ty) => match_ty,
+                    None => self.declared_type(expression, self.db.unit_def_id(())),
+                }
+            }
+
+            hir::ExpressionData::Loop { body } => {
+                // A loop produces the values it is broken out of with,
+                // so `break` needs to find the loop's type while we are
+                // still checking the body.
+                let loop_ty = self.new_infer_ty();
+                self.results.record_ty(expression, loop_ty);
+                let _ = self.check_expression(body);
+
+                // A loop that is never broken out of never produces a
+                // value; say it produces `()` so that its type is known.
+                let is_broken = self.hir.expressions.iter().any(|data| match data.node {
+                    hir::ExpressionData::Break { target, .. } => target == expression,
+                    _ => false,
+                });
+                if !is_broken {
+                    let unit_ty = self.declared_type(expression, self.db.unit_def_id(()));
+                    self.require_assignable(expression, unit_ty, loop_ty);
+                }
+
+                loop_ty
+            }
+
+            hir::ExpressionData::Break { target, value } => {
+                let loop_ty = self.results.ty(target);
+                self.check_expression_has_type(loop_ty, value);
+                self.declared_type(expression, self.db.unit_def_id(()))
+            }
+
+            hir::ExpressionData::Continue { target: _ } => {
+                self.declared_type(expression, self.db.unit_def_id(()))
+            }
+
+            hir::ExpressionData::Call {
+                function,
+                arguments,
+            } => self.compute_call_ty(expression, function, arguments),
+
+            hir::ExpressionData::Aggregate { entity, fields } => {
+                self.compute_aggregate_ty(expression, entity, fields)
+            }
+
+            hir::ExpressionData::Binary {
+                operator,
+                left,
+                right,
+            } => match operator {
+                hir::BinaryOperator::And | hir::BinaryOperator::Or => {
+                    let boolean_ty = self.declared_type(expression, self.db.boolean_def_id(()));
+                    self.check_expression_has_type(boolean_ty, left);
+                    self.check_expression_has_type(boolean_ty, right);
+                    boolean_ty
+                }
+
+                // Operators only apply to the built-in types whose values
+                // eval and the generated Rust both support them on.
+                _ if operator.is_comparison() => {
+                    let left_ty = self.check_expression(left);
+                    self.check_expression_has_type(left_ty, right);
+                    self.require_builtin(expression, left_ty, is_ordered, move |found| {
+                        ErrorKind::UnsupportedOperator {
+                            operator: operator.symbol(),
+                            found,
+                        }
+                    });
+                    self.declared_type(expression, self.db.boolean_def_id(()))
+                }
+
+                _ => {
+                    let left_ty = self.check_expression(left);
+                    self.check_expression_has_type(left_ty, right);
+                    self.require_builtin(expression, left_ty, is_number, move |found| {
+                        ErrorKind::UnsupportedOperator {
+                            operator: operator.symbol(),
+                            found,
+                        }
+                    });
+                    left_ty
+                }
+            },
+
+            hir::ExpressionData::Unary { operator, value } => match operator {
+                hir::UnaryOperator::Negate => {
+                    let value_ty = self.check_expression(value);
+                    self.require_builtin(expression, value_ty, is_signed, move |found| {
+                        ErrorKind::UnsupportedOperator {
+                            operator: operator.symbol(),
+                            found,
+                        }
+                    });
+                    value_ty
+                }
+                hir::UnaryOperator::Not => {
+                    let boolean_ty = self.declared_type(expression, self.db.boolean_def_id(()));
+                    self.check_expression_has_type(boolean_ty, value);
+                    boolean_ty
+                }
+            },
+
+            hir::ExpressionData::Literal { data } => {
+                let def_id = self.literal_def_id(data.kind);
+                self.declared_type(expression, def_id)
+            }
+
+            // Only built-in types other than `()` can be embedded in a
+            // string, since those are the ones that compile to Rust
+            // types that implement `Display`.
+            hir::ExpressionData::Interpolation { elements } => {
+                for &element in elements.iter() {
+                    let element_ty = self.check_expression(element);
+                    self.require_builtin(element, element_ty, is_ordered, |found| {
+                        ErrorKind::CannotInterpolate { found }
+                    });
+                }
+                let string_def_id = self.db.string_def_id(());
+                self.declared_type(expression, string_def_id)
+            }
+
+            hir::ExpressionData::Unit {} => {
+                let unit_def_id = self.db.unit_def_id(());
+                self.declared_type(expression, unit_def_id)
+            }
+
+            hir::ExpressionData::Error { error: _ } => self.error_type(),
         }
     }
 
+    /// Requires `ty`, the type of `expression` or of one of its
+    /// operands, to be a built-in type that `allowed` accepts, once it
+    /// is known. Otherwise reports the error that `error` makes from
+    /// the type that was found.
+    fn require_builtin(
+        &mut self,
+        expression: hir::Expression,
+        ty: Ty<F>,
+        allowed: fn(BuiltinType) -> bool,
+        error: impl FnOnce(BaseKind<BaseInferred>) -> ErrorKind + 'static,
+    ) {
+        self.with_base_data(expression, ty.base.into(), move |this, base_data| {
+            let found = match base_data.kind {
+                BaseKind::Named(def_id) => {
+                    if let DefIdData::Builtin(name) = def_id.untern(this.db()) {
+                        let builtin = BuiltinType::from_name(&this.db().untern_string(name));
+                        if builtin.map_or(false, allowed) {
+                            return ty;
+                        }
+                    }
+                    BaseKind::Named(def_id)
+                }
+                BaseKind::Placeholder(placeholder) => BaseKind::Placeholder(placeholder),
+                BaseKind::Error => return ty,
+            };
+            this.results.record_error(expression, error(found));
+            ty
+        });
+    }
+
+    /// The type of the struct or built-in `def_id`, which must not
+    /// have generic parameters.
+    fn declared_type(&mut self, location: impl Into<hir::MetaIndex>, def_id: DefId) -> Ty<F> {
+        let declared_ty = self.db.ty(def_id);
+        self.substitute(location, &Generics::empty(), declared_ty)
+    }
+
+    /// The built-in type of literals of the given kind.
+    fn literal_def_id(&self, kind: hir::LiteralKind) -> DefId {
+        match kind {
+            hir::LiteralKind::String => self.db.string_def_id(()),
+            hir::LiteralKind::Integer => self.db.i32_def_id(()),
+            hir::LiteralKind::Float => self.db.f64_def_id(()),
+            hir::LiteralKind::Bool => self.db.boolean_def_id(()),
+        }
+    }
+
+    /// Type-check `pattern`, which matches values of type
+    /// `expected_ty`, recording the types of the variables it binds.
+    fn check_pattern(&mut self, pattern: hir::Pattern, expected_ty: Ty<F>) {
+        self.results.record_ty(pattern, expected_ty);
+        match self.hir[pattern].clone() {
+            hir::PatternData::Wildcard | hir::PatternData::Error { error: _ } => {}
+
+            hir::PatternData::Binding { var } => self.results.record_ty(var, expected_ty),
+
+            hir::PatternData::Literal { data } => {
+                let def_id = self.literal_def_id(data.kind);
+                let literal_ty = self.declared_type(pattern, def_id);
+                self.equate_types(pattern.into(), literal_ty, expected_ty);
+            }
+
+            hir::PatternData::Constructor { entity, fields } => {
+                let generics = self.inference_variables_for(entity);
+                let entity_ty = self.db.ty(entity);
+                let entity_ty = self.substitute(pattern, &generics, entity_ty);
+                self.equate_types(pattern.into(), entity_ty, expected_ty);
+
+                for &(identifier, field_pattern) in fields.iter() {
+                    let text = self.hir[identifier].text;
+                    let field_ty = match self
+                        .db
+                        .member_def_id((entity, hir::MemberKind::Field, text))
+                    {
+                        Some(field_def_id) => {
+                            let field_decl_ty = self.db.ty(field_def_id);
+                            self.substitute(field_pattern, &generics, field_decl_ty)
+                        }
+
+                        None => {
+                            self.results
+                                .record_error(identifier, ErrorKind::UnknownMember { name: text });
+                            self.error_type()
+                        }
+                    };
+                    self.check_pattern(field_pattern, field_ty);
+                }
+            }
+        }
+    }
+
+    /// Helper for `check_expression`: Compute the type from a call to a function.
+    fn compute_call_ty(
+        &mut self,
+        expression: hir::Expression,
+        function: DefId,
+        arguments: Arc<Vec<hir::Expression>>,
+    ) -> Ty<F> {
+        let signature_decl = self.db.signature(function);
+        let generics = self.inference_variables_for(function);
+        let signature = self.substitute(expression, &generics, signature_decl);
+        if signature.inputs.len() != arguments.len() {
+            self.results.record_error(
+                expression,
+                ErrorKind::WrongNumberOfArguments {
+                    expected: signature.inputs.len(),
+                    found: arguments.len(),
+                },
+            );
+        }
+        for (&expected_ty, &argument_expr) in signature.inputs.iter().zip(arguments.iter()) {
+            self.check_expression_has_type(expected_ty, argument_expr);
+        }
+        signature.output
+    }
+
+    /// Helper for `check_expression`: Compute the type from constructing a struct.
+    fn compute_aggregate_ty(
+        &mut self,
+        expression: hir::Expression,
+        entity: DefId,
+        fields: Arc<Vec<(hir::Identifier, hir::Expression)>>,
+    ) -> Ty<F> {
+        let members = self.db.members(entity);
+        let expected = members
+            .iter()
+            .filter(|member| member.kind == hir::MemberKind::Field)
+            .count();
+        if expected != fields.len() {
+            self.results.record_error(
+                expression,
+                ErrorKind::WrongNumberOfFields {
+                    expected,
+                    found: fields.len(),
+                },
+            );
+        }
+        let generics = self.inference_variables_for(entity);
+        for &(identifier, value) in fields.iter() {
+            let text = self.hir[identifier].text;
+            match self
+                .db
+                .member_def_id((entity, hir::MemberKind::Field, text))
+            {
+                Some(field_def_id) => {
+                    let field_decl_ty = self.db.ty(field_def_id);
+                    let field_ty = self.substitute(value, &generics, field_decl_ty);
+                    self.check_expression_has_type(field_ty, value);
+                }
+
+                None => {
+                    self.results
+                        .record_error(identifier, ErrorKind::UnknownMember { name: text });
+                    self.check_expression(value);
+                }
+            }
+        }
+        let entity_ty = self.db.ty(entity);
+        self.substitute(expression, &generics, entity_ty)
+    }
+
     /// Type-check `place`, recording and returning the resulting type (which may be
     /// an inference variable).
     fn check_place(&mut self, place: hir::Place) -> Ty<F> {
@@ -123,12 +441,18 @@ where
                                 let field_ty = this.substitute(place, &generics, field_decl_ty);
                                 this.apply_owner_perm(place, owner_ty.perm, field_ty)
                             } else {
-                                this.results.record_error(place);
+                                this.results
+                                    .record_error(place, ErrorKind::UnknownMember { name: text });
                                 this.error_type()
                             }
                         }
 
-                        BaseKind::Placeholder(_placeholder) => unimplemented!(),
+                        // Type parameters have no fields.
+                        BaseKind::Placeholder(_) => {
+                            this.results
+                                .record_error(place, ErrorKind::UnknownMember { name: text });
+                            this.error_type()
+                        }
 
                         BaseKind::Error => this.error_type(),
                     }
@@ -159,9 +483,9 @@ where
         base_data: BaseData<F>,
     ) -> Ty<F> {
         let BaseData { kind, generics } = base_data;
+        let text = self.hir[method_name].text;
         match kind {
             BaseKind::Named(def_id) => {
-                let text = self.hir[method_name].text;
                 let method_def_id =
                     match self
                         .db()
@@ -169,28 +493,73 @@ where
                     {
                         Some(def_id) => def_id,
                         None => {
-                            self.results.record_error(expression);
+                            self.results
+                                .record_error(expression, ErrorKind::UnknownMember { name: text });
                             return self.error_type();
                         }
                     };
 
-                // FIXME -- what role does `owner_ty` place here??
+                // The method's own type parameters follow those of
+                // the owner, which we already know from its type.
+                let mut generics = generics;
+                let declarations = self.db().generic_declarations(method_def_id);
+                generics.extend(
+                    declarations
+                        .declarations
+                        .indices()
+                        .map(|_| GenericKind::Ty(self.new_infer_ty())),
+                );
 
+                // The first input is the `self` parameter, whose type
+                // is the type of the owner. (Methods without one are
+                // reported by `item_errors`.)
                 let signature_decl = self.db().signature(method_def_id);
                 let signature = self.substitute(expression, &generics, signature_decl);
-                if signature.inputs.len() != arguments.len() {
-                    self.results.record_error(expression);
+                let inputs = signature.inputs.get(1..).unwrap_or(&[]);
+                if inputs.len() != arguments.len() {
+                    self.results.record_error(
+                        expression,
+                        ErrorKind::WrongNumberOfArguments {
+                            expected: inputs.len(),
+                            found: arguments.len(),
+                        },
+                    );
                 }
-                for (&expected_ty, &argument_expr) in signature.inputs.iter().zip(arguments.iter())
-                {
+                for (&expected_ty, &argument_expr) in inputs.iter().zip(arguments.iter()) {
                     self.check_expression_has_type(expected_ty, argument_expr);
                 }
                 signature.output
             }
 
-            BaseKind::Placeholder(_placeholder) => unimplemented!(),
+            // Type parameters have no methods.
+            BaseKind::Placeholder(_) => {
+                self.results
+                    .record_error(expression, ErrorKind::UnknownMember { name: text });
+                self.error_type()
+            }
 
             BaseKind::Error => self.error_type(),
         }
     }
 }
+
+/// The types whose values can be compared, and embedded in strings.
+fn is_ordered(builtin: BuiltinType) -> bool {
+    builtin != BuiltinType::Unit
+}
+
+/// The types that support arithmetic.
+fn is_number(builtin: BuiltinType) -> bool {
+    match builtin {
+        BuiltinType::I32 | BuiltinType::U32 | BuiltinType::F64 => true,
+        BuiltinType::Unit | BuiltinType::Bool | BuiltinType::String => false,
+    }
+}
+
+/// The types that support negation.
+fn is_signed(builtin: BuiltinType) -> bool {
+    match builtin {
+        BuiltinType::I32 | BuiltinType::F64 => true,
+        BuiltinType::Unit | BuiltinType::Bool | BuiltinType::U32 | BuiltinType::String => false,
+    }
+}
@@ -8,12 +8,14 @@ use hir;
 use indices::IndexVec;
 use intern::Has;
 use map::FxIndexMap;
-use mir::DefId;
+use ast::def_id::DefId;
 use std::sync::Arc;
+use parser::StringId;
 use ty::base_inferred::BaseInferred;
 use ty::declaration::Declaration;
 use ty::interners::TyInternTables;
 use ty::map_family::Map;
+use ty::BaseKind;
 use ty::Generics;
 use ty::Placeholder;
 use ty::Ty;
@@ -24,9 +26,13 @@ use unify::Inferable;
 use unify::UnificationTable;
 
 mod base_only;
+pub mod diagnostic;
 mod hir_typeck;
+mod match_check;
 mod ops;
+pub mod pretty;
 mod query_definitions;
+mod resolve_to_base_inferred;
 mod substitute;
 
 salsa::query_group! {
@@ -49,6 +55,10 @@ struct TypeChecker<'db, DB: TypeCheckDatabase, F: TypeCheckFamily> {
     unify: UnificationTable<TyInternTables, hir::MetaIndex>,
     results: TypeCheckResults<F>,
 
+    /// The placeholders for the type parameters in scope in the fn
+    /// body, which its signature and type annotations refer to.
+    fn_generics: Generics<F>,
+
     /// Information about each universe that we have created.
     universe_binders: IndexVec<Universe, UniverseBinder>,
 }
@@ -65,6 +75,9 @@ trait TypeCheckFamily: TypeFamily<Placeholder = Placeholder> {
 
     fn new_infer_ty(this: &mut impl TypeCheckerFields<Self>) -> Ty<Self>;
 
+    /// Requires `ty1` and `ty2` to be equal. For error reporting,
+    /// `ty1` is taken to be the type that was found and `ty2` the
+    /// type that was expected.
     fn equate_types(
         this: &mut impl TypeCheckerFields<Self>,
         cause: hir::MetaIndex,
@@ -167,9 +180,20 @@ impl<F: TypeFamily> TypeCheckResults<F> {
         self.types[&index.into()]
     }
 
-    fn record_error(&mut self, location: impl Into<hir::MetaIndex>) {
+    /// Each part of the HIR whose type was recorded, with its type.
+    pub fn types(&self) -> impl Iterator<Item = (hir::MetaIndex, Ty<F>)> + '_ {
+        self.types.iter().map(|(&index, &ty)| (index, ty))
+    }
+
+    /// The type errors that were found.
+    pub fn errors(&self) -> &[Error] {
+        &self.errors
+    }
+
+    fn record_error(&mut self, location: impl Into<hir::MetaIndex>, kind: ErrorKind) {
         self.errors.push(Error {
             location: location.into(),
+            kind,
         });
     }
 }
@@ -184,8 +208,56 @@ impl<F: TypeFamily> Default for TypeCheckResults<F> {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
-crate struct Error {
-    location: hir::MetaIndex,
+pub struct Error {
+    /// The part of the HIR where the error was detected.
+    pub location: hir::MetaIndex,
+
+    /// What went wrong.
+    pub kind: ErrorKind,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// The type found at the location is not the one expected there.
+    Mismatch {
+        expected: BaseKind<BaseInferred>,
+        found: BaseKind<BaseInferred>,
+
+        /// Where the expected type was inferred from, if it was
+        /// inferred.
+        expected_from: Option<hir::MetaIndex>,
+    },
+
+    /// A function was called with the wrong number of arguments.
+    WrongNumberOfArguments { expected: usize, found: usize },
+
+    /// A struct was constructed with the wrong number of fields.
+    WrongNumberOfFields { expected: usize, found: usize },
+
+    /// The owner has no field or method with this name.
+    UnknownMember { name: StringId },
+
+    /// Inference did not determine the type.
+    CannotInfer,
+
+    /// A `match` with no arm for some values, like the one described
+    /// by `missing`.
+    NonExhaustive { missing: StringId },
+
+    /// A `match` arm whose pattern only matches values that the arms
+    /// before it already do.
+    UnreachableArm,
+
+    /// A value embedded in a string whose type cannot be shown as
+    /// text, like a struct.
+    CannotInterpolate { found: BaseKind<BaseInferred> },
+
+    /// An operator, like `*`, applied to a type that does not support
+    /// it.
+    UnsupportedOperator {
+        operator: &'static str,
+        found: BaseKind<BaseInferred>,
+    },
 }
 
 impl<DB, F> Has<TyInternTables> for TypeChecker<'_, DB, F>
@@ -0,0 +1,335 @@
+//! Checks that each `match` covers every value it could be given, and
+//! that each of its arms matches some value that the arms before it do
+//! not. Both come down to whether a pattern is *useful* after a list of
+//! others -- whether it matches a value that none of them does -- which
+//! we answer as in Maranget's "Warnings for pattern matching".
+
+use crate::Error;
+use crate::ErrorKind;
+use crate::TypeCheckDatabase;
+use ast::ast as a;
+use ast::def_id::{DefId, DefIdData};
+use hir;
+use intern::Untern;
+use std::collections::BTreeMap;
+use ty::base_inferred::BaseInferred;
+use ty::{BaseKind, Ty};
+
+/// Checks each `match` in `fn_body`, whose types are `types`, adding
+/// the errors found to `errors`.
+crate fn check_matches(
+    db: &impl TypeCheckDatabase,
+    fn_body: &hir::FnBody,
+    types: &BTreeMap<hir::MetaIndex, Ty<BaseInferred>>,
+    errors: &mut Vec<Error>,
+) {
+    for expression in fn_body.expressions.iter() {
+        if let hir::ExpressionData::Match { scrutinee, arms } = &expression.node {
+            let scrutinee_ty = types[&hir::MetaIndex::from(*scrutinee)];
+            check_match(db, fn_body, *scrutinee, scrutinee_ty, arms, errors);
+        }
+    }
+}
+
+fn check_match(
+    db: &impl TypeCheckDatabase,
+    fn_body: &hir::FnBody,
+    scrutinee: hir::Place,
+    scrutinee_ty: Ty<BaseInferred>,
+    arms: &[hir::MatchArm],
+    errors: &mut Vec<Error>,
+) {
+    let mut rows: Vec<Vec<Pat>> = vec![];
+    for arm in arms {
+        let row = vec![Pat::from_hir(db, fn_body, arm.pattern)];
+        if !is_useful(db, &rows, &row) {
+            errors.push(Error {
+                location: arm.pattern.into(),
+                kind: ErrorKind::UnreachableArm,
+            });
+        }
+        rows.push(row);
+    }
+
+    // An enum without variants has no values, so a `match` on one
+    // needs no arms.
+    if arms.is_empty() && is_empty_enum(db, scrutinee_ty) {
+        return;
+    }
+
+    if let Some(witness) = witness(db, &rows, 1) {
+        let missing = db.intern_string(witness[0].describe(db).as_str());
+        errors.push(Error {
+            location: scrutinee.into(),
+            kind: ErrorKind::NonExhaustive { missing },
+        });
+    }
+}
+
+/// True if `ty` is an enum without variants, which has no values.
+fn is_empty_enum(db: &impl TypeCheckDatabase, ty: Ty<BaseInferred>) -> bool {
+    let def_id = match ty.base.untern(db).kind {
+        BaseKind::Named(def_id) => def_id,
+        BaseKind::Placeholder(_) | BaseKind::Error => return false,
+    };
+
+    match def_id.untern(db) {
+        DefIdData::Item(item_id) => match &*db.ast_of_item(item_id) {
+            a::Item::Enum(e) => e.variants.is_empty(),
+            a::Item::Struct(_) | a::Item::Def(_) | a::Item::Mod(_) | a::Item::Use(_) => false,
+        },
+        _ => false,
+    }
+}
+
+/// A pattern, reduced to what matters for usefulness.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Pat {
+    /// Matches anything: `_`, a binding, or a pattern with errors.
+    Wild,
+
+    /// Matches the values made by `Ctor` whose fields match the
+    /// patterns, one for each field in declaration order.
+    Ctor(Ctor, Vec<Pat>),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Ctor {
+    /// A struct or a variant.
+    Entity(DefId),
+
+    Bool(bool),
+
+    /// Any other literal. Their types have too many values to list.
+    Literal(hir::LiteralData),
+}
+
+impl Pat {
+    fn from_hir(db: &impl TypeCheckDatabase, fn_body: &hir::FnBody, pattern: hir::Pattern) -> Pat {
+        match &fn_body[pattern] {
+            hir::PatternData::Wildcard
+            | hir::PatternData::Binding { .. }
+            | hir::PatternData::Error { .. } => Pat::Wild,
+
+            hir::PatternData::Literal { data } => match data.kind {
+                hir::LiteralKind::Bool => {
+                    Pat::Ctor(Ctor::Bool(&*db.untern_string(data.value) == "true"), vec![])
+                }
+                _ => Pat::Ctor(Ctor::Literal(*data), vec![]),
+            },
+
+            hir::PatternData::Constructor { entity, fields } => {
+                let fields = field_names(db, *entity)
+                    .into_iter()
+                    .map(|name| {
+                        let field = fields
+                            .iter()
+                            .find(|&&(identifier, _)| fn_body[identifier].text == name);
+                        match field {
+                            Some(&(_, pattern)) => Pat::from_hir(db, fn_body, pattern),
+                            None => Pat::Wild,
+                        }
+                    })
+                    .collect();
+                Pat::Ctor(Ctor::Entity(*entity), fields)
+            }
+        }
+    }
+
+    /// The pattern as the user would write it, like
+    /// `Shape::Circle(_)`.
+    fn describe(&self, db: &impl TypeCheckDatabase) -> String {
+        let (ctor, fields) = match self {
+            Pat::Wild => return "_".to_string(),
+            Pat::Ctor(ctor, fields) => (ctor, fields),
+        };
+
+        let entity = match ctor {
+            Ctor::Bool(value) => return value.to_string(),
+            Ctor::Literal(data) => match data.kind {
+                hir::LiteralKind::String => return format!("{:?}", db.untern_string(data.value)),
+                _ => return db.untern_string(data.value).to_string(),
+            },
+            Ctor::Entity(entity) => *entity,
+        };
+
+        let name = match entity.untern(db) {
+            DefIdData::Variant { owner, name } => format!(
+                "{}::{}",
+                crate::diagnostic::def_id_name(db, owner),
+                db.untern_string(name)
+            ),
+            _ => crate::diagnostic::def_id_name(db, entity),
+        };
+
+        let names = field_names(db, entity);
+        let fields: Vec<_> = fields.iter().map(|field| field.describe(db)).collect();
+        match names.first() {
+            None => name,
+
+            // The fields of a tuple variant are named by position.
+            Some(&first) if &*db.untern_string(first) == "0" => {
+                format!("{}({})", name, fields.join(", "))
+            }
+
+            Some(_) => {
+                let fields: Vec<_> = names
+                    .iter()
+                    .zip(fields)
+                    .map(|(&name, field)| format!("{}: {}", db.untern_string(name), field))
+                    .collect();
+                format!("{} {{ {} }}", name, fields.join(", "))
+            }
+        }
+    }
+}
+
+/// The names of the fields of the struct or variant `entity`.
+fn field_names(db: &impl TypeCheckDatabase, entity: DefId) -> Vec<parser::StringId> {
+    db.members(entity)
+        .iter()
+        .filter(|member| member.kind == hir::MemberKind::Field)
+        .map(|member| member.name)
+        .collect()
+}
+
+fn arity(db: &impl TypeCheckDatabase, ctor: Ctor) -> usize {
+    match ctor {
+        Ctor::Entity(entity) => field_names(db, entity).len(),
+        Ctor::Bool(_) | Ctor::Literal(_) => 0,
+    }
+}
+
+/// Every constructor of the type that `ctor` constructs, or `None`
+/// if there are too many to list.
+fn all_ctors(db: &impl TypeCheckDatabase, ctor: Ctor) -> Option<Vec<Ctor>> {
+    match ctor {
+        Ctor::Entity(entity) => match entity.untern(db) {
+            DefIdData::Variant { owner, .. } => Some(
+                db.members(owner)
+                    .iter()
+                    .filter(|member| member.kind == hir::MemberKind::Variant)
+                    .map(|member| Ctor::Entity(member.def_id))
+                    .collect(),
+            ),
+            _ => Some(vec![ctor]),
+        },
+        Ctor::Bool(_) => Some(vec![Ctor::Bool(true), Ctor::Bool(false)]),
+        Ctor::Literal(_) => None,
+    }
+}
+
+/// The constructors that the first patterns of `rows` use, in order.
+fn head_ctors(rows: &[Vec<Pat>]) -> Vec<Ctor> {
+    let mut ctors = vec![];
+    for row in rows {
+        if let Pat::Ctor(ctor, _) = &row[0] {
+            if !ctors.contains(ctor) {
+                ctors.push(*ctor);
+            }
+        }
+    }
+    ctors
+}
+
+/// All the constructors of the type, if `used` includes each of them.
+fn complete_ctors(db: &impl TypeCheckDatabase, used: &[Ctor]) -> Option<Vec<Ctor>> {
+    let all = all_ctors(db, *used.first()?)?;
+    if all.iter().all(|ctor| used.contains(ctor)) {
+        Some(all)
+    } else {
+        None
+    }
+}
+
+/// The rows that match a value made by `ctor`, with their first
+/// pattern replaced by the patterns for that value's fields.
+fn specialize(db: &impl TypeCheckDatabase, rows: &[Vec<Pat>], ctor: Ctor) -> Vec<Vec<Pat>> {
+    rows.iter()
+        .filter_map(|row| {
+            let mut fields = match &row[0] {
+                Pat::Ctor(head, fields) if *head == ctor => fields.clone(),
+                Pat::Ctor(..) => return None,
+                Pat::Wild => vec![Pat::Wild; arity(db, ctor)],
+            };
+            fields.extend(row[1..].iter().cloned());
+            Some(fields)
+        })
+        .collect()
+}
+
+/// The rows whose first pattern matches anything, without it.
+fn default_rows(rows: &[Vec<Pat>]) -> Vec<Vec<Pat>> {
+    rows.iter()
+        .filter(|row| row[0] == Pat::Wild)
+        .map(|row| row[1..].to_vec())
+        .collect()
+}
+
+/// True if some values match `row` but none of `rows`.
+fn is_useful(db: &impl TypeCheckDatabase, rows: &[Vec<Pat>], row: &[Pat]) -> bool {
+    let (first, rest) = match row.split_first() {
+        Some(pair) => pair,
+        None => return rows.is_empty(),
+    };
+
+    // `row` with its first pattern replaced by `fields`.
+    let specialized_row = |fields: Vec<Pat>| {
+        let mut row = fields;
+        row.extend(rest.iter().cloned());
+        row
+    };
+
+    match first {
+        Pat::Ctor(ctor, fields) => is_useful(
+            db,
+            &specialize(db, rows, *ctor),
+            &specialized_row(fields.clone()),
+        ),
+
+        Pat::Wild => match complete_ctors(db, &head_ctors(rows)) {
+            Some(all) => all.into_iter().any(|ctor| {
+                let fields = vec![Pat::Wild; arity(db, ctor)];
+                is_useful(db, &specialize(db, rows, ctor), &specialized_row(fields))
+            }),
+            None => is_useful(db, &default_rows(rows), rest),
+        },
+    }
+}
+
+/// Some `width` patterns that match values none of `rows` match, if
+/// there are any.
+fn witness(db: &impl TypeCheckDatabase, rows: &[Vec<Pat>], width: usize) -> Option<Vec<Pat>> {
+    if width == 0 {
+        return if rows.is_empty() { Some(vec![]) } else { None };
+    }
+
+    let used = head_ctors(rows);
+    if let Some(all) = complete_ctors(db, &used) {
+        return all
+            .into_iter()
+            .filter_map(|ctor| {
+                let arity = arity(db, ctor);
+                let mut fields = witness(db, &specialize(db, rows, ctor), arity + width - 1)?;
+                let rest = fields.split_off(arity);
+                let mut result = vec![Pat::Ctor(ctor, fields)];
+                result.extend(rest);
+                Some(result)
+            })
+            .next();
+    }
+
+    let mut result = witness(db, &default_rows(rows), width - 1)?;
+
+    // Name a constructor that no row uses, if we can list them.
+    let missing = used
+        .first()
+        .and_then(|&ctor| all_ctors(db, ctor))
+        .and_then(|all| all.into_iter().find(|ctor| !used.contains(ctor)));
+    let head = match missing {
+        Some(ctor) => Pat::Ctor(ctor, vec![Pat::Wild; arity(db, ctor)]),
+        None => Pat::Wild,
+    };
+    result.insert(0, head);
+    Some(result)
+}
@@ -2,7 +2,7 @@ use crate::TypeCheckFamily;
 use crate::TypeChecker;
 use crate::UniverseBinder;
 use hir;
-use mir::DefId;
+use ast::def_id::DefId;
 use ty::declaration::Declaration;
 use ty::interners::TyInternTables;
 use ty::map_family::Map;
@@ -141,6 +141,29 @@ where
         generics
     }
 
+    /// Fresh inference variables for the generic parameters of
+    /// `def_id`, including those it inherits from its parent, for use
+    /// where `def_id` is referenced (e.g., at a call).
+    pub(super) fn inference_variables_for(&mut self, def_id: DefId) -> Generics<F> {
+        let GenericDeclarations {
+            parent_item,
+            declarations,
+        } = &*self.db.generic_declarations(def_id);
+
+        let mut generics = match parent_item {
+            Some(def_id) => self.inference_variables_for(*def_id),
+            None => Generics::empty(),
+        };
+
+        generics.extend(
+            declarations
+                .indices()
+                .map(|_| GenericKind::Ty(self.new_infer_ty())),
+        );
+
+        generics
+    }
+
     /// Create a fresh universe (one that did not exist before) with
     /// the given binder. This universe will be able to see names
     /// from all previously existing universes.
@@ -0,0 +1,62 @@
+//! Renders types as the user would write them, like `Foo<i32>`.
+
+use crate::diagnostic::def_id_name;
+use hir::HirDatabase;
+use intern::Untern;
+use ty::base_inferred::BaseInferred;
+use ty::declaration::Declaration;
+use ty::{BaseData, BaseKind, BoundVarOr, GenericKind, Signature, Ty, TypeFamily};
+
+pub trait Pretty {
+    fn pretty(&self, db: &impl HirDatabase) -> String;
+}
+
+impl Pretty for Ty<BaseInferred> {
+    fn pretty(&self, db: &impl HirDatabase) -> String {
+        base_data(db, &self.base.untern(db))
+    }
+}
+
+impl Pretty for Ty<Declaration> {
+    fn pretty(&self, db: &impl HirDatabase) -> String {
+        match self.base.untern(db) {
+            BoundVarOr::BoundVar(_) => "<generic>".to_string(),
+            BoundVarOr::Known(data) => base_data(db, &data),
+        }
+    }
+}
+
+/// Renders a signature like `(i32, String) -> ()`.
+impl<F: TypeFamily> Pretty for Signature<F>
+where
+    Ty<F>: Pretty,
+{
+    fn pretty(&self, db: &impl HirDatabase) -> String {
+        let inputs: Vec<String> = self.inputs.iter().map(|input| input.pretty(db)).collect();
+        format!("({}) -> {}", inputs.join(", "), self.output.pretty(db))
+    }
+}
+
+fn base_data<F: TypeFamily>(db: &impl HirDatabase, data: &BaseData<F>) -> String
+where
+    Ty<F>: Pretty,
+{
+    let mut text = match data.kind {
+        BaseKind::Named(def_id) => def_id_name(db, def_id),
+        BaseKind::Placeholder(_) => "<generic>".to_string(),
+        BaseKind::Error => "<error>".to_string(),
+    };
+
+    if data.generics.is_not_empty() {
+        let generics: Vec<String> = data
+            .generics
+            .iter()
+            .map(|generic| match generic {
+                GenericKind::Ty(ty) => ty.pretty(db),
+            })
+            .collect();
+        text.push_str(&format!("<{}>", generics.join(", ")));
+    }
+
+    text
+}
@@ -1,3 +1,7 @@
+use crate::match_check;
+use crate::resolve_to_base_inferred::Resolver;
+use crate::Error;
+use crate::ErrorKind;
 use crate::TypeCheckDatabase;
 use crate::TypeCheckResults;
 use crate::TypeChecker;
@@ -5,10 +9,12 @@ use crate::UniverseBinder;
 use generational_arena::Arena;
 use indices::IndexVec;
 use map::FxIndexMap;
-use mir::DefId;
+use ast::def_id::DefId;
 use ty::base_inferred::BaseInferred;
 use ty::base_only::BaseOnly;
 use ty::interners::TyInternTables;
+use ty::map_family::Map;
+use ty::Generics;
 use unify::InferVar;
 use unify::UnificationTable;
 
@@ -17,6 +23,7 @@ crate fn base_type_check(
     fn_def_id: DefId,
 ) -> TypeCheckResults<BaseInferred> {
     let fn_body = db.fn_body(fn_def_id);
+    let hir = fn_body.clone();
     let interners: &TyInternTables = db.intern_tables();
     let mut base_type_checker: TypeChecker<'_, _, BaseOnly> = TypeChecker {
         db,
@@ -26,6 +33,7 @@ crate fn base_type_check(
         ops_blocked: FxIndexMap::default(),
         unify: UnificationTable::new(interners.clone()),
         results: TypeCheckResults::default(),
+        fn_generics: Generics::empty(),
         universe_binders: IndexVec::from(vec![UniverseBinder::Root]),
     };
     base_type_checker.check_fn_body();
@@ -40,5 +48,31 @@ crate fn base_type_check(
         }
     }
 
-    unimplemented!()
+    let TypeChecker {
+        mut unify, results, ..
+    } = base_type_checker;
+    let TypeCheckResults { types, mut errors } = results;
+    let mut resolver = Resolver::new(db, &mut unify);
+    let types = types
+        .into_iter()
+        .map(|(index, ty)| {
+            resolver.unresolved = false;
+            let ty = ty.map(&mut resolver);
+            if resolver.unresolved {
+                errors.push(Error {
+                    location: index,
+                    kind: ErrorKind::CannotInfer,
+                });
+            }
+            (index, ty)
+        })
+        .collect();
+
+    // Patterns of the wrong type would confuse the checks on `match`,
+    // so they wait until everything else is right.
+    if errors.is_empty() && hir.errors.is_empty() {
+        match_check::check_matches(db, &hir, &types, &mut errors);
+    }
+
+    TypeCheckResults { types, errors }
 }
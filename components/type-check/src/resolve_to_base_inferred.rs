@@ -0,0 +1,70 @@
+use intern::Has;
+use ty::base_inferred::BaseInferred;
+use ty::base_only::BaseOnly;
+use ty::interners::TyInternTables;
+use ty::map_family::FamilyMapper;
+use ty::map_family::Map;
+use ty::BaseData;
+use ty::BaseKind;
+use ty::Erased;
+use ty::Generics;
+use ty::Placeholder;
+use ty::Ty;
+use ty::TypeFamily;
+use unify::UnificationTable;
+
+/// Maps types from the `BaseOnly` family, as produced during
+/// inference, into the fully resolved `BaseInferred` family.
+/// Inference variables that were never constrained become the error
+/// type; `unresolved` records whether we encountered any.
+crate struct Resolver<'me> {
+    intern_tables: TyInternTables,
+    unify: &'me mut UnificationTable<TyInternTables, hir::MetaIndex>,
+    crate unresolved: bool,
+}
+
+impl Resolver<'me> {
+    crate fn new(
+        intern_tables: &dyn Has<TyInternTables>,
+        unify: &'me mut UnificationTable<TyInternTables, hir::MetaIndex>,
+    ) -> Self {
+        Resolver {
+            intern_tables: intern_tables.intern_tables().clone(),
+            unify,
+            unresolved: false,
+        }
+    }
+}
+
+impl Has<TyInternTables> for Resolver<'_> {
+    fn intern_tables(&self) -> &TyInternTables {
+        &self.intern_tables
+    }
+}
+
+impl FamilyMapper<BaseOnly, BaseInferred> for Resolver<'_> {
+    fn map_ty(&mut self, ty: Ty<BaseOnly>) -> Ty<BaseInferred> {
+        let Ty { perm: Erased, base } = ty;
+
+        let base_data = match self.unify.shallow_resolve_data(base) {
+            Ok(base_data) => base_data.map(self),
+
+            Err(_) => {
+                self.unresolved = true;
+                BaseData {
+                    kind: BaseKind::Error,
+                    generics: Generics::empty(),
+                }
+            }
+        };
+
+        Ty {
+            perm: Erased,
+            base: BaseInferred::intern_base_data(self, base_data),
+        }
+    }
+
+    fn map_placeholder(&mut self, placeholder: Placeholder) -> Placeholder {
+        placeholder
+    }
+}
@@ -215,17 +215,63 @@ impl<Interners, Cause> UnificationTable<Interners, Cause> {
             }
 
             (Err(var1), Ok(_)) => {
-                self.bind_unbound_var_to_value(cause, var1, Value::cast_from(key2));
+                let value2 = self.known_value(key2);
+                self.bind_unbound_var_to_value(cause, var1, value2);
                 Ok(())
             }
 
             (Ok(_), Err(var2)) => {
-                self.bind_unbound_var_to_value(cause, var2, Value::cast_from(key1));
+                let value1 = self.known_value(key1);
+                self.bind_unbound_var_to_value(cause, var2, value1);
                 Ok(())
             }
         }
     }
 
+    /// If `value` is an inference variable that has been bound, returns
+    /// the cause of the unification that gave it its value. This is a
+    /// best-effort answer, meant for error reporting.
+    pub fn value_cause<K>(&self, value: K) -> Option<Cause>
+    where
+        K: Inferable<Interners>,
+        Cause: Clone,
+    {
+        let mut var = value.as_infer_var(&self.interners)?;
+        loop {
+            match &self.trace[var] {
+                Some(UnificationTrace {
+                    cause,
+                    other_variable: None,
+                }) => return Some(cause.clone()),
+
+                Some(UnificationTrace {
+                    other_variable: Some(other),
+                    ..
+                }) => var = *other,
+
+                None => match self.infers[var] {
+                    InferData::Redirect(parent) => var = parent,
+                    InferData::Unbound(_) | InferData::Value(_) => return None,
+                },
+            }
+        }
+    }
+
+    /// The value that `key` is known to have: `key` itself, or, if it
+    /// is an inference variable, the value it is bound to (we must not
+    /// bind a variable to another variable as if it were a value).
+    fn known_value<K>(&mut self, key: K) -> Value
+    where
+        K: Inferable<Interners>,
+    {
+        match key.as_infer_var(&self.interners) {
+            Some(var) => self
+                .probe(var)
+                .unwrap_or_else(|| panic!("{:?} is not bound", var)),
+            None => Value::cast_from(key),
+        }
+    }
+
     /// Creates a new inference variable.
     fn new_infer_var(&mut self) -> InferVar {
         self.trace.push(None);
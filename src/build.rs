@@ -0,0 +1,343 @@
+use ast::def_id::{DefId, DefIdData};
+use ast::{AstDatabase, HasParserState};
+use codespan::{ByteIndex, ByteSpan, CodeMap, FileMap, FileName};
+use codespan_reporting::{Diagnostic, Label};
+use database::check::CheckErrorKind;
+use database::LarkDatabase;
+use hir::HirDatabase;
+use intern::Untern;
+use mir_build::LoweredItems;
+use parser::pos::Span;
+use parser::StringId;
+use std::path::{Path, PathBuf};
+use termcolor::{ColorChoice, StandardStream};
+use type_check::TypeCheckDatabase;
+
+/// `lark build [--out-dir <dir>] <file>`: compiles the file into a
+/// Rust crate under `<dir>/<name>`, where `<dir>` is `target/lark`
+/// unless given, or reports errors and exits.
+crate fn build(args: &[String]) {
+    let (filename, out_dir) = match build_args(args) {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            std::process::exit(1);
+        }
+    };
+
+    let (db, codemap, path) = load(filename);
+
+    let lowered = match compile(&db, &codemap, path) {
+        Ok(lowered) => lowered,
+        Err(diagnostics) => report_and_exit(&codemap, &diagnostics),
+    };
+
+    let mut rust = codegen::RustFile::new();
+    codegen::codegen(&mut rust, &lowered.context);
+
+    let crate_name = crate_name(filename);
+    let output_dir = out_dir.join(&crate_name);
+    let result = write_crate(&output_dir, &crate_name, &rust.render());
+    if let Err(err) = result {
+        eprintln!("error: failed to write `{}`: {}", output_dir.display(), err);
+        std::process::exit(1);
+    }
+
+    println!("wrote `{}`", output_dir.display());
+}
+
+/// The file to compile and the directory to write its crate under,
+/// from the arguments of `lark build`.
+crate fn build_args(args: &[String]) -> Result<(&str, PathBuf), String> {
+    let mut filename = None;
+    let mut out_dir = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--out-dir" {
+            match args.next() {
+                Some(dir) => out_dir = Some(PathBuf::from(dir)),
+                None => return Err("`--out-dir` needs a directory".to_string()),
+            }
+        } else if filename.is_none() {
+            filename = Some(&arg[..]);
+        } else {
+            return Err(format!("unexpected argument `{}`", arg));
+        }
+    }
+
+    match filename {
+        Some(filename) => {
+            let out_dir = out_dir.unwrap_or_else(|| PathBuf::from("target").join("lark"));
+            Ok((filename, out_dir))
+        }
+        None => Err("no file to build".to_string()),
+    }
+}
+
+/// Reads `filename`, and the files of the modules it declares, into a
+/// fresh database, exiting if `filename` cannot be read. A module whose
+/// file is missing is reported when the program is compiled.
+crate fn load(filename: &str) -> (LarkDatabase, CodeMap, StringId) {
+    let text = match std::fs::read_to_string(filename) {
+        Ok(text) => text,
+        Err(err) => {
+            eprintln!("error: failed to read `{}`: {}", filename, err);
+            std::process::exit(1);
+        }
+    };
+
+    let db = LarkDatabase::default();
+    let path = db.add_file(filename, &text);
+
+    let mut codemap = CodeMap::new();
+    codemap.add_filemap(FileName::from(PathBuf::from(filename)), text);
+
+    let mut pending = vec![path];
+    while let Some(file) = pending.pop() {
+        for &item_id in db.items_in_file(file).iter() {
+            let name = match &*db.ast_of_item(item_id) {
+                ast::ast::Item::Mod(m) => m.name.node,
+                _ => continue,
+            };
+            let candidates =
+                hir::module_file_candidates(&db.untern_string(file), &db.untern_string(name));
+            for candidate in candidates {
+                if db.input_files(()).contains(&db.intern_string(candidate.as_str())) {
+                    break;
+                }
+                if let Ok(text) = std::fs::read_to_string(&candidate) {
+                    pending.push(db.add_file(&candidate, &text));
+                    codemap.add_filemap(FileName::from(PathBuf::from(candidate)), text);
+                    break;
+                }
+            }
+        }
+    }
+
+    (db, codemap, path)
+}
+
+/// The files of the program whose root is the file `path`: `path`
+/// itself, followed by the files of the modules it declares, and so on.
+fn program_files(db: &LarkDatabase, path: StringId) -> Vec<StringId> {
+    let mut files = vec![path];
+    let mut index = 0;
+    while index < files.len() {
+        for &def_id in db.defs_in_file(files[index]).iter() {
+            if let Some(file) = module_file(db, def_id) {
+                if !files.contains(&file) {
+                    files.push(file);
+                }
+            }
+        }
+        index += 1;
+    }
+    files
+}
+
+/// The file of the module `def_id`, if it is a `mod` item whose file
+/// was found.
+fn module_file(db: &LarkDatabase, def_id: DefId) -> Option<StringId> {
+    match def_id.untern(db) {
+        DefIdData::Item(item_id) => match &*db.ast_of_item(item_id) {
+            ast::ast::Item::Mod(_) => db.module_file(def_id),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Runs the whole pipeline on the program whose root is the file
+/// `path` -- the checks that the IDE runs too, then lowering to MIR.
+/// `codemap` must contain the text of each file of the program, under
+/// the same name.
+crate fn compile(
+    db: &LarkDatabase,
+    codemap: &CodeMap,
+    path: StringId,
+) -> Result<LoweredItems, Vec<Diagnostic>> {
+    compile_files(db, codemap, &program_files(db, path))
+}
+
+/// Like `compile`, for the program made of the items in `files`.
+crate fn compile_files(
+    db: &LarkDatabase,
+    codemap: &CodeMap,
+    files: &[StringId],
+) -> Result<LoweredItems, Vec<Diagnostic>> {
+    check_files(db, codemap, files)?;
+    lower_files(db, codemap, files)
+}
+
+/// Runs the checks that the IDE runs too on the items in `files`.
+crate fn check_files(
+    db: &LarkDatabase,
+    codemap: &CodeMap,
+    files: &[StringId],
+) -> Result<(), Vec<Diagnostic>> {
+    let errors = database::check::check(db, files);
+    if !errors.is_empty() {
+        return Err(errors
+            .into_iter()
+            .flat_map(|err| {
+                let filemap = filemap(db, codemap, err.file);
+                match err.kind {
+                    CheckErrorKind::Message { message, span } => {
+                        vec![error(filemap, &message, span)]
+                    }
+                    CheckErrorKind::TypeCheck(diagnostic) => {
+                        type_check_diagnostic(filemap, diagnostic)
+                    }
+                }
+            })
+            .collect());
+    }
+    Ok(())
+}
+
+/// Lowers the items in `files`, which must have passed `check_files`,
+/// to MIR.
+crate fn lower_files(
+    db: &LarkDatabase,
+    codemap: &CodeMap,
+    files: &[StringId],
+) -> Result<LoweredItems, Vec<Diagnostic>> {
+    let items: Vec<DefId> = files
+        .iter()
+        .flat_map(|&file| db.defs_in_file(file).iter().cloned().collect::<Vec<_>>())
+        .collect();
+
+    mir_build::lower_items(db, &items).map_err(|errors| {
+        errors
+            .iter()
+            .map(|err| {
+                let span = match err.def_id.untern(db) {
+                    DefIdData::Item(item_id) => match &*db.ast_of_item(item_id) {
+                        ast::ast::Item::Def(d) => d.name.span,
+                        ast::ast::Item::Struct(s) => s.name.span,
+                        ast::ast::Item::Enum(e) => e.name.span,
+                        ast::ast::Item::Mod(m) => m.name.span,
+                        ast::ast::Item::Use(u) => u.name().span,
+                    },
+                    _ => Span::Synthetic,
+                };
+                let span = span.shifted(database::check::def_offset(db, err.def_id));
+                error(item_filemap(db, codemap, err.def_id), &err.message, span)
+            })
+            .collect()
+    })
+}
+
+/// The file in `codemap` named `path`.
+fn filemap(db: &LarkDatabase, codemap: &'c CodeMap, path: StringId) -> &'c FileMap {
+    let name = db.untern_string(path);
+    codemap
+        .iter()
+        .find(|filemap| match filemap.name() {
+            FileName::Real(real) => real.as_path() == Path::new(&*name),
+            FileName::Virtual(text) => **text == *name,
+        })
+        .unwrap_or_else(|| panic!("no file `{}` in codemap", name))
+}
+
+/// The file in `codemap` that holds the item, variant or field `def_id`.
+fn item_filemap(db: &LarkDatabase, codemap: &'c CodeMap, def_id: DefId) -> &'c FileMap {
+    let mut def_id = def_id;
+    loop {
+        def_id = match def_id.untern(db) {
+            DefIdData::Item(item_id) => {
+                return filemap(db, codemap, item_id.untern(db).input_file)
+            }
+            DefIdData::Variant { owner, .. }
+            | DefIdData::Field { owner, .. }
+            | DefIdData::TypeParameter { owner, .. } => owner,
+            DefIdData::Builtin(_) => panic!("builtin `{:?}` has no file", def_id),
+        };
+    }
+}
+
+/// Prints `diagnostics` to stderr.
+crate fn report(codemap: &CodeMap, diagnostics: &[Diagnostic]) {
+    let mut writer = StandardStream::stderr(ColorChoice::Auto);
+    for diagnostic in diagnostics {
+        codespan_reporting::emit(&mut writer, codemap, diagnostic).unwrap();
+    }
+}
+
+/// Prints `diagnostics` to stderr and exits with a failure status.
+crate fn report_and_exit(codemap: &CodeMap, diagnostics: &[Diagnostic]) -> ! {
+    report(codemap, diagnostics);
+    std::process::exit(1);
+}
+
+/// An error diagnostic labeled with `span`, which is relative to the
+/// start of `filemap`.
+crate fn error(filemap: &FileMap, message: &str, span: Span) -> Diagnostic {
+    let diagnostic = Diagnostic::new_error(message);
+    match byte_span(filemap, span) {
+        Some(byte_span) => diagnostic.with_label(Label::new_primary(byte_span)),
+        None => diagnostic,
+    }
+}
+
+/// Converts a type-check diagnostic for a file in `filemap` into an
+/// error followed by one note for each of its notes.
+fn type_check_diagnostic(
+    filemap: &FileMap,
+    diagnostic: type_check::diagnostic::Diagnostic,
+) -> Vec<Diagnostic> {
+    let labels = diagnostic.labels.iter().filter_map(|label| {
+        let byte_span = byte_span(filemap, label.span)?;
+        let codespan_label = if label.primary {
+            Label::new_primary(byte_span)
+        } else {
+            Label::new_secondary(byte_span)
+        };
+        Some(codespan_label.with_message(label.message.clone()))
+    });
+
+    let error = Diagnostic::new_error(diagnostic.message)
+        .with_code(diagnostic.code)
+        .with_labels(labels);
+
+    std::iter::once(error)
+        .chain(diagnostic.notes.into_iter().map(Diagnostic::new_note))
+        .collect()
+}
+
+/// Converts `span`, which is relative to the start of `filemap`, into
+/// a span within the codemap.
+fn byte_span(filemap: &FileMap, span: Span) -> Option<ByteSpan> {
+    let start = filemap.span().start();
+    match span {
+        Span::Real(span) => Some(ByteSpan::new(
+            start + (span.start() - ByteIndex(0)),
+            start + (span.end() - ByteIndex(0)),
+        )),
+        Span::EOF => Some(ByteSpan::new(filemap.span().end(), filemap.span().end())),
+        Span::Synthetic => None,
+    }
+}
+
+fn crate_name(filename: &str) -> String {
+    let stem = Path::new(filename)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    stem.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+fn write_crate(output_dir: &Path, crate_name: &str, source: &str) -> std::io::Result<()> {
+    std::fs::create_dir_all(output_dir.join("src"))?;
+    std::fs::write(
+        output_dir.join("Cargo.toml"),
+        format!(
+            "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"2018\"\n\n[workspace]\n",
+            crate_name
+        ),
+    )?;
+    std::fs::write(output_dir.join("src").join("main.rs"), source)
+}
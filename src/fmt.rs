@@ -0,0 +1,54 @@
+use crate::build::{error, report};
+use codespan::{CodeMap, FileName};
+use std::path::PathBuf;
+
+/// `lark fmt [--check] <files>`: rewrites each file in `args` in the
+/// canonical layout, or with `--check`, lists the files that are not
+/// in it without changing them. Exits with a failure status if a file
+/// cannot be read or has syntax errors, or, with `--check`, if a file
+/// is not formatted.
+crate fn fmt(args: &[String]) {
+    let check = args.iter().any(|arg| arg == "--check");
+    let mut failed = false;
+
+    for filename in args.iter().filter(|arg| *arg != "--check") {
+        let text = match std::fs::read_to_string(filename) {
+            Ok(text) => text,
+            Err(err) => {
+                eprintln!("error: failed to read `{}`: {}", filename, err);
+                failed = true;
+                continue;
+            }
+        };
+
+        let formatted = match parser::format::format(&text) {
+            Ok(formatted) => formatted,
+            Err(errors) => {
+                let mut codemap = CodeMap::new();
+                let filemap = codemap.add_filemap(FileName::from(PathBuf::from(filename)), text);
+                let diagnostics: Vec<_> = errors
+                    .iter()
+                    .map(|err| error(&filemap, &err.description, err.span))
+                    .collect();
+                report(&codemap, &diagnostics);
+                failed = true;
+                continue;
+            }
+        };
+
+        if formatted == text {
+            continue;
+        }
+        if check {
+            println!("`{}` is not formatted", filename);
+            failed = true;
+        } else if let Err(err) = std::fs::write(filename, formatted) {
+            eprintln!("error: failed to write `{}`: {}", filename, err);
+            failed = true;
+        }
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+}
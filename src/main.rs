@@ -15,25 +15,29 @@
 #![allow(dead_code)]
 #![allow(unused_imports)]
 
+mod build;
+mod fmt;
 mod parser2;
+mod repl;
+mod run;
 mod tests;
 
 use std::{env, io};
 
 use ide::{lsp_serve, LspResponder};
-use task_manager::{Actor, FakeTypeChecker};
+use query_system::QuerySystem;
+use task_manager::Actor;
 
-fn build(_filename: &str) {}
-
-fn run(_filename: &str) {}
-
-fn repl() {}
+use crate::build::build;
+use crate::fmt::fmt;
+use crate::repl::repl;
+use crate::run::run;
 
 fn ide() {
-    let type_checker = FakeTypeChecker::new();
+    let query_system = QuerySystem::new();
     let lsp_responder = LspResponder;
 
-    let task_manager = task_manager::TaskManager::spawn(type_checker, lsp_responder);
+    let task_manager = task_manager::TaskManager::spawn(query_system, lsp_responder);
 
     lsp_serve(task_manager.channel);
     let _ = task_manager.join_handle.join();
@@ -43,16 +47,22 @@ fn main() {
     let mut args = std::env::args();
 
     match (args.next(), args.next(), args.next()) {
-        (_, Some(ref cmd), Some(ref x)) if cmd == "build" => build(x),
+        (_, Some(ref cmd), Some(ref x)) if cmd == "build" => {
+            build(&std::iter::once(x.clone()).chain(args).collect::<Vec<_>>())
+        }
         (_, Some(ref cmd), Some(ref x)) if cmd == "run" => run(x),
+        (_, Some(ref cmd), Some(ref x)) if cmd == "fmt" => {
+            fmt(&std::iter::once(x.clone()).chain(args).collect::<Vec<_>>())
+        }
         (_, Some(ref cmd), None) if cmd == "repl" => repl(),
         (_, Some(ref cmd), None) if cmd == "ide" => ide(),
         _ => {
             println!("Usage:");
-            println!("  lark build <file> - compiles the given file");
-            println!("  lark run <file>   - runs the given file");
-            println!("  lark repl         - REPL/interactive mode");
-            println!("  lark ide          - run the Lark languge server/IDE support");
+            println!("  lark build [--out-dir <dir>] <file> - compiles the given file");
+            println!("  lark run <file>                     - runs the given file");
+            println!("  lark fmt [--check] <files>          - formats the given files in place");
+            println!("  lark repl                           - REPL/interactive mode");
+            println!("  lark ide                            - run the Lark languge server/IDE support");
         }
     }
 }
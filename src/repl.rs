@@ -0,0 +1,304 @@
+use crate::build::{check_files, error, lower_files, report};
+use ast::def_id::DefIdData;
+use ast::{AstDatabase, HasParserState};
+use codespan::{CodeMap, FileName};
+use codespan_reporting::Diagnostic;
+use database::LarkDatabase;
+use eval::Value;
+use hir::HirDatabase;
+use intern::{Intern, Untern};
+use mir_build::LoweredItems;
+use parser::{ModuleTable, StringId, Token, Tokenizer};
+use std::io::{self, BufRead, Write};
+use ty::base_inferred::BaseInferred;
+use ty::{BaseKind, Ty};
+use type_check::pretty::Pretty;
+use type_check::TypeCheckDatabase;
+
+/// The name of the function that each expression entered at the
+/// prompt is wrapped in.
+const EXPRESSION_FN: &str = "repl_expression";
+
+/// `lark repl`: reads items and expressions from stdin, one at a time.
+crate fn repl() {
+    let mut repl = Repl::new();
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    loop {
+        let input = match read_input(&mut lines) {
+            Some(input) => input,
+            None => break,
+        };
+        if input.trim().is_empty() {
+            continue;
+        }
+
+        let input = input.trim_start();
+        if input.trim() == ":items" {
+            for item in repl.items() {
+                println!("{}", item);
+            }
+        } else if input.starts_with(":type ") {
+            if let Some(ty) = repl.type_of(&input[":type ".len()..]) {
+                println!("{}", ty);
+            }
+        } else if ["def ", "struct ", "enum ", "use "].iter().any(|k| input.starts_with(k)) {
+            repl.add_item(input);
+        } else if let Some(value) = repl.evaluate(input) {
+            match value {
+                Value::Void => {}
+                value => println!("{}", value),
+            }
+        }
+    }
+}
+
+/// Reads one input from `lines`, continuing onto further lines while
+/// there are unclosed braces. Braces in strings and comments do not
+/// count. Returns `None` at the end of input.
+crate fn read_input(lines: &mut impl Iterator<Item = io::Result<String>>) -> Option<String> {
+    let mut input = String::new();
+    let mut prompt = "> ";
+    loop {
+        print!("{}", prompt);
+        io::stdout().flush().unwrap();
+
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            Some(Err(_)) | None => return None,
+        };
+        input.push_str(&line);
+        input.push('\n');
+
+        let mut table = ModuleTable::new();
+        let depth: isize = Tokenizer::new(&mut table, &input, 0)
+            .filter_map(|token| token.ok())
+            .map(|(_, token, _)| match token {
+                Token::CurlyBraceOpen => 1,
+                Token::CurlyBraceClose => -1,
+                _ => 0,
+            })
+            .sum();
+        if depth <= 0 {
+            return Some(input);
+        }
+        prompt = "| ";
+    }
+}
+
+/// The state of a REPL session. Each item entered goes into a file of
+/// its own in `db`, and each expression into a scratch file, with the
+/// items entered so far visible from all of them. An entry only sets
+/// the text of its own file, so the database only recomputes what
+/// depends on it.
+crate struct Repl {
+    db: LarkDatabase,
+
+    /// The file that holds the expression being evaluated.
+    scratch: StringId,
+
+    /// The name and the file of each item entered so far, in order.
+    items: Vec<(StringId, StringId)>,
+
+    /// How many files we have made for items, to name the next one.
+    entries: usize,
+}
+
+impl Repl {
+    crate fn new() -> Repl {
+        let db = LarkDatabase::default();
+        let scratch = db.add_file("repl", "");
+        Repl {
+            db,
+            scratch,
+            items: vec![],
+            entries: 0,
+        }
+    }
+
+    /// The items entered so far, one line each, like `def foo`.
+    crate fn items(&self) -> Vec<String> {
+        self.items
+            .iter()
+            .map(|&(name, file)| {
+                let item_id = self.db.items_in_file(file)[0];
+                let name = self.db.untern_string(name);
+                match &*self.db.ast_of_item(item_id) {
+                    ast::ast::Item::Struct(_) => format!("struct {}", name),
+                    ast::ast::Item::Enum(_) => format!("enum {}", name),
+                    ast::ast::Item::Def(_) => format!("def {}", name),
+                    ast::ast::Item::Mod(_) => format!("mod {}", name),
+                    ast::ast::Item::Use(_) => format!("use {}", name),
+                }
+            })
+            .collect()
+    }
+
+    /// Adds the item whose source is `text`, replacing any earlier item
+    /// with the same name. Reports errors and returns false if the item
+    /// does not compile, in which case nothing changes.
+    crate fn add_item(&mut self, text: &str) -> bool {
+        self.entries += 1;
+        let file = self.db.add_file(&format!("repl-{}", self.entries), text);
+
+        let name = match self.parse_item(file) {
+            Some(name) => name,
+            None => {
+                self.db.remove_file(file);
+                return false;
+            }
+        };
+
+        let mut items: Vec<_> = self.items.iter().filter(|&&(n, _)| n != name).cloned().collect();
+        items.push((name, file));
+        let files: Vec<_> = items.iter().map(|&(_, file)| file).collect();
+        self.set_visible_files(&files);
+
+        let codemap = self.codemap(&files);
+        match check_files(&self.db, &codemap, &files) {
+            Ok(_) => {
+                for &(_, replaced) in self.items.iter().filter(|&&(n, _)| n == name) {
+                    self.db.remove_file(replaced);
+                }
+                self.items = items;
+                true
+            }
+            Err(diagnostics) => {
+                report(&codemap, &diagnostics);
+                self.db.remove_file(file);
+                let files = self.files();
+                self.set_visible_files(&files);
+                false
+            }
+        }
+    }
+
+    /// Parses the file `file`, which should contain exactly one item,
+    /// and returns its name.
+    fn parse_item(&self, file: StringId) -> Option<StringId> {
+        let codemap = self.codemap(&[file]);
+        let parse_errors = self.db.parse_errors(file);
+        let result = if parse_errors.is_empty() {
+            match &self.db.ast_of_file(file).items[..] {
+                [item] => Ok(item.name()),
+                _ => Err(vec![Diagnostic::new_error("expected a single item")]),
+            }
+        } else {
+            let filemap = codemap.iter().next().unwrap();
+            Err(parse_errors
+                .iter()
+                .map(|err| error(filemap, &err.description, err.span))
+                .collect())
+        };
+
+        match result {
+            Ok(name) => Some(name),
+            Err(diagnostics) => {
+                report(&codemap, &diagnostics);
+                None
+            }
+        }
+    }
+
+    /// The type of the expression `text`, like `String`.
+    crate fn type_of(&mut self, text: &str) -> Option<String> {
+        let ty = self.infer(text)?;
+        Some(ty.pretty(&self.db))
+    }
+
+    /// Evaluates the expression `text`.
+    crate fn evaluate(&mut self, text: &str) -> Option<Value> {
+        self.infer(text)?;
+
+        let files = self.expression_files();
+        let codemap = self.codemap(&files);
+        match lower_files(&self.db, &codemap, &files) {
+            Ok(lowered) => {
+                // The wrapper binds the value to a variable as its last
+                // step, so that variable is the last local mir-build
+                // declares.
+                let main = self.expression_fn(&lowered);
+                eval::eval_locals(&lowered.context, main).pop()
+            }
+            Err(diagnostics) => {
+                report(&codemap, &diagnostics);
+                None
+            }
+        }
+    }
+
+    /// Puts the expression `text` in the scratch file and checks it,
+    /// returning the type it produces.
+    fn infer(&mut self, text: &str) -> Option<Ty<BaseInferred>> {
+        // Binding the expression to a variable lets us read its type
+        // and its value back without knowing what the function should
+        // return.
+        let wrapper = format!("def {}() {{\nlet value = {}}}\n", EXPRESSION_FN, text);
+        self.db.update_file(self.scratch, &wrapper);
+
+        let files = self.expression_files();
+        let codemap = self.codemap(&files);
+        if let Err(diagnostics) = check_files(&self.db, &codemap, &files) {
+            report(&codemap, &diagnostics);
+            return None;
+        }
+
+        let item_id = self.db.items_in_file(self.scratch)[0];
+        let def_id = DefIdData::Item(item_id).intern(&self.db);
+        let fn_body = self.db.fn_body(def_id);
+        let variable = match fn_body[fn_body.root_expression] {
+            hir::ExpressionData::Let { var, .. } => var,
+            _ => unreachable!("expression wrapper is not a `let`"),
+        };
+
+        let ty = self.db.base_type_check(def_id).ty(variable);
+        match ty.base.untern(&self.db).kind {
+            BaseKind::Named(_) => Some(ty),
+            BaseKind::Placeholder(_) | BaseKind::Error => {
+                report(&codemap, &[Diagnostic::new_error("cannot infer the type of this expression")]);
+                None
+            }
+        }
+    }
+
+    /// The scratch file, followed by the files of the items entered so
+    /// far.
+    fn expression_files(&self) -> Vec<StringId> {
+        let mut files = vec![self.scratch];
+        files.extend(self.files());
+        files
+    }
+
+    /// The files of the items entered so far.
+    fn files(&self) -> Vec<StringId> {
+        self.items.iter().map(|&(_, file)| file).collect()
+    }
+
+    /// Makes the items in `files` visible from each of them and from
+    /// the scratch file.
+    fn set_visible_files(&self, files: &[StringId]) {
+        for &file in files.iter().chain(Some(&self.scratch)) {
+            self.db.set_visible_files(file, files.to_vec());
+        }
+    }
+
+    /// A codemap containing the text of each of `files`.
+    fn codemap(&self, files: &[StringId]) -> CodeMap {
+        let mut codemap = CodeMap::new();
+        for &file in files {
+            let text = match self.db.input_text(file) {
+                Some(text) => self.db.untern_string(text).to_string(),
+                None => String::new(),
+            };
+            let name = self.db.untern_string(file).to_string();
+            codemap.add_filemap(FileName::from(name), text);
+        }
+        codemap
+    }
+
+    fn expression_fn(&self, lowered: &LoweredItems) -> mir::DefId {
+        let item_id = self.db.items_in_file(self.scratch)[0];
+        lowered.def_ids[&DefIdData::Item(item_id).intern(&self.db)]
+    }
+}
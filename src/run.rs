@@ -0,0 +1,57 @@
+use crate::build::{compile, load, report_and_exit};
+use ast::def_id::DefIdData;
+use ast::{AstDatabase, HasParserState};
+use codespan_reporting::Diagnostic;
+use database::LarkDatabase;
+use eval::Value;
+use hir::HirDatabase;
+use intern::Untern;
+use mir_build::LoweredItems;
+
+/// `lark run <file>`: compiles `filename` and interprets its `main`
+/// function, exiting with the value that `main` returns.
+crate fn run(filename: &str) {
+    let (db, codemap, path) = load(filename);
+
+    let lowered = match compile(&db, &codemap, path) {
+        Ok(lowered) => lowered,
+        Err(diagnostics) => report_and_exit(&codemap, &diagnostics),
+    };
+
+    let main = match find_main(&db, &lowered) {
+        Some(main) => main,
+        None => report_and_exit(
+            &codemap,
+            &[Diagnostic::new_error("`main` function not found")],
+        ),
+    };
+
+    let status = match eval::eval_context(&lowered.context, main) {
+        Value::I32(status) => status,
+        _ => 0,
+    };
+
+    std::process::exit(status);
+}
+
+/// The MIR definition of the `def main()` item at the root of the
+/// program, if there is one.
+crate fn find_main(db: &LarkDatabase, lowered: &LoweredItems) -> Option<mir::DefId> {
+    let main = db.intern_string("main");
+    lowered
+        .def_ids
+        .iter()
+        .filter_map(|(&def_id, &index)| match def_id.untern(db) {
+            DefIdData::Item(item_id) => Some((item_id, index)),
+            _ => None,
+        })
+        .filter(|&(item_id, _)| db.module_path(item_id.untern(db).input_file).is_empty())
+        .find(|&(item_id, _)| match &*db.ast_of_item(item_id) {
+            ast::ast::Item::Def(def) => def.name.node == main && def.parameters.is_empty(),
+            ast::ast::Item::Struct(_)
+            | ast::ast::Item::Enum(_)
+            | ast::ast::Item::Mod(_)
+            | ast::ast::Item::Use(_) => false,
+        })
+        .map(|(_, index)| index)
+}
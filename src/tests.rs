@@ -1,17 +1,51 @@
+use crate::build::{build_args, compile};
+use crate::repl::Repl;
+use crate::run::find_main;
 use codegen::{codegen, RustFile};
-use eval::eval_context;
+use codespan::{CodeMap, FileName};
+use codespan_reporting::Diagnostic;
+use database::LarkDatabase;
+use eval::{eval_context, Value};
 use mir::{
-    builtin_type, BasicBlock, BinOp, Context, Definition, Function, LocalDecl, Operand, Place,
-    Rvalue, StatementKind, Struct, TerminatorKind,
+    BasicBlock, BinOp, BuiltinFn, BuiltinType, Context, Definition, Function, LocalDecl, Operand,
+    Place, Rvalue, StatementKind, Struct, TerminatorKind,
 };
+use mir_build::LoweredItems;
+
+/// Compiles `source` as the file `test.lark`.
+fn compile_source(source: &str) -> (LarkDatabase, Result<LoweredItems, Vec<Diagnostic>>) {
+    let db = LarkDatabase::default();
+    let path = db.add_file("test.lark", source);
+    let mut codemap = CodeMap::new();
+    codemap.add_filemap(FileName::from("test.lark"), source.to_string());
+
+    let result = compile(&db, &codemap, path);
+    (db, result)
+}
+
+/// Compiles `source`, which must have no errors.
+fn compile_ok(source: &str) -> (LarkDatabase, LoweredItems) {
+    match compile_source(source) {
+        (db, Ok(lowered)) => (db, lowered),
+        (_, Err(diagnostics)) => panic!("unexpected errors: {:?}", diagnostics),
+    }
+}
+
+/// Compiles `source`, which must have errors, and returns them.
+fn compile_err(source: &str) -> Vec<Diagnostic> {
+    match compile_source(source) {
+        (_, Ok(_)) => panic!("expected errors"),
+        (_, Err(diagnostics)) => diagnostics,
+    }
+}
 
 #[test]
 fn internaltest() {
     let mut c = Context::new();
 
-    let i32_ty = c.simple_type_for_def_id(builtin_type::I32);
-    let void_ty = c.simple_type_for_def_id(builtin_type::VOID);
-    let string_ty = c.simple_type_for_def_id(builtin_type::STRING);
+    let i32_ty = c.builtin_ty(BuiltinType::I32);
+    let void_ty = c.builtin_ty(BuiltinType::Unit);
+    let string_ty = c.builtin_ty(BuiltinType::String);
 
     let mut bob = Function::new(
         i32_ty,
@@ -66,7 +100,7 @@ fn internaltest() {
     bb2.push_stmt(StatementKind::Assign(
         Place::Local(interp_result_tmp),
         Rvalue::Call(
-            101, /*builtin string interp*/
+            c.builtin_fn(BuiltinFn::StringInterpolate),
             vec![
                 Operand::ConstantString("Hello, world {}".into()),
                 Operand::Move(Place::Local(call_result_tmp)),
@@ -98,3 +132,1609 @@ fn internaltest() {
     codegen(&mut rust, &c);
     eval_context(&c, main_def_id);
 }
+
+#[test]
+fn build_from_source() {
+    let source = "struct Diagnostic {
+  msg: own String,
+  level: String,
+}
+
+def new(msg: own String, level: String) -> Diagnostic {
+  Diagnostic { msg, level }
+}
+
+def main() {
+  let d = new(\"hello\", \"warning\")
+}
+";
+    let (_, lowered) = compile_ok(source);
+
+    let mut rust = RustFile::new();
+    codegen(&mut rust, &lowered.context);
+    let output = rust.render();
+    assert!(output.contains("struct Diagnostic {"), "{}", output);
+    assert!(output.contains("fn new(msg: String, level: String) -> Diagnostic {"), "{}", output);
+    assert!(output.contains("new(String::from(\"hello\"), String::from(\"warning\"))"), "{}", output);
+}
+
+#[test]
+fn build_out_dir() {
+    use std::path::PathBuf;
+
+    let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(
+        build_args(&args(&["a.lark"])),
+        Ok(("a.lark", PathBuf::from("target/lark")))
+    );
+    assert_eq!(
+        build_args(&args(&["--out-dir", "out", "a.lark"])),
+        Ok(("a.lark", PathBuf::from("out")))
+    );
+    assert_eq!(
+        build_args(&args(&["a.lark", "--out-dir", "out"])),
+        Ok(("a.lark", PathBuf::from("out")))
+    );
+    assert!(build_args(&args(&["a.lark", "--out-dir"])).is_err());
+    assert!(build_args(&args(&["--out-dir", "out"])).is_err());
+}
+
+#[test]
+fn build_reports_unknown_identifier() {
+    let source = "def main() {
+  let d = foo(\"hello\", \"warning\")
+}
+";
+    let diagnostics = compile_err(source);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].message, "cannot find `foo` in this scope");
+}
+
+#[test]
+fn run_from_source() {
+    let source = "struct Diagnostic {
+  msg: own String,
+  level: String,
+}
+
+def new(msg: own String, level: String) -> Diagnostic {
+  Diagnostic { msg, level }
+}
+
+def main() -> Diagnostic {
+  new(\"hello\", \"warning\")
+}
+";
+    let (db, lowered) = compile_ok(source);
+
+    let main = find_main(&db, &lowered).expect("no main");
+    match eval_context(&lowered.context, main) {
+        Value::Struct(fields) => {
+            assert_eq!(fields["msg"].to_string(), "hello");
+            assert_eq!(fields["level"].to_string(), "warning");
+        }
+        value => panic!("unexpected value: {:?}", value),
+    }
+}
+
+#[test]
+fn repl_session() {
+    let mut repl = Repl::new();
+    assert!(repl.add_item("struct Diagnostic {\n  msg: own String,\n  level: String,\n}\n"));
+    assert!(repl.add_item("def new(msg: own String, level: String) -> Diagnostic {\n  Diagnostic { msg, level }\n}\n"));
+    assert!(!repl.add_item("def broken(msg: String, level: String) -> Diagnostic {\n  undefined\n}\n"));
+    assert_eq!(repl.items(), vec!["struct Diagnostic", "def new"]);
+
+    assert_eq!(repl.type_of("new(\"a\", \"b\")\n").unwrap(), "Diagnostic");
+    assert_eq!(repl.type_of("\"a\"\n").unwrap(), "String");
+
+    match repl.evaluate("\"hello\"\n") {
+        Some(Value::Str(s)) => assert_eq!(s, "hello"),
+        value => panic!("unexpected value: {:?}", value),
+    }
+}
+
+#[test]
+fn build_reports_syntax_errors() {
+    let source = "def main( {\n}\n";
+    let diagnostics = compile_err(source);
+    assert!(!diagnostics.is_empty());
+}
+
+#[test]
+fn repl_rejects_syntax_errors() {
+    let mut repl = Repl::new();
+    assert!(!repl.add_item("def broken( {\n}\n"));
+    assert!(repl.items().is_empty());
+}
+
+#[test]
+fn repl_replaces_items() {
+    let mut repl = Repl::new();
+    assert!(repl.add_item("def greeting() -> String {\n  \"hello\"\n}\n"));
+    assert!(repl.add_item("def twice() -> String {\n  greeting()\n}\n"));
+    assert!(repl.add_item("def greeting() -> String {\n  \"goodbye\"\n}\n"));
+    assert_eq!(repl.items(), vec!["def twice", "def greeting"]);
+
+    match repl.evaluate("twice()\n") {
+        Some(Value::Str(s)) => assert_eq!(s, "goodbye"),
+        value => panic!("unexpected value: {:?}", value),
+    }
+}
+
+#[test]
+fn repl_generic_value() {
+    let mut repl = Repl::new();
+    assert!(repl.add_item("struct Box<T> {\n  value: T,\n}\n"));
+    assert!(repl.add_item("def boxed(value: i32, unused: i32) -> Box<i32> {\n  Box { value, }\n}\n"));
+    assert_eq!(repl.type_of("boxed(1, 2)\n").unwrap(), "Box<i32>");
+
+    // mir-build cannot lower type parameters yet.
+    assert!(repl.evaluate("boxed(1, 2)\n").is_none());
+}
+
+#[test]
+fn repl_reads_until_braces_close() {
+    use crate::repl::read_input;
+
+    let input = "def f() {\n  \"}\" // }\n}\ndef g() {}\n";
+    let mut lines = input.lines().map(|line| Ok(line.to_string()));
+    assert_eq!(read_input(&mut lines).unwrap(), "def f() {\n  \"}\" // }\n}\n");
+    assert_eq!(read_input(&mut lines).unwrap(), "def g() {}\n");
+    assert!(read_input(&mut lines).is_none());
+}
+
+#[test]
+fn update_and_remove_files() {
+    use ast::AstDatabase;
+
+    let db = LarkDatabase::default();
+    let path = db.add_file("test.lark", "struct A {\n  x: String,\n}\n");
+    assert_eq!(db.items_in_file(path).len(), 1);
+
+    db.update_file(path, "struct A {\n  x: String,\n}\nstruct B {\n  y: String,\n}\n");
+    assert_eq!(db.items_in_file(path).len(), 2);
+    assert_eq!(*db.files(), vec![path]);
+
+    db.remove_file(path);
+    assert!(db.files().is_empty());
+}
+
+#[test]
+fn editing_an_item_keeps_later_fn_bodies() {
+    use ast::def_id::DefIdData;
+    use ast::AstDatabase;
+    use hir::HirDatabase;
+    use intern::Intern;
+    use std::sync::Arc;
+
+    let db = LarkDatabase::default();
+    let path = db.add_file("test.lark", "def first() {\n  print(\"a\")\n}\n\ndef second() {\n  print(\"b\")\n}\n");
+    let items = db.items_in_file(path);
+    let first = DefIdData::Item(items[0]).intern(&db);
+    let second = DefIdData::Item(items[1]).intern(&db);
+    let before = (db.fn_body(first), db.fn_body(second));
+
+    // Lengthening `first` moves `second` further into the file, but
+    // its body is kept rather than lowered again.
+    db.update_file(path, "def first() {\n  print(\"abc\")\n}\n\ndef second() {\n  print(\"b\")\n}\n");
+    let after = (db.fn_body(first), db.fn_body(second));
+    assert!(!Arc::ptr_eq(&before.0, &after.0));
+    assert!(Arc::ptr_eq(&before.1, &after.1));
+}
+
+#[test]
+fn lower_fn_body() {
+    use ast::def_id::DefIdData;
+    use ast::{AstDatabase, HasParserState};
+    use hir::HirDatabase;
+    use intern::Intern;
+
+    let source = "def main() {
+  let x = \"hello\"
+  let y = x
+  missing(y, unknown)
+}
+";
+    let db = LarkDatabase::default();
+    let path = db.add_file("test.lark", source);
+    let item_id = db.items_in_file(path)[0];
+    let fn_body = db.fn_body(DefIdData::Item(item_id).intern(&db));
+
+    let span_text = |span: parser::pos::Span| match span {
+        parser::pos::Span::Real(span) => {
+            source[span.start().to_usize()..span.end().to_usize()].to_string()
+        }
+        span => panic!("unexpected span {:?}", span),
+    };
+
+    // `let x = "hello"` scopes over the rest of the block.
+    let (x, body) = match fn_body[fn_body.root_expression] {
+        hir::ExpressionData::Let {
+            var,
+            ty: None,
+            initializer,
+            body,
+        } => {
+            assert_eq!(span_text(fn_body.span(initializer)), "\"hello\"");
+            (var, body)
+        }
+        ref data => panic!("expected a let, found {:?}", data),
+    };
+    assert_eq!(span_text(fn_body.span(x)), "x");
+
+    // `let y = x` refers back to the variable `x`.
+    match fn_body[body] {
+        hir::ExpressionData::Let { initializer, .. } => match fn_body[initializer] {
+            hir::ExpressionData::Place { place, .. } => {
+                assert_eq!(fn_body[place], hir::PlaceData::Variable(x));
+            }
+            ref data => panic!("expected a place, found {:?}", data),
+        },
+        ref data => panic!("expected a let, found {:?}", data),
+    }
+
+    // Both unresolved names are reported, not just the callee.
+    let errors: Vec<_> = fn_body
+        .errors
+        .iter()
+        .map(|error| (error.node, span_text(error.span)))
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            (
+                hir::ErrorData::UnknownIdentifier {
+                    text: db.intern_string("missing")
+                },
+                "missing".to_string()
+            ),
+            (
+                hir::ErrorData::UnknownIdentifier {
+                    text: db.intern_string("unknown")
+                },
+                "unknown".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn build_reports_unknown_type() {
+    let source = "struct Diagnostic {
+  msg: own Strin,
+  level: String,
+}
+";
+    let diagnostics = compile_err(source);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].message, "cannot find type `Strin` in this scope");
+}
+
+#[test]
+fn build_checks_let_annotations() {
+    let source = "def main() {
+  let x: i32 = 1
+  let s: String = \"hello\"
+  let _: bool = true
+  let p: Point = Point { x }
+}
+
+struct Point {
+  x: i32,
+}
+";
+    compile_ok(source);
+
+    // An annotation may name the type parameters of the function.
+    {
+        use ast::def_id::DefIdData;
+        use ast::AstDatabase;
+        use hir::HirDatabase;
+        use intern::Intern;
+        use type_check::TypeCheckDatabase;
+
+        let db = LarkDatabase::default();
+        let path = db.add_file("test.lark", "def id<T>(x: T) -> T {\n  let y: T = x\n  y\n}\n");
+        let def_id = DefIdData::Item(db.items_in_file(path)[0]).intern(&db);
+        assert!(db.fn_body(def_id).errors.is_empty());
+        assert!(db.base_type_check(def_id).errors().is_empty());
+    }
+
+    let diagnostics = compile_err("def main() {\n  let s: String = 1\n}\n");
+    assert_eq!(diagnostics[0].message, "mismatched types");
+
+    let diagnostics = compile_err("def main() {\n  let _: bool = 1\n}\n");
+    assert_eq!(diagnostics[0].message, "mismatched types");
+
+    let diagnostics = compile_err("def main() {\n  let s: Strng = \"a\"\n}\n");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].message, "cannot find type `Strng` in this scope");
+}
+
+#[test]
+fn build_with_prelude() {
+    let source = "struct Point {
+  x: i32,
+  y: u32,
+}
+
+def main() {
+  print(\"hello\")
+}
+";
+    let (_, lowered) = compile_ok(source);
+
+    let mut rust = RustFile::new();
+    codegen(&mut rust, &lowered.context);
+    let output = rust.render();
+    assert!(output.contains("x: i32,\ny: u32,"), "{}", output);
+    assert!(output.contains("println!(\"{}\", String::from(\"hello\"))"), "{}", output);
+}
+
+#[test]
+fn type_check_diagnostics() {
+    use ast::def_id::DefIdData;
+    use ast::AstDatabase;
+    use hir::HirDatabase;
+    use intern::Intern;
+    use type_check::TypeCheckDatabase;
+
+    let source = "struct Diagnostic {
+  msg: own String,
+  level: String,
+}
+
+def new(msg: String, level: Diagnostic) -> Diagnostic {
+  Diagnostic { msg, level }
+}
+";
+    let db = LarkDatabase::default();
+    let path = db.add_file("test.lark", source);
+    let item_id = db.items_in_file(path)[1];
+    let def_id = DefIdData::Item(item_id).intern(&db);
+
+    assert_eq!(db.base_type_check(def_id).errors().len(), 1);
+
+    // `check` moves the spans from within `new` to within the file.
+    let errors = database::check::check(&db, &[path]);
+    assert_eq!(errors.len(), 1);
+    let diagnostic = match &errors[0].kind {
+        database::check::CheckErrorKind::TypeCheck(diagnostic) => diagnostic.clone(),
+        kind => panic!("unexpected error {:?}", kind),
+    };
+    assert_eq!(diagnostic.code, "E0001");
+    assert_eq!(diagnostic.message, "mismatched types");
+    assert_eq!(
+        diagnostic.labels[0].message,
+        "expected `String`, found `Diagnostic`"
+    );
+    assert_eq!(
+        diagnostic.notes,
+        vec!["expected type `String`", "   found type `Diagnostic`"]
+    );
+
+    let uri = languageserver_types::Url::parse("file:///test.lark").unwrap();
+    let lsp = ide::diagnostics::lsp_diagnostic(&diagnostic, &uri, source);
+    assert_eq!(lsp.range.start, languageserver_types::Position::new(6, 20));
+    assert_eq!(lsp.range.end, languageserver_types::Position::new(6, 25));
+}
+
+#[test]
+fn lsp_positions_of_stale_offsets() {
+    use ide::diagnostics::offset_position;
+    use languageserver_types::Position;
+
+    let text = "let s = \"héllo\"\n";
+    assert_eq!(offset_position(10, text), Position::new(0, 10));
+    // Within the two bytes of `é`.
+    assert_eq!(offset_position(11, text), Position::new(0, 10));
+    // Past the end, as for a span from before the text got shorter.
+    assert_eq!(offset_position(100, text), Position::new(1, 0));
+}
+
+#[test]
+fn query_system_publishes_diagnostics() {
+    use languageserver_types::{Position, Range, TextDocumentContentChangeEvent, Url};
+    use query_system::QuerySystem;
+    use std::sync::mpsc::channel;
+    use task_manager::{Actor, TypeMessage, TypeResponse};
+
+    let (tx, rx) = channel();
+    let mut query_system = QuerySystem::new();
+    query_system.startup(Box::new(move |response| tx.send(response).unwrap()));
+
+    let mut diagnostics = || match rx.try_recv() {
+        Ok(TypeResponse::Diagnostics(_, diagnostics)) => diagnostics,
+        _ => panic!("expected diagnostics"),
+    };
+
+    let uri = Url::parse("file:///test.lark").unwrap();
+    let source = "def main(a: i32, b: i32) {\n  prnt(\"hello\")\n}\n";
+    query_system.receive_message(TypeMessage::OpenFile(uri.clone(), source.to_string()));
+
+    let open = diagnostics();
+    assert_eq!(open.len(), 1);
+    assert_eq!(open[0].message, "cannot find `prnt` in this scope");
+    assert_eq!(open[0].range.start, Position::new(1, 2));
+    assert_eq!(open[0].range.end, Position::new(1, 6));
+
+    // Insert the missing `i`.
+    query_system.receive_message(TypeMessage::EditFile(
+        uri.clone(),
+        vec![TextDocumentContentChangeEvent {
+            range: Some(Range::new(Position::new(1, 4), Position::new(1, 4))),
+            range_length: Some(0),
+            text: "i".to_string(),
+        }],
+    ));
+    assert_eq!(diagnostics(), vec![]);
+
+    // Replace `"hello"` with something that is not a `String`.
+    query_system.receive_message(TypeMessage::EditFile(
+        uri.clone(),
+        vec![TextDocumentContentChangeEvent {
+            range: Some(Range::new(Position::new(1, 8), Position::new(1, 15))),
+            range_length: Some(7),
+            text: "a".to_string(),
+        }],
+    ));
+    let edited = diagnostics();
+    assert_eq!(edited.len(), 1);
+    assert_eq!(edited[0].range.start, Position::new(1, 8));
+    assert_eq!(edited[0].range.end, Position::new(1, 9));
+}
+
+#[test]
+fn query_system_republishes_affected_files() {
+    use languageserver_types::{Position, Range, TextDocumentContentChangeEvent, Url};
+    use query_system::QuerySystem;
+    use std::sync::mpsc::channel;
+    use task_manager::{Actor, TypeMessage, TypeResponse};
+
+    let (tx, rx) = channel();
+    let mut query_system = QuerySystem::new();
+    query_system.startup(Box::new(move |response| tx.send(response).unwrap()));
+
+    // Which documents got diagnostics, and whether they had errors.
+    let mut published = || {
+        let mut documents = vec![];
+        while let Ok(response) = rx.try_recv() {
+            match response {
+                TypeResponse::Diagnostics(uri, diagnostics) => {
+                    documents.push((uri.path().to_string(), !diagnostics.is_empty()))
+                }
+                _ => panic!("expected diagnostics"),
+            }
+        }
+        documents
+    };
+
+    let main = Url::parse("file:///main.lark").unwrap();
+    let source = "mod shapes\nuse shapes::area\n\ndef main() -> i32 {\n  area(2)\n}\n";
+    query_system.receive_message(TypeMessage::OpenFile(main.clone(), source.to_string()));
+    assert_eq!(published(), vec![("/main.lark".to_string(), true)]);
+
+    // Opening the module fixes `main.lark` too.
+    let shapes = Url::parse("file:///shapes.lark").unwrap();
+    let source = "pub def area(x: i32) -> i32 {\n  x * x\n}\n";
+    query_system.receive_message(TypeMessage::OpenFile(shapes.clone(), source.to_string()));
+    assert_eq!(
+        published(),
+        vec![("/main.lark".to_string(), false), ("/shapes.lark".to_string(), false)]
+    );
+
+    // Renaming `area` breaks `main.lark` again.
+    let rename = TextDocumentContentChangeEvent {
+        range: Some(Range::new(Position::new(0, 8), Position::new(0, 12))),
+        range_length: Some(4),
+        text: "size".to_string(),
+    };
+    query_system.receive_message(TypeMessage::EditFile(shapes.clone(), vec![rename]));
+    assert_eq!(
+        published(),
+        vec![("/main.lark".to_string(), true), ("/shapes.lark".to_string(), false)]
+    );
+
+    // An edit that changes nothing elsewhere republishes only the
+    // edited file.
+    let comment = TextDocumentContentChangeEvent {
+        range: Some(Range::new(Position::new(3, 0), Position::new(3, 0))),
+        range_length: Some(0),
+        text: "// unchanged\n".to_string(),
+    };
+    query_system.receive_message(TypeMessage::EditFile(shapes.clone(), vec![comment]));
+    assert_eq!(published(), vec![("/shapes.lark".to_string(), false)]);
+}
+
+#[test]
+fn query_system_answers_hover_and_completion() {
+    use languageserver_types::{Position, Url};
+    use query_system::QuerySystem;
+    use std::sync::mpsc::channel;
+    use task_manager::{Actor, TypeMessage, TypeResponse};
+
+    let (tx, rx) = channel();
+    let mut query_system = QuerySystem::new();
+    query_system.startup(Box::new(move |response| tx.send(response).unwrap()));
+
+    let uri = Url::parse("file:///test.lark").unwrap();
+    let source = "struct Point {
+  x: i32,
+  y: String,
+}
+
+def make(x: i32, y: String) -> Point {
+  Point { x, y }
+}
+";
+    query_system.receive_message(TypeMessage::OpenFile(uri.clone(), source.to_string()));
+    match rx.try_recv() {
+        Ok(TypeResponse::Diagnostics(_, diagnostics)) => assert_eq!(diagnostics, vec![]),
+        _ => panic!("expected diagnostics"),
+    }
+
+    let mut node_at = |line, character| {
+        query_system.receive_message(TypeMessage::DefIdForPos(
+            0,
+            uri.clone(),
+            Position::new(line, character),
+        ));
+        match rx.try_recv() {
+            Ok(TypeResponse::DefId(_, node)) => node,
+            _ => panic!("expected a node"),
+        }
+    };
+    let x = node_at(6, 10);
+    let make = node_at(5, 5);
+    let field = node_at(2, 3);
+    let aggregate = node_at(6, 2);
+
+    let mut type_of = |node| {
+        query_system.receive_message(TypeMessage::TypeForDefId(0, node));
+        match rx.try_recv() {
+            Ok(TypeResponse::Type(_, ty)) => ty,
+            _ => panic!("expected a type"),
+        }
+    };
+    assert_eq!(type_of(x), "i32");
+    assert_eq!(type_of(make), "(i32, String) -> Point");
+    assert_eq!(type_of(field), "String");
+    assert_eq!(type_of(aggregate), "Point");
+
+    query_system.receive_message(TypeMessage::CompletionsForDefId(0, aggregate));
+    match rx.try_recv() {
+        Ok(TypeResponse::Completions(_, completions)) => assert_eq!(
+            completions,
+            vec![
+                ("x".to_string(), "i32".to_string(), None),
+                ("y".to_string(), "String".to_string(), None),
+            ]
+        ),
+        _ => panic!("expected completions"),
+    }
+
+    // An edit forgets the nodes handed out before it.
+    query_system.receive_message(TypeMessage::OpenFile(uri.clone(), source.to_string()));
+    match rx.try_recv() {
+        Ok(TypeResponse::Diagnostics(..)) => {}
+        _ => panic!("expected diagnostics"),
+    }
+    query_system.receive_message(TypeMessage::TypeForDefId(0, x));
+    match rx.try_recv() {
+        Ok(TypeResponse::Type(_, ty)) => assert_eq!(ty, ""),
+        _ => panic!("expected a type"),
+    }
+}
+
+#[test]
+fn run_literals() {
+    let source = "def main() -> i32 {
+  let big = 1_000
+  let hex = 0xff
+  let binary = 0b1010
+  let float = 1.5e2
+  let truth = true
+  let letter = false
+  big + hex + binary
+}
+";
+    let (db, lowered) = compile_ok(source);
+
+    let main = find_main(&db, &lowered).expect("no main");
+    match eval_context(&lowered.context, main) {
+        Value::I32(value) => assert_eq!(value, 1265),
+        value => panic!("unexpected value: {:?}", value),
+    }
+}
+
+#[test]
+fn run_negative_literals() {
+    let source = "def main() -> i32 {
+  let min = -2147483648
+  let max = 2147483647
+  min + max - -1
+}
+";
+    let (db, lowered) = compile_ok(source);
+
+    let main = find_main(&db, &lowered).expect("no main");
+    match eval_context(&lowered.context, main) {
+        Value::I32(value) => assert_eq!(value, 0),
+        value => panic!("unexpected value: {:?}", value),
+    }
+}
+
+#[test]
+fn build_reports_literal_out_of_range() {
+    let source = "def main() -> i32 {
+  let float = 1e999
+  let low = -2147483649
+  2147483648
+}
+";
+    let diagnostics = compile_err(source);
+    let messages: Vec<_> = diagnostics.iter().map(|d| &d.message[..]).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "literal out of range for `f64`",
+            "literal out of range for `i32`",
+            "literal out of range for `i32`"
+        ]
+    );
+}
+
+#[test]
+fn type_check_if() {
+    use ast::def_id::DefIdData;
+    use ast::AstDatabase;
+    use hir::HirDatabase;
+    use intern::Intern;
+    use type_check::TypeCheckDatabase;
+
+    let source = "def pick(first: bool, second: bool) -> i32 {
+  if first { 1 } else if second { 2 } else { 3 }
+}
+
+def wrong(first: i32, second: bool) -> i32 {
+  if first { 1 } else { 2 }
+}
+
+def bind(value: i32, other: i32) -> i32 {
+  if let x = value { x + other } else { other }
+}
+";
+    let db = LarkDatabase::default();
+    let path = db.add_file("test.lark", source);
+    let items = db.items_in_file(path);
+
+    // `else if` nests another `if` in the `else` branch.
+    let pick = DefIdData::Item(items[0]).intern(&db);
+    let fn_body = db.fn_body(pick);
+    assert!(fn_body.errors.is_empty());
+    match fn_body[fn_body.root_expression] {
+        hir::ExpressionData::If { if_false, .. } => match fn_body[if_false] {
+            hir::ExpressionData::If { .. } => {}
+            ref data => panic!("expected an if, found {:?}", data),
+        },
+        ref data => panic!("expected an if, found {:?}", data),
+    }
+    assert!(db.base_type_check(pick).errors().is_empty());
+
+    let wrong = DefIdData::Item(items[1]).intern(&db);
+    let fn_body = db.fn_body(wrong);
+    let errors = db.base_type_check(wrong).errors().to_vec();
+    assert_eq!(errors.len(), 1);
+    let diagnostic = errors[0].diagnostic(&db, &fn_body);
+    assert_eq!(diagnostic.labels[0].message, "expected `bool`, found `i32`");
+
+    // `if let` is a `match` with a `_` arm for the `else`.
+    let bind = DefIdData::Item(items[2]).intern(&db);
+    let fn_body = db.fn_body(bind);
+    assert!(fn_body.errors.is_empty());
+    match fn_body[fn_body.root_expression] {
+        hir::ExpressionData::Match { ref arms, .. } => assert_eq!(arms.len(), 2),
+        ref data => panic!("expected a match, found {:?}", data),
+    }
+}
+
+#[test]
+fn run_loops() {
+    let source = "def pick(first: bool, second: bool) -> i32 {
+  if first { 1 } else if second { 2 } else { 3 }
+}
+
+def main() -> i32 {
+  let a = loop { break 40 }
+  let b = 'outer: loop {
+    loop {
+      if true { break 'outer pick(false, true) } else { continue }
+    }
+  }
+  while false { print(\"never\") }
+  a + b
+}
+";
+    let (db, lowered) = compile_ok(source);
+
+    let main = find_main(&db, &lowered).expect("no main");
+    match eval_context(&lowered.context, main) {
+        Value::I32(value) => assert_eq!(value, 42),
+        value => panic!("unexpected value: {:?}", value),
+    }
+}
+
+#[test]
+fn run_multibyte_labels() {
+    let source = "def main() -> i32 {
+  'äußere: loop {
+    loop { break 'äußere 42 }
+  }
+}
+";
+    let (db, lowered) = compile_ok(source);
+
+    let main = find_main(&db, &lowered).expect("no main");
+    match eval_context(&lowered.context, main) {
+        Value::I32(value) => assert_eq!(value, 42),
+        value => panic!("unexpected value: {:?}", value),
+    }
+}
+
+#[test]
+fn run_for_loops() {
+    let source = "def first(start: i32, end: i32) -> i32 {
+  'search: loop {
+    for i in start..end { break 'search i + 1 }
+    break 'search 0
+  }
+}
+
+def main() -> i32 {
+  for j in 5..5 { print(\"never\") }
+  for i in 0..3 { continue }
+  first(40, 50) + first(5, 5)
+}
+";
+    let (db, lowered) = compile_ok(source);
+
+    let main = find_main(&db, &lowered).expect("no main");
+    match eval_context(&lowered.context, main) {
+        Value::I32(value) => assert_eq!(value, 41),
+        value => panic!("unexpected value: {:?}", value),
+    }
+
+    let mut rust = RustFile::new();
+    codegen(&mut rust, &lowered.context);
+    let output = rust.render();
+    assert!(!output.contains("let mut _:"), "{}", output);
+}
+
+#[test]
+fn build_reports_loop_errors() {
+    let source = "def main() {
+  break
+  loop { continue 'missing }
+  while true { break 1 }
+}
+";
+    let diagnostics = compile_err(source);
+    let messages: Vec<_> = diagnostics.iter().map(|d| &d.message[..]).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "`break` outside of a loop",
+            "use of undeclared label `'missing`",
+            "`break` with a value is only allowed in `loop`"
+        ]
+    );
+}
+
+#[test]
+fn run_operators() {
+    let source = "def gcd(a: i32, b: i32) -> i32 {
+  if b == 0 { a } else { gcd(b, a % b) }
+}
+
+def main() -> i32 {
+  let a = 2 + 3 * 4 - 10 / 2
+  let b = -(1 - 4) * 2
+  let ok = a >= 9 && !(b < 0) || false
+  let f = 1.5 * 2.0 > 2.9
+  if ok && f { a + b + gcd(12, 18) } else { 0 }
+}
+";
+    let (db, lowered) = compile_ok(source);
+
+    let main = find_main(&db, &lowered).expect("no main");
+    match eval_context(&lowered.context, main) {
+        Value::I32(value) => assert_eq!(value, 21),
+        value => panic!("unexpected value: {:?}", value),
+    }
+}
+
+#[test]
+fn build_reports_chained_comparison() {
+    let source = "def main() -> bool {
+  1 < 2 < 3
+}
+";
+    let diagnostics = compile_err(source);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].message, "comparison operators cannot be chained");
+}
+
+#[test]
+fn run_field_access() {
+    let source = "struct Point {
+  x: i32,
+  y: i32,
+}
+
+def main() -> i32 {
+  let x = 40
+  let y = 2
+  let p = Point { x, y }
+  p.x + p.y
+}
+";
+    let (db, lowered) = compile_ok(source);
+
+    let main = find_main(&db, &lowered).expect("no main");
+    match eval_context(&lowered.context, main) {
+        Value::I32(value) => assert_eq!(value, 42),
+        value => panic!("unexpected value: {:?}", value),
+    }
+}
+
+#[test]
+fn build_reports_self_outside_method() {
+    let source = "def main() {
+  print(self.name)
+}
+";
+    let diagnostics = compile_err(source);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].message, "cannot find `self` in this scope");
+}
+
+#[test]
+fn run_methods() {
+    use ast::def_id::DefIdData;
+    use ast::{AstDatabase, HasParserState};
+    use hir::HirDatabase;
+    use intern::Intern;
+
+    let source = "struct Point {
+  x: i32,
+  y: i32,
+
+  def sum(borrow self) -> i32 {
+    self.x + self.y
+  }
+
+  def scaled(share self, by: i32) -> i32 {
+    self.sum() * by
+  }
+}
+
+def main() -> i32 {
+  let x = 4
+  let y = 3
+  let p = Point { x, y }
+  p.scaled(6)
+}
+";
+    let (db, lowered) = compile_ok(source);
+
+    let main = find_main(&db, &lowered).expect("no main");
+    match eval_context(&lowered.context, main) {
+        Value::I32(value) => assert_eq!(value, 42),
+        value => panic!("unexpected value: {:?}", value),
+    }
+
+    let point = db.items_in_file(db.files()[0])[0];
+    let members = db.members(DefIdData::Item(point).intern(&db));
+    let methods: Vec<_> = members
+        .iter()
+        .filter(|member| member.kind == hir::MemberKind::Method)
+        .map(|member| db.untern_string(member.name).to_string())
+        .collect();
+    assert_eq!(methods, vec!["sum", "scaled"]);
+}
+
+#[test]
+fn build_reports_receiver_errors() {
+    let source = "struct Point {
+  x: i32,
+  y: i32,
+
+  def origin() -> i32 {
+    0
+  }
+}
+
+def free(own self) {
+}
+";
+    let diagnostics = compile_err(source);
+    let messages: Vec<_> = diagnostics.iter().map(|d| &d.message[..]).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "methods must have a `self` parameter",
+            "`self` parameter is only allowed in methods"
+        ]
+    );
+}
+
+#[test]
+fn run_string_interpolation() {
+    let source = "def main() -> String {
+  let name = \"world\"
+  let n = 6 * 7
+  \"hello {{name}}: {{\"<{{n}}>\"}} \\{{braces}} {single}\"
+}
+";
+    let (db, lowered) = compile_ok(source);
+
+    let main = find_main(&db, &lowered).expect("no main");
+    match eval_context(&lowered.context, main) {
+        Value::Str(value) => assert_eq!(value, "hello world: <42> {{braces}} {single}"),
+        value => panic!("unexpected value: {:?}", value),
+    }
+
+    let mut rust = RustFile::new();
+    codegen(&mut rust, &lowered.context);
+    let output = rust.render();
+    assert!(
+        output.contains("format!(\"hello {}: {} {{{{braces}}}} {{single}}\""),
+        "{}",
+        output
+    );
+}
+
+#[test]
+fn build_reports_values_that_cannot_be_interpolated() {
+    let source = "struct Point {
+  x: i32,
+}
+
+def describe(x: i32, p: Point) -> String {
+  let nothing = print(\"side effect\")
+  \"{{x}} {{p}} {{nothing}}\"
+}
+";
+    let diagnostics = compile_err(source);
+    let messages: Vec<_> = diagnostics.iter().map(|d| &d.message[..]).collect();
+    let note = "only `bool`, `i32`, `u32`, `f64` and `String` can be embedded";
+    assert_eq!(
+        messages,
+        vec![
+            "`Point` cannot be embedded in a string",
+            note,
+            "`()` cannot be embedded in a string",
+            note
+        ]
+    );
+}
+
+#[test]
+fn build_reports_unsupported_operators() {
+    let source = "struct Point {
+  x: i32,
+}
+
+def compare(p: Point, q: Point, s: String, t: String) -> bool {
+  let product = s * t
+  let negated = -s
+  p < q
+}
+";
+    let diagnostics = compile_err(source);
+    let messages: Vec<_> = diagnostics.iter().map(|d| &d.message[..]).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "cannot apply `*` to `String`",
+            "cannot apply `-` to `String`",
+            "cannot apply `<` to `Point`"
+        ]
+    );
+}
+
+#[test]
+fn query_system_recovers_from_syntax_errors() {
+    use languageserver_types::{Position, Url};
+    use query_system::QuerySystem;
+    use std::sync::mpsc::channel;
+    use task_manager::{Actor, TypeMessage, TypeResponse};
+
+    let (tx, rx) = channel();
+    let mut query_system = QuerySystem::new();
+    query_system.startup(Box::new(move |response| tx.send(response).unwrap()));
+
+    let uri = Url::parse("file:///test.lark").unwrap();
+    let source = "struct Point {
+  x: i32,
+  y: String,
+}
+
+def unfinished( {
+
+def make(x: i32, y: String) -> Point {
+  let a = )
+  let p = Point { x, y }
+  p.
+  p
+}
+";
+    query_system.receive_message(TypeMessage::OpenFile(uri.clone(), source.to_string()));
+    match rx.try_recv() {
+        Ok(TypeResponse::Diagnostics(_, diagnostics)) => {
+            let lines: Vec<_> = diagnostics.iter().map(|d| d.range.start.line).collect();
+            assert_eq!(lines, vec![5, 8, 10]);
+        }
+        _ => panic!("expected diagnostics"),
+    }
+
+    let mut node_at = |line, character| {
+        query_system.receive_message(TypeMessage::DefIdForPos(
+            0,
+            uri.clone(),
+            Position::new(line, character),
+        ));
+        match rx.try_recv() {
+            Ok(TypeResponse::DefId(_, node)) => node,
+            _ => panic!("expected a node"),
+        }
+    };
+    let y = node_at(9, 21);
+    let dot = node_at(10, 4);
+
+    query_system.receive_message(TypeMessage::TypeForDefId(0, y));
+    match rx.try_recv() {
+        Ok(TypeResponse::Type(_, ty)) => assert_eq!(ty, "String"),
+        _ => panic!("expected a type"),
+    }
+
+    query_system.receive_message(TypeMessage::CompletionsForDefId(0, dot));
+    match rx.try_recv() {
+        Ok(TypeResponse::Completions(_, completions)) => assert_eq!(
+            completions,
+            vec![
+                ("x".to_string(), "i32".to_string(), None),
+                ("y".to_string(), "String".to_string(), None),
+            ]
+        ),
+        _ => panic!("expected completions"),
+    }
+}
+
+#[test]
+fn query_system_shows_doc_comments() {
+    use languageserver_types::{Position, Url};
+    use query_system::QuerySystem;
+    use std::sync::mpsc::channel;
+    use task_manager::{Actor, TypeMessage, TypeResponse};
+
+    let (tx, rx) = channel();
+    let mut query_system = QuerySystem::new();
+    query_system.startup(Box::new(move |response| tx.send(response).unwrap()));
+
+    let uri = Url::parse("file:///test.lark").unwrap();
+    let source = "/// A point on the plane.
+struct Point {
+  /// Across.
+  x: i32, // not documentation
+  /* y: String, /* nested */ */
+  y: i32,
+
+  /// The sum of the
+  /// coordinates.
+  def sum(borrow self) -> i32 {
+    self.x + self.y
+  }
+}
+
+//// Four slashes are an ordinary comment.
+def origin() -> Point {
+  let x = 0 /* zero */
+  let y = 0
+  Point { x, y }
+}
+";
+    query_system.receive_message(TypeMessage::OpenFile(uri.clone(), source.to_string()));
+    match rx.try_recv() {
+        Ok(TypeResponse::Diagnostics(_, diagnostics)) => assert_eq!(diagnostics, vec![]),
+        _ => panic!("expected diagnostics"),
+    }
+
+    let mut hover = |line, character| {
+        query_system.receive_message(TypeMessage::DefIdForPos(
+            0,
+            uri.clone(),
+            Position::new(line, character),
+        ));
+        let node = match rx.try_recv() {
+            Ok(TypeResponse::DefId(_, node)) => node,
+            _ => panic!("expected a node"),
+        };
+        query_system.receive_message(TypeMessage::TypeForDefId(0, node));
+        match rx.try_recv() {
+            Ok(TypeResponse::Type(_, ty)) => ty,
+            _ => panic!("expected a type"),
+        }
+    };
+    assert_eq!(hover(1, 8), "Point\n\nA point on the plane.");
+    assert_eq!(hover(3, 2), "i32\n\nAcross.");
+    assert_eq!(hover(15, 5), "() -> Point");
+
+    query_system.receive_message(TypeMessage::DefIdForPos(0, uri.clone(), Position::new(18, 2)));
+    let aggregate = match rx.try_recv() {
+        Ok(TypeResponse::DefId(_, node)) => node,
+        _ => panic!("expected a node"),
+    };
+    query_system.receive_message(TypeMessage::CompletionsForDefId(0, aggregate));
+    match rx.try_recv() {
+        Ok(TypeResponse::Completions(_, completions)) => assert_eq!(
+            completions,
+            vec![
+                ("x".to_string(), "i32".to_string(), Some("Across.".to_string())),
+                ("y".to_string(), "i32".to_string(), None),
+                (
+                    "sum".to_string(),
+                    "(Point) -> i32".to_string(),
+                    Some("The sum of the\ncoordinates.".to_string())
+                ),
+            ]
+        ),
+        _ => panic!("expected completions"),
+    }
+}
+
+#[test]
+fn type_check_generics() {
+    use ast::def_id::DefIdData;
+    use ast::{AstDatabase, HasParserState};
+    use hir::HirDatabase;
+    use intern::Intern;
+    use type_check::pretty::Pretty;
+    use type_check::TypeCheckDatabase;
+
+    let source = "struct Box<T> {
+  value: T,
+}
+
+def id<T>(x: T,) -> T {
+  x
+}
+
+def make() -> Box<own String> {
+  let value = id(\"boxed\")
+  Box { value, }
+}
+
+def unbox(b: Box<String>,) -> String {
+  b.value
+}
+";
+    let db = LarkDatabase::default();
+    let path = db.add_file("test.lark", source);
+    let def_ids: Vec<_> = db
+        .items_in_file(path)
+        .iter()
+        .map(|&item_id| DefIdData::Item(item_id).intern(&db))
+        .collect();
+
+    let names = |def_id| -> Vec<String> {
+        db.generic_declarations(def_id)
+            .declarations
+            .iter()
+            .map(|declaration| match declaration {
+                ty::GenericKind::Ty(declaration) => {
+                    db.untern_string(declaration.name).to_string()
+                }
+            })
+            .collect()
+    };
+    assert_eq!(names(def_ids[0]), vec!["T"]);
+    assert_eq!(names(def_ids[1]), vec!["T"]);
+    assert!(names(def_ids[2]).is_empty());
+
+    // Type arguments are part of the declared types.
+    assert_eq!(
+        db.signature(def_ids[2]).pretty(&db),
+        "() -> Box<String>"
+    );
+    assert_eq!(db.signature(def_ids[1]).pretty(&db), "(<generic>) -> <generic>");
+
+    assert!(db.item_errors(def_ids[0]).is_empty());
+    for &def_id in &def_ids[1..] {
+        assert!(db.item_errors(def_id).is_empty());
+        let errors = db.base_type_check(def_id).errors().to_vec();
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+}
+
+#[test]
+fn build_reports_wrong_number_of_type_arguments() {
+    let source = "struct Box<T> {
+  value: T,
+}
+
+def unbox(b: Box<String, String>,) -> Box {
+  b
+}
+";
+    let diagnostics = compile_err(source);
+    let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "expected 1 type arguments, found 2",
+            "expected 1 type arguments, found 0",
+        ]
+    );
+}
+
+#[test]
+fn run_match() {
+    let source = "enum Shape {
+  Circle(i32),
+  Rect { w: i32, h: i32 },
+  Empty,
+}
+
+def area(s: Shape,) -> i32 {
+  match s {
+    Shape::Circle(r) => 3 * r * r,
+    Shape::Rect { w, h: 0 } => 0,
+    Shape::Rect { w, h } => w * h,
+    Shape::Empty => 0,
+  }
+}
+
+def sign(n: i32, flip: bool) -> i32 {
+  match flip {
+    true => 0 - n,
+    false => match n {
+      0 => 0,
+      _ => n,
+    },
+  }
+}
+
+def main() -> i32 {
+  let w = 3
+  let h = 4
+  let a = area(Shape::Rect { w, h }) + area(Shape::Circle(2)) + area(Shape::Empty)
+  let h = 0
+  a + area(Shape::Rect { w, h }) + sign(0 - 18, true) + sign(0, false)
+}
+";
+    let (db, lowered) = compile_ok(source);
+
+    let main = find_main(&db, &lowered).expect("no main");
+    match eval_context(&lowered.context, main) {
+        Value::I32(value) => assert_eq!(value, 42),
+        value => panic!("unexpected value: {:?}", value),
+    }
+
+    let mut rust = RustFile::new();
+    codegen(&mut rust, &lowered.context);
+    let output = rust.render();
+    assert!(output.contains("enum Shape {"), "{}", output);
+    assert!(output.contains("Shape::Circle {_0: 2, }"), "{}", output);
+    assert!(output.contains("Shape::Rect { .. } =>"), "{}", output);
+}
+
+#[test]
+fn run_if_let() {
+    let source = "enum Shape {
+  Circle(i32),
+  Square(i32),
+  Empty,
+}
+
+def size(s: Shape, otherwise: i32) -> i32 {
+  if let Shape::Circle(r) = s {
+    r
+  } else if let Shape::Square(side) = s {
+    side * side
+  } else {
+    otherwise
+  }
+}
+
+def main() -> i32 {
+  let total = size(Shape::Circle(5), 0) + size(Shape::Square(6), 0) + size(Shape::Empty, 0)
+  if let Shape::Empty = Shape::Circle(1) {
+    print(\"unreachable\")
+  }
+  total
+}
+";
+    let (db, lowered) = compile_ok(source);
+
+    let main = find_main(&db, &lowered).expect("no main");
+    match eval_context(&lowered.context, main) {
+        Value::I32(value) => assert_eq!(value, 41),
+        value => panic!("unexpected value: {:?}", value),
+    }
+
+    // A pattern that always matches leaves nothing for the `else`.
+    let source = "def main() -> i32 {\n  if let x = 1 { x } else { 0 }\n}\n";
+    let diagnostics = compile_err(source);
+    assert_eq!(diagnostics[0].message, "unreachable pattern");
+}
+
+#[test]
+fn build_reports_match_errors() {
+    let source = "enum Shape {
+  Circle(i32),
+  Rect { w: i32, h: i32 },
+  Empty,
+}
+
+def area(s: Shape,) -> i32 {
+  match s {
+    Shape::Circle(_) => 3,
+    Shape::Rect { w, h: 0 } => 0,
+    Shape::Empty => 0,
+  }
+}
+
+def rest(s: Shape,) -> i32 {
+  match s {
+    _ => 1,
+    Shape::Empty => 0,
+  }
+}
+
+def square(s: Shape,) -> i32 {
+  match s {
+    Shape::Square => 1,
+    _ => 0,
+  }
+}
+";
+    let diagnostics = compile_err(source);
+    let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "non-exhaustive patterns: `Shape::Rect { w: _, h: _ }` not covered",
+            "unreachable pattern",
+            "no variant named `Square` in `Shape`",
+        ]
+    );
+}
+
+#[test]
+fn run_modules() {
+    let files = [
+        (
+            "src/main.lark",
+            "mod shapes
+mod geometry
+use shapes::Shape
+use shapes::area as shape_area
+use geometry::double
+
+def main() -> i32 {
+  let s = Shape::Square(3)
+  shape_area(s) + crate::geometry::double(2) + double(shapes::answer())
+}
+",
+        ),
+        (
+            "src/shapes.lark",
+            "pub enum Shape {
+  Square(i32),
+  Rect(i32, i32),
+}
+
+pub def area(s: Shape,) -> i32 {
+  match s {
+    Shape::Square(x) => x * x,
+    Shape::Rect(w, h) => w * h,
+  }
+}
+
+pub def answer() -> i32 {
+  secret()
+}
+
+def secret() -> i32 {
+  crate::geometry::helper()
+}
+",
+        ),
+        (
+            "src/geometry/mod.lark",
+            "pub use helper as fourteen
+
+pub def double(x: i32,) -> i32 {
+  x * 2
+}
+
+pub def helper() -> i32 {
+  14
+}
+
+def main() -> i32 {
+  0
+}
+",
+        ),
+    ];
+    let db = LarkDatabase::default();
+    let mut codemap = CodeMap::new();
+    let paths: Vec<_> = files
+        .iter()
+        .map(|&(name, source)| {
+            codemap.add_filemap(FileName::from(name), source.to_string());
+            db.add_file(name, source)
+        })
+        .collect();
+    let path = paths[0];
+
+    let lowered = match compile(&db, &codemap, path) {
+        Ok(lowered) => lowered,
+        Err(diagnostics) => panic!("unexpected errors: {:?}", diagnostics),
+    };
+
+    let main = find_main(&db, &lowered).expect("no main");
+    match eval_context(&lowered.context, main) {
+        Value::I32(value) => assert_eq!(value, 41),
+        value => panic!("unexpected value: {:?}", value),
+    }
+
+    let mut rust = RustFile::new();
+    codegen(&mut rust, &lowered.context);
+    let output = rust.render();
+    assert!(output.contains("enum shapes__Shape {"), "{}", output);
+    assert!(output.contains("fn geometry__double(x: i32) -> i32 {"), "{}", output);
+    assert!(output.contains("fn geometry__main() -> i32 {"), "{}", output);
+}
+
+#[test]
+fn codegen_names_do_not_collide() {
+    let source = "struct Point {
+  x: i32,
+
+  def sum(borrow self) -> i32 {
+    self.x
+  }
+}
+
+def Point_sum(fn: i32, type: i32) -> i32 {
+  fn + type
+}
+
+def main() -> i32 {
+  let x = 40
+  let p = Point { x, }
+  Point_sum(p.sum(), 2)
+}
+";
+    let (db, lowered) = compile_ok(source);
+
+    let main = find_main(&db, &lowered).expect("no main");
+    match eval_context(&lowered.context, main) {
+        Value::I32(value) => assert_eq!(value, 42),
+        value => panic!("unexpected value: {:?}", value),
+    }
+
+    let mut rust = RustFile::new();
+    codegen(&mut rust, &lowered.context);
+    let output = rust.render();
+    assert!(output.contains("fn Point__sum(self_: Point) -> i32 {"), "{}", output);
+    assert!(output.contains("fn Point_sum(r#fn: i32, r#type: i32) -> i32 {"), "{}", output);
+}
+
+#[test]
+fn build_reports_module_errors() {
+    let files = [
+        (
+            "src/main.lark",
+            "mod shapes
+mod missing
+use shapes::secret
+
+def main() -> i32 {
+  shapes::nothing() + shapes() + shapes::area()
+}
+",
+        ),
+        (
+            "src/shapes.lark",
+            "pub def area() -> i32 {
+  1
+}
+
+def secret() -> i32 {
+  2
+}
+",
+        ),
+    ];
+    let db = LarkDatabase::default();
+    let mut codemap = CodeMap::new();
+    let paths: Vec<_> = files
+        .iter()
+        .map(|&(name, source)| {
+            codemap.add_filemap(FileName::from(name), source.to_string());
+            db.add_file(name, source)
+        })
+        .collect();
+    let path = paths[0];
+
+    let diagnostics = compile(&db, &codemap, path).err().unwrap();
+    let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "file not found for module `missing`: expected `src/missing.lark`",
+            "`secret` is private",
+        ]
+    );
+
+    let source = "mod shapes\n\ndef main() -> i32 {\n  shapes::nothing() + shapes()\n}\n";
+    db.update_file(path, source);
+    let mut codemap = CodeMap::new();
+    codemap.add_filemap(FileName::from(files[0].0), source.to_string());
+    codemap.add_filemap(FileName::from(files[1].0), files[1].1.to_string());
+
+    let diagnostics = compile(&db, &codemap, path).err().unwrap();
+    let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "cannot find `nothing` in `shapes`",
+            "expected a value, found module `shapes`",
+        ]
+    );
+}
+
+#[test]
+fn query_system_formats_documents() {
+    use languageserver_types::{Position, Range, TextEdit, Url};
+    use query_system::QuerySystem;
+    use std::sync::mpsc::channel;
+    use task_manager::{Actor, TypeMessage, TypeResponse};
+
+    let (tx, rx) = channel();
+    let mut query_system = QuerySystem::new();
+    query_system.startup(Box::new(move |response| tx.send(response).unwrap()));
+
+    let uri = Url::parse("file:///test.lark").unwrap();
+    let source = "def f(){\n    1\n}\n\ndef g(){\n    2\n}\n";
+    query_system.receive_message(TypeMessage::OpenFile(uri.clone(), source.to_string()));
+    let _ = rx.try_recv();
+
+    let mut edits_for = |range| {
+        query_system.receive_message(TypeMessage::EditsForFormatting(0, uri.clone(), range));
+        match rx.try_recv() {
+            Ok(TypeResponse::Edits(_, edits)) => edits,
+            _ => panic!("expected edits"),
+        }
+    };
+    assert_eq!(
+        edits_for(None),
+        vec![TextEdit::new(
+            Range::new(Position::new(0, 0), Position::new(7, 0)),
+            "def f() {\n  1\n}\n\ndef g() {\n  2\n}\n".to_string(),
+        )]
+    );
+    assert_eq!(
+        edits_for(Some(Range::new(Position::new(5, 0), Position::new(5, 1)))),
+        vec![TextEdit::new(
+            Range::new(Position::new(4, 0), Position::new(6, 1)),
+            "def g() {\n  2\n}".to_string(),
+        )]
+    );
+}